@@ -0,0 +1,240 @@
+//! Derive macro for the [ads_client](https://crates.io/crates/ads_client) crate.
+//!
+//! Use the re-export `ads_client::AdsType` instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, format_ident};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Ident, LitInt, Type};
+
+/// Default alignment of TwinCAT 3 ([InfoSys](https://infosys.beckhoff.com/content/1033/tc3_plc_intro/2529399691.html)).
+const DEFAULT_PACK_MODE : usize = 8;
+
+/// Base type of an ENUM without explicit type (INT).
+const DEFAULT_ENUM_REPR : &str = "i16";
+
+/// Derive `AdsType`, `AdsEncode` and `AdsDecode` for structs and fieldless enums.
+///
+/// Structs are laid out like a TwinCAT `STRUCT`: Each member is aligned to its natural alignment,
+/// limited by the pack mode. The default pack mode is 8 byte.
+///
+/// Attributes on the type:
+/// - `#[ads(pack_mode = n)]` Corresponds to `{attribute 'pack_mode' := 'n'}`. Valid values are 0, 1, 2, 4 and 8.
+/// - `#[ads(size = n)]` Fails to compile if the size of the type differs from `n`.
+///
+/// Enums use the integer type given by `#[repr(..)]`, otherwise `INT` (`i16`).
+#[proc_macro_derive(AdsType, attributes(ads))]
+pub fn derive_ads_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+struct AdsAttributes {
+    pack_mode   : usize,
+    size        : Option<usize>,
+}
+
+fn parse_attributes(input: &DeriveInput) -> syn::Result<AdsAttributes> {
+    let mut attributes = AdsAttributes { pack_mode : DEFAULT_PACK_MODE, size : None };
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("ads")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("pack_mode") {
+                let lit : LitInt = meta.value()?.parse()?;
+                attributes.pack_mode = match lit.base10_parse::<usize>()? {
+                    0 | 1 => 1, // 0 and 1 both disable the alignment
+                    n @ (2 | 4 | 8) => n,
+                    _ => return Err(meta.error("pack_mode must be 0, 1, 2, 4 or 8"))
+                };
+                Ok(())
+            } else if meta.path.is_ident("size") {
+                let lit : LitInt = meta.value()?.parse()?;
+                attributes.size = Some(lit.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported ads attribute, expected pack_mode or size"))
+            }
+        })?;
+    }
+    Ok(attributes)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let attributes = parse_attributes(&input)?;
+
+    let mut tokens = match &input.data {
+        Data::Struct(data) => expand_struct(&input, &data.fields, &attributes)?,
+        Data::Enum(data) => {
+            if attributes.pack_mode != DEFAULT_PACK_MODE {
+                return Err(syn::Error::new_spanned(&input.ident, "pack_mode is not applicable to enums"));
+            }
+            expand_enum(&input, data)?
+        },
+        Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "AdsType can not be derived for unions"))
+    };
+
+    if let Some(size) = attributes.size {
+        if !input.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(&input.generics, "size check is not supported for generic types"));
+        }
+        let ident = &input.ident;
+        let msg = format!("size of {} differs from {} byte", ident, size);
+        tokens.extend(quote! {
+            const _: () = ::std::assert!(<#ident as ::ads_client::AdsType>::SIZE == #size, #msg);
+        });
+    }
+    Ok(tokens)
+}
+
+fn expand_struct(input: &DeriveInput, fields: &Fields, attributes: &AdsAttributes) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let pack_mode = attributes.pack_mode;
+
+    let types : Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let members : Vec<TokenStream2> = fields.iter().enumerate().map(|(idx, field)| {
+        match &field.ident {
+            Some(name) => quote! { #name },
+            None => {
+                let idx = syn::Index::from(idx);
+                quote! { #idx }
+            }
+        }
+    }).collect();
+    let bindings : Vec<Ident> = (0..types.len()).map(|idx| format_ident!("__field{}", idx)).collect();
+
+    // Generic structs require all members to be ADS types
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for ty in &types {
+            where_clause.predicates.push(parse_quote! { #ty: ::ads_client::AdsEncode + ::ads_client::AdsDecode });
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let construct = match fields {
+        Fields::Named(_) => quote! { Self { #(#members: #bindings),* } },
+        Fields::Unnamed(_) => quote! { Self ( #(#bindings),* ) },
+        Fields::Unit => quote! { Self }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::ads_client::AdsType for #ident #ty_generics #where_clause {
+            const SIZE: usize = {
+                let mut offset : usize = 0;
+                #(
+                    offset = ::ads_client::__private::align_up(offset, ::ads_client::__private::align_of(<#types as ::ads_client::AdsType>::ALIGN, #pack_mode));
+                    offset += <#types as ::ads_client::AdsType>::SIZE;
+                )*
+                ::ads_client::__private::align_up(offset, <Self as ::ads_client::AdsType>::ALIGN)
+            };
+
+            const ALIGN: usize = {
+                let mut align : usize = 1;
+                #(
+                    align = ::ads_client::__private::max(align, ::ads_client::__private::align_of(<#types as ::ads_client::AdsType>::ALIGN, #pack_mode));
+                )*
+                align
+            };
+        }
+
+        impl #impl_generics ::ads_client::AdsEncode for #ident #ty_generics #where_clause {
+            fn encode(&self, buf: &mut [u8]) -> ::ads_client::Result<()> {
+                if buf.len() != <Self as ::ads_client::AdsType>::SIZE {
+                    return Err(::ads_client::__private::size_error(<Self as ::ads_client::AdsType>::SIZE, buf.len()));
+                }
+                // Padding bytes
+                buf.fill(0);
+
+                #[allow(unused_mut)]
+                let mut offset : usize = 0;
+                #(
+                    offset = ::ads_client::__private::align_up(offset, ::ads_client::__private::align_of(<#types as ::ads_client::AdsType>::ALIGN, #pack_mode));
+                    ::ads_client::AdsEncode::encode(&self.#members, &mut buf[offset..offset + <#types as ::ads_client::AdsType>::SIZE])?;
+                    offset += <#types as ::ads_client::AdsType>::SIZE;
+                )*
+                let _ = offset;
+                Ok(())
+            }
+        }
+
+        impl #impl_generics ::ads_client::AdsDecode for #ident #ty_generics #where_clause {
+            fn decode(buf: &[u8]) -> ::ads_client::Result<Self> {
+                if buf.len() != <Self as ::ads_client::AdsType>::SIZE {
+                    return Err(::ads_client::__private::size_error(<Self as ::ads_client::AdsType>::SIZE, buf.len()));
+                }
+
+                #[allow(unused_mut)]
+                let mut offset : usize = 0;
+                #(
+                    offset = ::ads_client::__private::align_up(offset, ::ads_client::__private::align_of(<#types as ::ads_client::AdsType>::ALIGN, #pack_mode));
+                    let #bindings = <#types as ::ads_client::AdsDecode>::decode(&buf[offset..offset + <#types as ::ads_client::AdsType>::SIZE])?;
+                    offset += <#types as ::ads_client::AdsType>::SIZE;
+                )*
+                let _ = offset;
+                Ok(#construct)
+            }
+        }
+    })
+}
+
+fn expand_enum(input: &DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "AdsType can not be derived for generic enums"));
+    }
+
+    if let Some(variant) = data.variants.iter().find(|variant| !matches!(variant.fields, Fields::Unit)) {
+        return Err(syn::Error::new_spanned(variant, "AdsType can only be derived for enums without fields"));
+    }
+
+    // Base type of the enum
+    let mut repr = Ident::new(DEFAULT_ENUM_REPR, Span::call_site());
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            match meta.path.get_ident().map(|ident| ident.to_string()).as_deref() {
+                Some("u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64") => {
+                    repr = meta.path.get_ident().cloned().unwrap_or(repr.clone());
+                    Ok(())
+                },
+                Some("C") => Err(meta.error("repr(C) is not supported, use the integer type of the PLC enum only (e.g. #[repr(u16)])")),
+                _ => Err(meta.error("unsupported repr, expected an integer type of at most 64 bit"))
+            }
+        })?;
+    }
+
+    let variants : Vec<&Ident> = data.variants.iter().map(|variant| &variant.ident).collect();
+    let msg = format!("{}: Conversion from {} failed", ident, repr);
+
+    Ok(quote! {
+        impl ::ads_client::AdsType for #ident {
+            const SIZE: usize = <#repr as ::ads_client::AdsType>::SIZE;
+            const ALIGN: usize = <#repr as ::ads_client::AdsType>::ALIGN;
+        }
+
+        impl ::ads_client::AdsEncode for #ident {
+            fn encode(&self, buf: &mut [u8]) -> ::ads_client::Result<()> {
+                let value : #repr = match self {
+                    #( #ident::#variants => #ident::#variants as #repr, )*
+                };
+                ::ads_client::AdsEncode::encode(&value, buf)
+            }
+        }
+
+        impl ::ads_client::AdsDecode for #ident {
+            fn decode(buf: &[u8]) -> ::ads_client::Result<Self> {
+                let value = <#repr as ::ads_client::AdsDecode>::decode(buf)?;
+                match value {
+                    #( x if x == #ident::#variants as #repr => Ok(#ident::#variants), )*
+                    _ => Err(::ads_client::AdsError{ n_error : ::ads_client::AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : ::std::string::String::from(#msg) })
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,23 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use ads_client::{discovery, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+
+    // IPv4 address of the local network interface, e.g. `cargo run --example discovery_async -- 192.168.1.20`
+    let interface = std::env::args().nth(1)
+                        .and_then(| arg | arg.parse::<Ipv4Addr>().ok())
+                        .unwrap_or(Ipv4Addr::UNSPECIFIED);
+
+    match discovery::scan(interface, Duration::from_secs(2)).await {
+        Ok(devices) => {
+            for device in devices {
+                println!("{:<16} {:<20} {:<20} TwinCAT {:<12} {} {}", device.address, device.net_id, device.hostname,
+                            device.twincat_version, device.os_name, device.os_version);
+            }
+        },
+        Err(err) => println!("Error: {}", err)
+    }
+    Ok(())
+}
@@ -145,10 +145,10 @@ impl EtherCATSlave {
         if ec_err_state != EcErrState::Ok || link_state != EcLinkState::Ok {     
             Ok(EtherCATSlave {
                 state : Err(EcSlaveError {
-                    ec_state : ec_state,
-                    ec_err_state : ec_err_state,
-                    link_state : link_state,
-                    link_port : link_port
+                    ec_state,
+                    ec_err_state,
+                    link_state,
+                    link_port
                 })
             })
         } else {
@@ -0,0 +1,29 @@
+use ads_client::{ClientBuilder, Result};
+use ads_client::files::{self, FilePath};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // The file requests are submitted to the TwinCAT system service (port 10000) of the target
+    let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+
+    // Write a file to the boot directory
+    let mut file = ads_client.open_file("ads_client_test.txt", files::WRITE | files::TEXT, FilePath::BootPath).await?;
+    file.write_all(b"Hello from ads_client!").await?;
+    file.close().await?;
+
+    // Read it back
+    let mut file = ads_client.open_file("ads_client_test.txt", files::READ | files::TEXT, FilePath::BootPath).await?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).await?;
+    file.close().await?;
+    println!("Content: {}", content);
+
+    // List the boot directory
+    for entry in ads_client.read_dir("", FilePath::BootPath).await? {
+        println!("{:<40} {:>10} byte {}", entry.name, entry.size, if entry.is_dir() { "<DIR>" } else { "" });
+    }
+
+    ads_client.delete_file("ads_client_test.txt", FilePath::BootPath).await?;
+    Ok(())
+}
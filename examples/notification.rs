@@ -1,4 +1,4 @@
-use ads_client::{ClientBuilder, AdsNotificationAttrib, AdsTransMode};
+use ads_client::{ClientBuilder, AdsNotificationAttrib, AdsTransMode, NotificationSample};
 use std::thread;
 use std::time::Duration;
 use std::sync::{Arc, Mutex};
@@ -25,28 +25,28 @@ fn main() {
 
     // Get handle for  MAIN.n_cnt_a (1/s)
     if let Err(err) = rt.block_on(ads_client.read_write(0xF003, 0, &mut var_hdl_a, symbol_a)){
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_a = u32::from_ne_bytes(var_hdl_a.try_into().unwrap());
+    let var_hdl_a = u32::from_ne_bytes(var_hdl_a);
 
 
     // Get handle for  MAIN.n_cnt_b (10/s)
     if let Err(err) = rt.block_on(ads_client.read_write(0xF003, 0, &mut var_hdl_b, symbol_b)){
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_b = u32::from_ne_bytes(var_hdl_b.try_into().unwrap());
+    let var_hdl_b = u32::from_ne_bytes(var_hdl_b);
 
     // Get handle for  MAIN.n_cnt_c (1000/s)
     if let Err(err) = rt.block_on(ads_client.read_write(0xF003, 0, &mut var_hdl_c, symbol_c)){
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_c = u32::from_ne_bytes(var_hdl_c.try_into().unwrap());
+    let var_hdl_c = u32::from_ne_bytes(var_hdl_c);
 
     if var_hdl_a != 0 && var_hdl_b != 0 && var_hdl_c != 0 {
         println!("Got handles!");
@@ -55,8 +55,6 @@ fn main() {
         println!("Handle n_cnt_c: {}", var_hdl_c);
 
         // Register Device Notification for n_cnt_a
-        let mut not_hdl_a : u32 = 0;
-
         let ads_notification_attrib_a = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange, // trigger notification when value changed
@@ -64,89 +62,106 @@ fn main() {
             cycle_time  : 0  // check for value change each cycle
         };
 
-        match rt.block_on(ads_client.add_device_notification(   0xF005,
-                                                                var_hdl_a,
-                                                                &ads_notification_attrib_a,
-                                                                &mut not_hdl_a,
-                                                                notification_a,
-                                                                Some(&buf_n_cnt_a)))
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_a = Arc::clone(&buf_n_cnt_a);
+
+        let not_hdl_a = match rt.block_on(ads_client.add_device_notification(   0xF005,
+                                                                                var_hdl_a,
+                                                                                &ads_notification_attrib_a,
+                                                                                move |sample| notification_a(sample, &cb_buf_n_cnt_a)))
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_a!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
 
 
         // // Register Device Notification for n_cnt_b
-        let mut not_hdl_b : u32 = 0;
-
         let ads_notification_attrib_b = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange,
             max_delay   : 500, // sumup notifications each 500ms
             cycle_time  : 0 // check for value change each cycle
         };
+
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_b = Arc::clone(&buf_n_cnt_b);
         
-        match rt.block_on(ads_client.add_device_notification(   0xF005,
-                                                                var_hdl_b,
-                                                                &ads_notification_attrib_b,
-                                                                &mut not_hdl_b,
-                                                                notification_b,
-                                                                Some(&buf_n_cnt_b)))
+        let not_hdl_b = match rt.block_on(ads_client.add_device_notification(   0xF005,
+                                                                                var_hdl_b,
+                                                                                &ads_notification_attrib_b,
+                                                                                move |sample| notification_b(sample, &cb_buf_n_cnt_b)))
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_b!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
         // Register Device Notification for n_cnt_c
-        let mut not_hdl_c : u32 = 0;
-
         let ads_notification_attrib_c = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange,
             max_delay   : 100, // sumup notifications each 100ms
             cycle_time  : 0 // check for value change each cycle
         };
+
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_c = Arc::clone(&buf_n_cnt_c);
         
-        match rt.block_on(ads_client.add_device_notification(   0xF005,
-                                                                var_hdl_c,
-                                                                &ads_notification_attrib_c,
-                                                                &mut not_hdl_c,
-                                                                notification_c,
-                                                                Some(&buf_n_cnt_c)))
+        let not_hdl_c = match rt.block_on(ads_client.add_device_notification(   0xF005,
+                                                                                var_hdl_c,
+                                                                                &ads_notification_attrib_c,
+                                                                                move |sample| notification_c(sample, &cb_buf_n_cnt_c)))
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_c!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
 
         thread::sleep(Duration::from_secs(5));
 
-        match rt.block_on(ads_client.delete_device_notification(not_hdl_a)){
-            Ok(_)     => {
-                println!("Notification for n_cnt_a deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_a) = not_hdl_a {
+            match rt.block_on(not_hdl_a.unsubscribe()) {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_a deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
-        match rt.block_on(ads_client.delete_device_notification(not_hdl_b)){
-            Ok(_)     => {
-                println!("Notification for n_cnt_b deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_b) = not_hdl_b {
+            match rt.block_on(not_hdl_b.unsubscribe()) {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_b deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
-        match rt.block_on(ads_client.delete_device_notification(not_hdl_c)){
-            Ok(_)     => {
-                println!("Notification for n_cnt_c deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_c) = not_hdl_c {
+            match rt.block_on(not_hdl_c.unsubscribe()) {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_c deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
         let b_n_cnt_a : Bytes;
@@ -179,53 +194,41 @@ fn main() {
 }
 
 
-fn notification_a(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_a = u16::from_ne_bytes(payload[..].try_into().expect("Failed to parse data"));
+fn notification_a(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_a = u16::from_ne_bytes(sample.data[..].try_into().expect("Failed to parse data"));
     println!("Notification Event!, n_cnt_a: {}", n_cnt_a);
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
 
-fn notification_b(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_b = u16::from_ne_bytes(payload[..].try_into().expect("failed to parse data"));
+fn notification_b(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_b = u16::from_ne_bytes(sample.data[..].try_into().expect("failed to parse data"));
     println!("Notification Event!, n_cnt_b: {}", n_cnt_b);
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
 
-fn notification_c(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_c = u16::from_ne_bytes(payload[..].try_into().expect("failed to parse data"));
+fn notification_c(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_c = u16::from_ne_bytes(sample.data[..].try_into().expect("failed to parse data"));
     if n_cnt_c % 100 == 0 {
         println!("Notification Event!, n_cnt_c: {}", n_cnt_c);
     }
     
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
\ No newline at end of file
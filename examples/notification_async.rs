@@ -1,4 +1,4 @@
-use ads_client::{ClientBuilder, Result, AdsNotificationAttrib, AdsTransMode};
+use ads_client::{ClientBuilder, Result, AdsNotificationAttrib, AdsTransMode, NotificationSample};
 use std::thread;
 use std::time::Duration;
 use std::sync::{Arc, Mutex};
@@ -24,28 +24,28 @@ async fn main() -> Result<()> {
 
     // Get handle for  MAIN.n_cnt_a (1/s)
     if let Err(err) = ads_client.read_write(0xF003, 0, &mut var_hdl_a, symbol_a).await{
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_a = u32::from_ne_bytes(var_hdl_a.try_into().unwrap());
+    let var_hdl_a = u32::from_ne_bytes(var_hdl_a);
 
 
     // Get handle for  MAIN.n_cnt_b (10/s)
     if let Err(err) = ads_client.read_write(0xF003, 0, &mut var_hdl_b, symbol_b).await{
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_b = u32::from_ne_bytes(var_hdl_b.try_into().unwrap());
+    let var_hdl_b = u32::from_ne_bytes(var_hdl_b);
 
     // Get handle for  MAIN.n_cnt_c (1000/s)
     if let Err(err) = ads_client.read_write(0xF003, 0, &mut var_hdl_c, symbol_c).await{
-        eprintln!("Error: {}", err.to_string());
+        eprintln!("Error: {}", err);
         panic!();
     }
 
-    let var_hdl_c = u32::from_ne_bytes(var_hdl_c.try_into().unwrap());
+    let var_hdl_c = u32::from_ne_bytes(var_hdl_c);
 
 
 
@@ -56,8 +56,6 @@ async fn main() -> Result<()> {
         println!("Handle n_cnt_c: {}", var_hdl_c);
 
         // Register Device Notification for n_cnt_a
-        let mut not_hdl_a : u32 = 0;
-
         let ads_notification_attrib_a = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange, // trigger notification when value changed
@@ -65,89 +63,106 @@ async fn main() -> Result<()> {
             cycle_time  : 0  // check for value change each cycle
         };
 
-        match ads_client.add_device_notification(   0xF005,
-                                                    var_hdl_a,
-                                                    &ads_notification_attrib_a,
-                                                    &mut not_hdl_a,
-                                                    notification_a,
-                                                    Some(&buf_n_cnt_a)).await
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_a = Arc::clone(&buf_n_cnt_a);
+
+        let not_hdl_a = match ads_client.add_device_notification(   0xF005,
+                                                                    var_hdl_a,
+                                                                    &ads_notification_attrib_a,
+                                                                    move |sample| notification_a(sample, &cb_buf_n_cnt_a)).await
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_a!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
 
 
         // // Register Device Notification for n_cnt_b
-        let mut not_hdl_b : u32 = 0;
-
         let ads_notification_attrib_b = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange,
             max_delay   : 500, // sumup notifications each 500ms
             cycle_time  : 0 // check for value change each cycle
         };
+
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_b = Arc::clone(&buf_n_cnt_b);
         
-        match ads_client.add_device_notification(   0xF005,
-                                                    var_hdl_b,
-                                                    &ads_notification_attrib_b,
-                                                    &mut not_hdl_b,
-                                                    notification_b,
-                                                    Some(&buf_n_cnt_b)).await
+        let not_hdl_b = match ads_client.add_device_notification(   0xF005,
+                                                                    var_hdl_b,
+                                                                    &ads_notification_attrib_b,
+                                                                    move |sample| notification_b(sample, &cb_buf_n_cnt_b)).await
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_b!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
         // // Register Device Notification for n_cnt_c
-        let mut not_hdl_c : u32 = 0;
-
         let ads_notification_attrib_c = AdsNotificationAttrib {
             cb_length   : 2, // UINT
             trans_mode  : AdsTransMode::OnChange,
             max_delay   : 100, // sumup notifications each 100ms
             cycle_time  : 0 // check for value change each cycle
         };
+
+        // Buffer captured by the callback
+        let cb_buf_n_cnt_c = Arc::clone(&buf_n_cnt_c);
         
-        match ads_client.add_device_notification(   0xF005,
-                                                    var_hdl_c,
-                                                    &ads_notification_attrib_c,
-                                                    &mut not_hdl_c,
-                                                    notification_c,
-                                                    Some(&buf_n_cnt_c)).await
+        let not_hdl_c = match ads_client.add_device_notification(   0xF005,
+                                                                    var_hdl_c,
+                                                                    &ads_notification_attrib_c,
+                                                                    move |sample| notification_c(sample, &cb_buf_n_cnt_c)).await
         {
-            Ok(_)     => {
+            Ok(hdl)   => {
                 println!("Waiting for notifications on n_cnt_c!");
+                Some(hdl)
             },
-            Err(err) => println!("Error: {}", err.to_string())
-        }
+            Err(err) => {
+                println!("Error: {}", err);
+                None
+            }
+        };
 
 
         thread::sleep(Duration::from_secs(5));
 
-        match ads_client.delete_device_notification(not_hdl_a).await {
-            Ok(_)     => {
-                println!("Notification for n_cnt_a deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_a) = not_hdl_a {
+            match not_hdl_a.unsubscribe().await {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_a deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
-        match ads_client.delete_device_notification(not_hdl_b).await {
-            Ok(_)     => {
-                println!("Notification for n_cnt_b deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_b) = not_hdl_b {
+            match not_hdl_b.unsubscribe().await {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_b deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
-        match ads_client.delete_device_notification(not_hdl_c).await {
-            Ok(_)     => {
-                println!("Notification for n_cnt_c deleted.");
-            },
-            Err(err) => println!("Error: {}", err.to_string())
+        if let Some(not_hdl_c) = not_hdl_c {
+            match not_hdl_c.unsubscribe().await {
+                Ok(_)     => {
+                    println!("Notification for n_cnt_c deleted.");
+                },
+                Err(err) => println!("Error: {}", err)
+            }
         }
 
         let b_n_cnt_a : Bytes;
@@ -180,53 +195,41 @@ async fn main() -> Result<()> {
 }
 
 
-fn notification_a(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_a = u16::from_ne_bytes(payload[..].try_into().expect("Failed to parse data"));
+fn notification_a(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_a = u16::from_ne_bytes(sample.data[..].try_into().expect("Failed to parse data"));
     println!("Notification Event!, n_cnt_a: {}", n_cnt_a);
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
 
-fn notification_b(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_b = u16::from_ne_bytes(payload[..].try_into().expect("failed to parse data"));
+fn notification_b(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_b = u16::from_ne_bytes(sample.data[..].try_into().expect("failed to parse data"));
     println!("Notification Event!, n_cnt_b: {}", n_cnt_b);
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
 
-fn notification_c(_handle: u32, _timestamp: u64, payload: Bytes, user_data: Option<Arc<Mutex<BytesMut>>>){
-    let n_cnt_c = u16::from_ne_bytes(payload[..].try_into().expect("failed to parse data"));
+fn notification_c(sample: NotificationSample, user_data: &Mutex<BytesMut>){
+    let n_cnt_c = u16::from_ne_bytes(sample.data[..].try_into().expect("failed to parse data"));
     if n_cnt_c % 100 == 0 {
         println!("Notification Event!, n_cnt_c: {}", n_cnt_c);
     }
     
 
-    // Process userdata if available
-    if user_data.is_some() {
-        let user_data = user_data.unwrap();
-
-        { // LOCK
-            let mut user_data = user_data.lock().expect("Threading error");
-            user_data.clear();
-            user_data.put(&payload[..]);
-        } // UNLOCK
-    }
+    // Store the value in the captured buffer
+    { // LOCK
+        let mut user_data = user_data.lock().expect("Threading error");
+        user_data.clear();
+        user_data.put(&sample.data[..]);
+    } // UNLOCK
 }
\ No newline at end of file
@@ -13,6 +13,6 @@ fn main() {
                 device_info.build,
                 device_info.device_name)
         }
-        Err(err) => println!("Error: {}", err.to_string())
+        Err(err) => println!("Error: {}", err)
     }
 }
\ No newline at end of file
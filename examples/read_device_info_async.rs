@@ -13,7 +13,7 @@ async fn main() -> Result<()> {
                 device_info.build,
                 device_info.device_name)
         }
-        Err(err) => println!("Error: {}", err.to_string())
+        Err(err) => println!("Error: {}", err)
     }
     Ok(())
 }
\ No newline at end of file
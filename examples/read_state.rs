@@ -7,6 +7,6 @@ fn main() {
 
     match rt.block_on(ads_client.read_state()) {
         Ok(state) => println!("State: {:?}", state),
-        Err(err) => println!("Error: {}", err.to_string())
+        Err(err) => println!("Error: {}", err)
     }
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ async fn main() -> Result<()> {
 
     match ads_client.read_state().await {
         Ok(state) => println!("State: {:?}", state),
-        Err(err) => println!("Error: {}", err.to_string())
+        Err(err) => println!("Error: {}", err)
     }
     Ok(())
 }
\ No newline at end of file
@@ -9,10 +9,10 @@ async fn main() -> Result<()> {
     let symbol = b"MAIN.n_cnt_a";
 
     if let Err(err) = ads_client.read_write(0xF003, 0, &mut hdl, symbol).await{
-        println!("Error: {}", err.to_string());
+        println!("Error: {}", err);
     }
 
-    let n_hdl = u32::from_ne_bytes(hdl.try_into().unwrap());
+    let n_hdl = u32::from_ne_bytes(hdl);
 
     if n_hdl != 0 {
         println!("Got handle!");
@@ -24,10 +24,10 @@ async fn main() -> Result<()> {
 
         match read_hdl {
             Ok(_bytes_read)     => {
-                let n_cnt_a = u16::from_ne_bytes(plc_n_cnt_a.try_into().unwrap());
+                let n_cnt_a = u16::from_ne_bytes(plc_n_cnt_a);
                 println!("MAIN.n_cnt_a: {}", n_cnt_a);
             },
-            Err(err) => println!("Read failed: {}", err.to_string())
+            Err(err) => println!("Read failed: {}", err)
         }
     }
     Ok(())
@@ -0,0 +1,20 @@
+use ads_client::{ClientBuilder, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+
+    let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
+
+    match ads_client.system_info().await {
+        Ok(info) => {
+            println!("AmsNetId: {}", info.ams_net_id);
+            println!("Host name: {}", info.hostname.as_deref().unwrap_or("-"));
+            println!("TwinCAT: {}", info.twincat_version);
+            println!("OS: {} {}", info.os_name, info.os_version);
+            println!("Platform: {} ({})", info.platform, info.hardware_model);
+            println!("System ID: {}", info.system_id.as_deref().unwrap_or("-"));
+        },
+        Err(err) => println!("Error: {}", err)
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+use ads_client::{ClientBuilder, Time, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+
+    // Read MAIN.n_cnt_a (UINT)
+    let hdl_n_cnt_a = ads_client.symbol_handle("MAIN.n_cnt_a").await?;
+
+    match ads_client.read_value::<u16>(0xF005, hdl_n_cnt_a).await {
+        Ok(n_cnt_a) => println!("MAIN.n_cnt_a: {}", n_cnt_a),
+        Err(err) => println!("Read failed: {}", err)
+    }
+
+    // Change the preset time of MAIN.ton_a (TIME)
+    let hdl_pt = ads_client.symbol_handle("MAIN.ton_a.PT").await?;
+
+    match ads_client.write_value(0xF005, hdl_pt, &Time::try_from(Duration::from_millis(500))?).await {
+        Ok(_) => println!("MAIN.ton_a.PT successfully written!"),
+        Err(err) => println!("Write failed: {}", err)
+    }
+
+    let pt = ads_client.read_value::<Time>(0xF005, hdl_pt).await?;
+    println!("MAIN.ton_a.PT: {:?}", Duration::from(pt));
+
+    ads_client.release_symbol_handle("MAIN.n_cnt_a").await?;
+    ads_client.release_symbol_handle("MAIN.ton_a.PT").await?;
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+use ads_client::{ClientBuilder, Result, AdsTransMode};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let ads_client =  ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+
+    // Handle of MAIN.n_cnt_c (1000/s) is managed by the subscription, samples are decoded as UINT
+    let mut subscription = ads_client.subscribe_typed::<u16>("MAIN.n_cnt_c", AdsTransMode::ServerCycle, Duration::from_millis(1)).await?;
+
+    // Receive 5000 samples
+    for _ in 0..5000 {
+        let Some(sample) = subscription.recv().await else {
+            break;
+        };
+
+        match sample {
+            Ok(sample) => {
+                if sample.lost > 0 {
+                    println!("{} samples lost", sample.lost);
+                }
+                println!("MAIN.n_cnt_c: {} (timestamp: {})", sample.value, sample.timestamp);
+            },
+            Err(err) => eprintln!("Error: {}", err)
+        }
+    }
+
+    subscription.unsubscribe().await?;
+    Ok(())
+}
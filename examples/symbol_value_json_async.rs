@@ -0,0 +1,23 @@
+use ads_client::{ClientBuilder, AdsValue, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+
+    // Read MAIN.ton_a (TON) without a corresponding Rust type
+    let ton_a = ads_client.read_symbol_value("MAIN.ton_a").await?;
+
+    match serde_json::to_string_pretty(&ton_a) {
+        Ok(json) => println!("MAIN.ton_a: {}", json),
+        Err(err) => println!("Serialization failed: {}", err)
+    }
+
+    // Write MAIN.n_cnt_a (UINT) from JSON
+    let n_cnt_a : AdsValue = serde_json::from_str("100").expect("Valid JSON");
+
+    match ads_client.write_symbol_value("MAIN.n_cnt_a", &n_cnt_a).await {
+        Ok(_) => println!("MAIN.n_cnt_a successfully written!"),
+        Err(err) => println!("Write failed: {}", err)
+    }
+    Ok(())
+}
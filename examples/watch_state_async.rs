@@ -0,0 +1,16 @@
+use ads_client::{ClientBuilder, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+
+    let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
+
+    let mut state = ads_client.watch_state().await?;
+    println!("State: {:?}", *state.borrow());
+
+    // Switch TwinCAT between Run and Config mode to see the changes
+    while state.changed().await.is_ok() {
+        println!("State changed: {:?}", *state.borrow_and_update());
+    }
+    Ok(())
+}
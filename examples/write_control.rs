@@ -1,4 +1,3 @@
-use std::{thread, time::Duration};
 use ads_client::{ClientBuilder, StateInfo, AdsState};
 use tokio::runtime::Runtime;
 
@@ -10,17 +9,19 @@ fn main() {
     let new_state_config = StateInfo {ads_state : AdsState::Reconfig, device_state : 0 };
 
     match rt.block_on(ads_client.write_control(&new_state_config, None)) {
-        Ok(_) => println!("State change to {:?} successfull", new_state_config.ads_state),
-        Err(err) => println!("Error: {}", err.to_string())
+        Ok(_) => println!("State change to {:?} requested", new_state_config.ads_state),
+        Err(err) => println!("Error: {}", err)
     }
 
-    thread::sleep(Duration::from_secs(5));
-
-    // Set target to run mode
-    let new_state_run = StateInfo {ads_state : AdsState::Reset, device_state : 0 };
+    // Wait until the system service reports Config mode
+    match rt.block_on(ads_client.wait_for_state(AdsState::Config, std::time::Duration::from_secs(30))) {
+        Ok(_) => println!("State change to {:?} successfull", AdsState::Config),
+        Err(err) => println!("Error: {}", err)
+    }
 
-    match rt.block_on(ads_client.write_control(&new_state_run, None)) {
-        Ok(_) => println!("State change to {:?} successfull", new_state_run.ads_state),
-        Err(err) => println!("Error: {}", err.to_string())
+    // Set target to run mode and wait until it is reached
+    match rt.block_on(ads_client.set_run_mode()) {
+        Ok(_) => println!("State change to {:?} successfull", AdsState::Run),
+        Err(err) => println!("Error: {}", err)
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,3 @@
-use std::{thread, time::Duration};
 use ads_client::{ClientBuilder, StateInfo, AdsState, Result};
 
 #[tokio::main]
@@ -6,23 +5,25 @@ async fn main() -> Result<()> {
 
     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
 
-    // Set target system to config mode
-    let new_state_config = StateInfo {ads_state : AdsState::Reconfig, device_state : 0 };
-
-    match ads_client.write_control(&new_state_config, None).await {
-        Ok(_) => println!("State change to {:?} successfull", new_state_config.ads_state),
-        Err(err) => println!("Error: {}", err.to_string())
+    // Set target system to config mode and wait until it is reached
+    match ads_client.set_config_mode().await {
+        Ok(_) => println!("State change to {:?} successfull", AdsState::Config),
+        Err(err) => println!("Error: {}", err)
     }
 
-    thread::sleep(Duration::from_secs(5));
-
     // Set target to run mode
     let new_state_run = StateInfo {ads_state : AdsState::Reset, device_state : 0 };
 
     match ads_client.write_control(&new_state_run, None).await {
-        Ok(_) => println!("State change to {:?} successfull", new_state_run.ads_state),
-        Err(err) => println!("Error: {}", err.to_string())
+        Ok(_) => println!("State change to {:?} requested", new_state_run.ads_state),
+        Err(err) => println!("Error: {}", err)
+    }
+
+    // The system service is not reachable while TwinCAT restarts
+    match ads_client.wait_for_state(AdsState::Run, std::time::Duration::from_secs(30)).await {
+        Ok(_) => println!("State change to {:?} successfull", AdsState::Run),
+        Err(err) => println!("Error: {}", err)
     }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -10,10 +10,10 @@ fn main() {
     let symbol = b"MAIN.n_cnt_a";
 
     if let Err(err) = rt.block_on(ads_client.read_write(0xF003, 0, &mut hdl, symbol)){
-        println!("Error: {}", err.to_string());
+        println!("Error: {}", err);
     }
 
-    let n_hdl = u32::from_ne_bytes(hdl.try_into().unwrap());
+    let n_hdl = u32::from_ne_bytes(hdl);
 
     if n_hdl != 0 {
         println!("Got handle!");
@@ -22,7 +22,7 @@ fn main() {
         
         match rt.block_on(ads_client.write(0xF005, n_hdl, &n_cnt_a.to_ne_bytes())){
             Ok(_)     => println!("Variable successfully written!"),
-            Err(err) => println!("Error: {}", err.to_string())
+            Err(err) => println!("Error: {}", err)
         }
     }
 }
\ No newline at end of file
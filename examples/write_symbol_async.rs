@@ -9,10 +9,10 @@ async fn main() -> Result<()> {
     let symbol = b"MAIN.n_cnt_a";
 
     if let Err(err) = ads_client.read_write(0xF003, 0, &mut hdl, symbol).await{
-        println!("Error: {}", err.to_string());
+        println!("Error: {}", err);
     }
 
-    let n_hdl = u32::from_ne_bytes(hdl.try_into().unwrap());
+    let n_hdl = u32::from_ne_bytes(hdl);
 
     if n_hdl != 0 {
         println!("Got handle!");
@@ -21,7 +21,7 @@ async fn main() -> Result<()> {
         
         match ads_client.write(0xF005, n_hdl, &n_cnt_a.to_ne_bytes()).await{
             Ok(_)     => println!("Variable successfully written!"),
-            Err(err) => println!("Error: {}", err.to_string())
+            Err(err) => println!("Error: {}", err)
         }
     }
     Ok(())
@@ -0,0 +1,22 @@
+//! Arbitrary input must not panic, accepted streams must stay within their bounds.
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use ads_client::parse_notification_stream;
+
+fuzz_target!(|data: &[u8]| {
+    let stream = Bytes::copy_from_slice(data);
+
+    if let Ok(stamps) = parse_notification_stream(&stream) {
+        let length = u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize;
+        let n_stamps = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+        assert_eq!(stamps.len(), n_stamps);
+
+        // Headers and data of all samples fit into the length of the stream
+        let used : usize = stamps.iter()
+                            .map(| stamp | 12 + stamp.samples.iter().map(| sample | 8 + sample.data.len()).sum::<usize>())
+                            .sum();
+        assert!(4 + used <= length);
+    }
+});
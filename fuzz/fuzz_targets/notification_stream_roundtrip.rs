@@ -0,0 +1,48 @@
+//! Encoded stamps are parsed to the same stamps, truncated streams are rejected.
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use ads_client::parse_notification_stream;
+
+fuzz_target!(|input: (Vec<(u64, Vec<(u32, Vec<u8>)>)>, Vec<u8>)| {
+    let (stamps, trailing) = input;
+
+    let mut body = Vec::new();
+    body.extend((stamps.len() as u32).to_le_bytes());
+
+    for (timestamp, samples) in &stamps {
+        body.extend(timestamp.to_le_bytes());
+        body.extend((samples.len() as u32).to_le_bytes());
+
+        for (not_hdl, data) in samples {
+            body.extend(not_hdl.to_le_bytes());
+            body.extend((data.len() as u32).to_le_bytes());
+            body.extend(data);
+        }
+    }
+
+    let mut stream = Vec::new();
+    stream.extend((body.len() as u32).to_le_bytes());
+    stream.extend(&body);
+    stream.extend(&trailing); // Not covered by the length
+
+    let parsed = parse_notification_stream(&Bytes::from(stream.clone())).expect("Valid stream rejected");
+
+    assert_eq!(parsed.len(), stamps.len());
+    for (stamp, (timestamp, samples)) in parsed.iter().zip(&stamps) {
+        assert_eq!(stamp.timestamp, *timestamp);
+        assert_eq!(stamp.samples.len(), samples.len());
+
+        for (sample, (not_hdl, data)) in stamp.samples.iter().zip(samples) {
+            assert_eq!(sample.not_hdl, *not_hdl);
+            assert_eq!(&sample.data[..], &data[..]);
+        }
+    }
+
+    // Any truncation of the stream is detected
+    let len = 4 + body.len();
+    for cut in [0, len / 2, len - 1] {
+        assert!(parse_notification_stream(&Bytes::copy_from_slice(&stream[..cut])).is_err());
+    }
+});
@@ -1,7 +1,9 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::{runtime, sync::mpsc};
 use bytes::{Bytes, BytesMut};
-use log::info;
-use crate::{Client, AdsCommand, AdsError, AdsErrorCode, Notification, AdsNotificationAttrib, HEADER_SIZE, LEN_ADD_DEV_NOT, Result, misc::HandleData};
+use log::{info, warn};
+use crate::{Client, NotificationHandle, AdsCommand, AdsError, AdsErrorCode, NotificationSample, NotCallback, NotDelivery, NotificationDelivery, AdsNotificationAttrib, HEADER_SIZE, LEN_ADD_DEV_NOT, CAPACITY_SAMPLES, IDX_GRP_SYM_VAL_BY_HDL, IDX_GRP_SYM_RELEASE_HDL, Result, misc::{HandleData, NotHandle, NotQueue}};
 
 impl Client {
 
@@ -26,7 +28,7 @@ impl Client {
         _add_not_req.freeze()
     }
 
-    fn post_add_dev_not(&self, add_dev_not_response : HandleData, handle: &mut u32, callback : Notification, user_data: Option<&Arc<Mutex<BytesMut>>>) -> Result<()>{
+    fn post_add_dev_not(add_dev_not_response : HandleData) -> Result<u32>{
 
         let payload = add_dev_not_response.payload
                         .ok_or_else(|| AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("Invalid data values.")})?;
@@ -34,21 +36,13 @@ impl Client {
         Client::eval_ams_error(add_dev_not_response.ams_err)?;
         Client::eval_return_code(payload.as_ref())?;
 
+        Ok(u32::from_ne_bytes(payload[4..8].try_into()?))
+    }
 
-        *handle = u32::from_ne_bytes(payload[4..8].try_into()?);
+    /// Submit the AddDeviceNotification request and return the notification handle assigned by the target.
+    pub(crate) async fn request_device_notification(&self, idx_grp: u32, idx_offs: u32, attributes : &AdsNotificationAttrib) -> Result<u32>{
+        attributes.validate()?;
 
-        // Check if registration of device notification was successfull
-        if *handle != 0 {
-            // Register notification handle
-            self.register_not_handle(*handle, callback, user_data);
-        }
-        Ok(())
-    }
-    /// Submit an asynchronous [ADS Add Device Notification](https://infosys.beckhoff.com/content/1033/tc3_ads_intro/115880971.html?id=7388557527878561663) request.
-    /// 
-    /// Checkout the extensive examples [notification](https://github.com/hANSIc99/ads_client/blob/main/examples/notification.rs) 
-    /// and [notification_async](https://github.com/hANSIc99/ads_client/blob/main/examples/notification_async.rs).
-    pub async fn add_device_notification(&self, idx_grp: u32, idx_offs: u32, attributes : &AdsNotificationAttrib, handle: &mut u32, callback : Notification, user_data: Option<&Arc<Mutex<BytesMut>>> ) -> Result<()>{
         // Prepare AddDeviceNotification request
         let invoke_id = self.create_invoke_id();
         let _add_not_req = self.pre_add_dev_not(idx_grp, idx_offs, attributes, invoke_id);
@@ -63,8 +57,158 @@ impl Client {
         // Launch socket future
         let socket_future = self.socket_write(&_add_not_req);
 
-        tokio::try_join!(cmd_man_future, socket_future).and_then( | (add_not_response, _) | {
-            self.post_add_dev_not(add_not_response, handle, callback, user_data)
-        })
+        tokio::try_join!(cmd_man_future, socket_future).and_then( | (add_not_response, _) | Client::post_add_dev_not(add_not_response))
+    }
+
+    /// Spawn a task which passes the queued samples to the callback in order.
+    /// 
+    /// The task terminates as soon as the notification is deleted and the sender dropped.
+    fn spawn_not_worker(callback: NotCallback) -> NotQueue {
+        let (tx, mut rx) = mpsc::channel::<NotificationSample>(CAPACITY_SAMPLES);
+        let lost = Arc::new(AtomicU64::new(0));
+        let worker_lost = Arc::clone(&lost);
+
+        runtime::Handle::current().spawn(async move {
+            while let Some(sample) = rx.recv().await {
+                let lost = worker_lost.swap(0, Ordering::Relaxed);
+                if lost > 0 {
+                    warn!("Callback of notification handle {} too slow - {} samples dropped", sample.handle, lost);
+                }
+                callback(sample);
+            }
+        });
+        NotQueue { tx, lost }
+    }
+
+    /// Register a notification, `symbol` is set if the notification owns the symbol handle `idx_offs`.
+    pub(crate) async fn add_notification(&self, idx_grp: u32, idx_offs: u32, attributes : &AdsNotificationAttrib, callback : NotCallback, delivery: NotDelivery, symbol: Option<String>) -> Result<u32>{
+        let not_hdl = self.request_device_notification(idx_grp, idx_offs, attributes).await?;
+
+        // Check if registration of device notification was successfull
+        if not_hdl == 0 {
+            return Err(AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("No notification handle received")});
+        }
+
+        // Register notification handle
+        Ok(self.register_not_handle(NotHandle {
+            callback,
+            delivery,
+            id        : 0, // Assigned during registration
+            not_hdl,
+            idx_grp,
+            idx_offs,
+            attrib    : *attributes,
+            symbol
+        }))
+    }
+
+    /// Register a notification on a symbol handle which is acquired for the notification and released with it.
+    pub(crate) async fn add_symbol_notification(&self, symbol: &str, attributes : &AdsNotificationAttrib, callback : NotCallback, delivery: NotDelivery) -> Result<NotificationHandle>{
+        let hdl = self.request_symbol_handle(symbol).await?;
+
+        match self.add_notification(IDX_GRP_SYM_VAL_BY_HDL, hdl, attributes, callback, delivery, Some(String::from(symbol))).await {
+            Ok(handle) => Ok(NotificationHandle::new(self.clone(), handle)),
+            Err(err) => {
+                let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &hdl.to_ne_bytes()).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Submit an asynchronous [ADS Add Device Notification](https://infosys.beckhoff.com/content/1033/tc3_ads_intro/115880971.html?id=7388557527878561663) request.
+    /// 
+    /// The notification is deleted when the returned [NotificationHandle] is dropped or 
+    /// [unsubscribed](NotificationHandle::unsubscribe).
+    /// 
+    /// [NotificationHandle::handle] is a handle assigned by the client, not the notification handle
+    /// assigned by the target. Only the client handle is accepted by [Client::delete_device_notification].
+    /// 
+    /// Checkout the extensive examples [notification](https://github.com/hANSIc99/ads_client/blob/main/examples/notification.rs) 
+    /// and [notification_async](https://github.com/hANSIc99/ads_client/blob/main/examples/notification_async.rs).
+    ///
+    /// The callback may capture state, it is invoked according to [ClientBuilder::set_notification_delivery](crate::ClientBuilder::set_notification_delivery).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use ads_client::{ClientBuilder, AdsNotificationAttrib, AdsTransMode, NotificationSample, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let attrib = AdsNotificationAttrib {
+    ///         cb_length   : 2, // UINT
+    ///         trans_mode  : AdsTransMode::OnChange,
+    ///         max_delay   : 0,
+    ///         cycle_time  : 0
+    ///     };
+    ///
+    ///     let hdl = ads_client.symbol_handle("MAIN.n_cnt_a").await?;
+    ///     let counter = Arc::new(AtomicU64::new(0));
+    ///     let cb_counter = Arc::clone(&counter);
+    ///
+    ///     let not_hdl = ads_client.add_device_notification(0xF005, hdl, &attrib, move |sample: NotificationSample| {
+    ///         cb_counter.fetch_add(1, Ordering::Relaxed);
+    ///         println!("MAIN.n_cnt_a: {:?}", &sample.data[..]);
+    ///     }).await?;
+    ///
+    ///     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    ///     not_hdl.unsubscribe().await?;
+    ///
+    ///     println!("Received {} notifications", counter.load(Ordering::Relaxed));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn add_device_notification(&self, idx_grp: u32, idx_offs: u32, attributes : &AdsNotificationAttrib, callback : impl Fn(NotificationSample) + Send + Sync + 'static) -> Result<NotificationHandle>{
+        let callback : NotCallback = Arc::new(callback);
+
+        let delivery = match self.delivery {
+            NotificationDelivery::Concurrent => NotDelivery::Spawn,
+            NotificationDelivery::Ordered => NotDelivery::Queue(Client::spawn_not_worker(Arc::clone(&callback)))
+        };
+
+        let handle = self.add_notification(idx_grp, idx_offs, attributes, callback, delivery, None).await?;
+        Ok(NotificationHandle::new(self.clone(), handle))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::mpsc as std_mpsc;
+
+    fn sample(handle: u32) -> NotificationSample {
+        NotificationSample { handle, timestamp : 0, data : Bytes::new() }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn full_queue_drops_samples() {
+        let (started_tx, started) = std_mpsc::channel();
+        let (release, gate) = std_mpsc::channel::<()>();
+        let (received_tx, received) = std_mpsc::channel();
+        let gate = Mutex::new(gate);
+
+        // The first sample blocks the worker until it is released
+        let queue = Client::spawn_not_worker(Arc::new(move | sample : NotificationSample | {
+            if sample.handle == 0 {
+                started_tx.send(()).unwrap();
+                gate.lock().unwrap().recv().unwrap();
+            }
+            received_tx.send(sample.handle).unwrap();
+        }));
+
+        queue.push(sample(0));
+        tokio::task::spawn_blocking(move || started.recv().unwrap()).await.unwrap();
+
+        for handle in 1..=CAPACITY_SAMPLES as u32 + 2 {
+            queue.push(sample(handle));
+        }
+        assert_eq!(queue.lost.load(Ordering::Relaxed), 2);
+
+        release.send(()).unwrap();
+        let handles = tokio::task::spawn_blocking(move || received.iter().take(CAPACITY_SAMPLES + 1).collect::<Vec<u32>>()).await.unwrap();
+        assert_eq!(handles, (0..=CAPACITY_SAMPLES as u32).collect::<Vec<u32>>());
     }
-}
\ No newline at end of file
+}
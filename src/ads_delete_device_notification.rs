@@ -1,6 +1,6 @@
 use bytes::{Bytes, BytesMut};
-use log::info;
-use crate::{Client, AdsCommand, AdsError, AdsErrorCode, HEADER_SIZE, LEN_DEL_DEV_NOT, Result, misc::HandleData};
+use log::{info, warn};
+use crate::{Client, AdsCommand, AdsError, AdsErrorCode, HEADER_SIZE, LEN_DEL_DEV_NOT, Result, IDX_GRP_SYM_RELEASE_HDL, misc::{HandleData, NotHandle}};
 
 impl Client {
 
@@ -33,14 +33,11 @@ impl Client {
         Ok(())
     }
 
-    /// Submit an asynchronous [ADS Delete Device Notification](https://infosys.beckhoff.com/content/1033/tc3_ads_intro/115881995.html?id=6216061301016726131) request.
-    /// 
-    /// Checkout the extensive examples [notification](https://github.com/hANSIc99/ads_client/blob/main/examples/notification.rs) 
-    /// and [notification_async](https://github.com/hANSIc99/ads_client/blob/main/examples/notification_async.rs).
-    pub async fn delete_device_notification(&self, handle: u32 ) -> Result<()>{
+    /// Submit the DeleteDeviceNotification request for a notification handle assigned by the target.
+    pub(crate) async fn request_delete_device_notification(&self, not_hdl: u32) -> Result<()>{
         // Prepare delete device notification request
         let invoke_id = self.create_invoke_id();
-        let _del_not_req = self.pre_delete_device_notification(handle, invoke_id);
+        let _del_not_req = self.pre_delete_device_notification(not_hdl, invoke_id);
 
         info!("Submit Delete Notification Request: Invoke ID: {}", invoke_id);
 
@@ -57,4 +54,35 @@ impl Client {
             Client::post_delete_device_notification(del_not_response)
         }) 
     }
+
+    /// Delete an unregistered notification on the target and release the symbol handle owned by it.
+    pub(crate) async fn delete_not_handle(&self, hdl: NotHandle) -> Result<()>{
+        let result = self.request_delete_device_notification(hdl.not_hdl).await;
+
+        if let Some(symbol) = hdl.symbol {
+            if let Err(err) = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &hdl.idx_offs.to_ne_bytes()).await {
+                warn!("Failed to release handle {} of symbol {}: {}", hdl.idx_offs, symbol, err);
+            }
+        }
+        result
+    }
+
+    /// Submit an asynchronous [ADS Delete Device Notification](https://infosys.beckhoff.com/content/1033/tc3_ads_intro/115881995.html?id=6216061301016726131) request.
+    /// 
+    /// The callback is removed from the client even if the target rejects the request.
+    /// Usually the notification is deleted through its [NotificationHandle](crate::NotificationHandle),
+    /// this function is intended for handles released with [NotificationHandle::detach](crate::NotificationHandle::detach).
+    /// 
+    /// The handle is the one assigned by the client, not the handle assigned by the target.
+    /// An unknown handle is rejected with `ADSERR_CLIENT_INVALIDPARM` without contacting the target.
+    /// 
+    /// Checkout the extensive examples [notification](https://github.com/hANSIc99/ads_client/blob/main/examples/notification.rs) 
+    /// and [notification_async](https://github.com/hANSIc99/ads_client/blob/main/examples/notification_async.rs).
+    pub async fn delete_device_notification(&self, handle: u32 ) -> Result<()>{
+        // Translate the user handle into the handle assigned by the target
+        match self.unregister_not_handle(handle) {
+            Some(hdl) => self.delete_not_handle(hdl).await,
+            None => Err(AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg : format!("Unknown notification handle {}", handle)})
+        }
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use bytes::{Bytes, BytesMut};
 use log::info;
-use crate::{Client, Result, AdsCommand, AdsError, AdsErrorCode, HEADER_SIZE, LEN_READ_REQ, misc::HandleData};
+use crate::{Client, Result, AdsCommand, AdsError, AdsErrorCode, AdsDecode, HEADER_SIZE, LEN_READ_REQ, misc::HandleData, plc_types::size_error};
 
 impl Client {
 
@@ -35,7 +35,7 @@ impl Client {
 
         // Copy payload to destination argument
         // Payload starts at offset 8
-        let iter_payload = payload[8..].into_iter();
+        let iter_payload = payload[8..].iter();
         let iter_read_data = data.iter_mut();
 
         // Zip payload and destination together
@@ -113,4 +113,33 @@ impl Client {
 
         tokio::try_join!(cmd_man_future, socket_future).and_then(| (rd_response, _) | Client::post_read(rd_response, data))
     }
-}
\ No newline at end of file
+
+    /// Read a value of type `T` (see [AdsDecode]).
+    /// 
+    /// The size of the read request is determined by `T`. 
+    /// An error is returned if the target responds with a different amount of data.
+    /// 
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let hdl = ads_client.symbol_handle("MAIN.n_cnt_a").await?;
+    ///     let n_cnt_a = ads_client.read_value::<u16>(0xF005, hdl).await?;
+    ///     println!("MAIN.n_cnt_a: {}", n_cnt_a);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn read_value<T: AdsDecode>(&self, idx_grp: u32, idx_offs: u32) -> Result<T> {
+        let mut buf = vec![0; T::SIZE];
+        let rd_len = self.read(idx_grp, idx_offs, &mut buf).await? as usize;
+
+        if rd_len != T::SIZE {
+            return Err(size_error(T::SIZE, rd_len));
+        }
+        T::decode(&buf)
+    }
+}
@@ -13,7 +13,7 @@ impl Client {
         Client::eval_ams_error(rd_dinfo_response.ams_err)?;
 
         if payload.len() != 24 {
-            return Err(AdsError{n_error : 0xE, s_msg : String::from("Invalid AMS length") });
+            Err(AdsError{n_error : 0xE, s_msg : String::from("Invalid AMS length") })
         } else {
 
             Client::eval_return_code(&payload.slice(0..4))?;
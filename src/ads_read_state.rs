@@ -11,7 +11,7 @@ impl Client {
         Client::eval_ams_error(rs_response.ams_err)?;
 
          if payload.len() != 8 {
-            return Err(AdsError{n_error : AdsErrorCode::ERR_INVALIDAMSLENGTH.into(), s_msg : String::from("Invalid AMS length") });
+            Err(AdsError{n_error : AdsErrorCode::ERR_INVALIDAMSLENGTH.into(), s_msg : String::from("Invalid AMS length") })
         } else {
 
             Client::eval_return_code(&payload.slice(0..4))?;
@@ -37,7 +37,7 @@ impl Client{
 
         // Copy payload to destination buffer
         // Payload starts at offset 8
-        let iter_payload = payload[8..].into_iter();
+        let iter_payload = payload[8..].iter();
         let iter_read_data = read_data.iter_mut();
     
         let iter_data = iter_read_data.zip(iter_payload);
@@ -0,0 +1,108 @@
+use log::{info, warn};
+use crate::{Client, Result, AdsError, AdsErrorCode, SymHandle, IDX_GRP_SYM_HDL_BY_NAME, IDX_GRP_SYM_RELEASE_HDL};
+
+impl Client {
+
+    /// Request a new handle for `symbol` from the target, bypassing the cache.
+    pub(crate) async fn request_symbol_handle(&self, symbol: &str) -> Result<u32> {
+        let mut hdl : [u8; 4] = [0; 4];
+        self.read_write(IDX_GRP_SYM_HDL_BY_NAME, 0, &mut hdl, symbol.as_bytes()).await?;
+
+        match u32::from_ne_bytes(hdl) {
+            0 => Err(AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_SYMBOLNOTFOUND.into(), s_msg : format!("No handle received for symbol {}", symbol)}),
+            n_hdl => Ok(n_hdl)
+        }
+    }
+
+    /// Remove the cached handles of methods (`path#method`, see [Client::call_method]) and return them.
+    ///
+    /// In contrast to symbol handles, they are not renewed but acquired again on the next call.
+    pub(crate) fn take_method_handles(&self) -> Vec<u32> {
+        let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+
+        let (methods, symbols) : (Vec<SymHandle>, Vec<SymHandle>) = std::mem::take(&mut *sym_handles)
+                                                                        .into_iter()
+                                                                        .partition(| hdl | hdl.symbol.contains('#'));
+        *sym_handles = symbols;
+        methods.into_iter().map(| hdl | hdl.handle).collect()
+    }
+
+    fn cached_symbol_handle(&self, symbol: &str) -> Option<u32> {
+        let sym_handles = self.sym_handles.lock().expect("Threading Error");
+        sym_handles.iter()
+            .find(| hdl | hdl.symbol == symbol)
+            .map(| hdl | hdl.handle)
+    }
+
+    /// Get a handle for `symbol` (e.g. `MAIN.n_cnt_a`).
+    /// 
+    /// The handle is requested only once from the target and cached afterwards. 
+    /// Use it as index offset with index group 0xF005 (read/write by handle).
+    /// 
+    /// Cached handles are renewed after an online change if the online change detection is 
+    /// enabled ([ClientBuilder::set_online_change_detection](crate::ClientBuilder::set_online_change_detection)). 
+    /// Hence, query the handle with this method instead of storing it.
+    /// 
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851)
+    ///                         .set_online_change_detection(true)
+    ///                         .build().await?;
+    ///
+    ///     let mut plc_n_cnt_a : [u8; 2] = [0; 2];
+    ///     let hdl = ads_client.symbol_handle("MAIN.n_cnt_a").await?;
+    ///     ads_client.read(0xF005, hdl, &mut plc_n_cnt_a).await?;
+    ///
+    ///     // Release the handle if it is no longer required
+    ///     ads_client.release_symbol_handle("MAIN.n_cnt_a").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn symbol_handle(&self, symbol: &str) -> Result<u32> {
+        if let Some(hdl) = self.cached_symbol_handle(symbol) {
+            return Ok(hdl);
+        }
+
+        let hdl = self.request_symbol_handle(symbol).await?;
+        info!("Cache handle {} for symbol {}", hdl, symbol);
+
+        let cached = { // LOCK
+            let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+
+            // Another task might have cached the symbol in the meantime
+            match sym_handles.iter().find(| sym_hdl | sym_hdl.symbol == symbol) {
+                Some(sym_hdl) => Some(sym_hdl.handle),
+                None => {
+                    sym_handles.push(SymHandle { symbol : String::from(symbol), handle : hdl });
+                    None
+                }
+            }
+        }; // UNLOCK
+
+        if let Some(cached) = cached {
+            warn!("Handle for symbol {} was cached concurrently - release redundant handle {}", symbol, hdl);
+            self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &hdl.to_ne_bytes()).await?;
+            return Ok(cached);
+        }
+        Ok(hdl)
+    }
+
+    /// Remove the handle of `symbol` from the cache and release it on the target.
+    pub async fn release_symbol_handle(&self, symbol: &str) -> Result<()> {
+        let hdl = { // LOCK
+            let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+            sym_handles.iter()
+                .position(| hdl | hdl.symbol == symbol)
+                .map(| pos | sym_handles.swap_remove(pos).handle)
+        }; // UNLOCK
+
+        match hdl {
+            Some(hdl) => self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &hdl.to_ne_bytes()).await,
+            None => Err(AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_REMOVEHASH.into(), s_msg : format!("No cached handle for symbol {}", symbol)})
+        }
+    }
+}
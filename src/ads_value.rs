@@ -0,0 +1,608 @@
+//! Dynamically typed PLC values based on the uploaded data type information.
+use crate::{Client, Result, AdsError, AdsErrorCode};
+use crate::plc_types::{size_error, range_error, encode_string, decode_string, encode_wstring, decode_wstring};
+use crate::symbols::*;
+
+/// Nesting limit while walking the data types (protects against cyclic type information).
+const MAX_TYPE_DEPTH : usize = 64;
+
+/// Value of an arbitrary PLC data type.
+///
+/// Use [SymbolTable::decode_value] and [SymbolTable::encode_value] or
+/// [Client::read_symbol_value] and [Client::write_symbol_value] to convert between
+/// PLC memory and `AdsValue`.
+///
+/// With the `serde` feature, `AdsValue` implements `Serialize` and `Deserialize`.
+/// Structs map to JSON objects, arrays to JSON arrays and enums to the name of the value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdsValue {
+    /// `BOOL`, `BIT`
+    Bool(bool),
+    /// `SINT`, `INT`, `DINT`, `LINT`
+    Int(i64),
+    /// `USINT`, `UINT`, `UDINT`, `ULINT`, `BYTE`, `WORD`, `DWORD`, `LWORD`, `TIME`, `DATE`, ...
+    UInt(u64),
+    /// `REAL`, `LREAL`
+    Real(f64),
+    /// `STRING`, `WSTRING`
+    String(String),
+    /// `STRUCT` and function blocks, the members in the order of declaration
+    Struct { fields: Vec<(String, AdsValue)> },
+    /// `ARRAY`, multi-dimensional arrays are nested
+    Array(Vec<AdsValue>),
+    /// Enumeration, `name` is empty if the value is not declared
+    Enum { name: String, value: i64 },
+    /// Data types without a representation (e.g. interfaces)
+    Bytes(Vec<u8>)
+}
+
+impl AdsValue {
+    /// Member `name` of a struct (case-insensitive).
+    pub fn field(&self, name: &str) -> Option<&AdsValue> {
+        match self {
+            AdsValue::Struct { fields } => fields.iter()
+                                            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+                                            .map(|(_, value)| value),
+            _ => None
+        }
+    }
+
+    /// Element `idx` of an array, counted from zero regardless of the lower bound.
+    pub fn get(&self, idx: usize) -> Option<&AdsValue> {
+        match self {
+            AdsValue::Array(items) => items.get(idx),
+            _ => None
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AdsValue::Bool(b) => Some(*b),
+            _ => None
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            AdsValue::Int(n) | AdsValue::Enum { value : n, .. } => Some(*n),
+            AdsValue::UInt(n) => i64::try_from(*n).ok(),
+            _ => None
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            AdsValue::UInt(n) => Some(*n),
+            AdsValue::Int(n) | AdsValue::Enum { value : n, .. } => u64::try_from(*n).ok(),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AdsValue::Real(r) => Some(*r),
+            AdsValue::Int(n) => Some(*n as f64),
+            AdsValue::UInt(n) => Some(*n as f64),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AdsValue::String(s) => Some(s),
+            AdsValue::Enum { name, .. } => Some(name),
+            _ => None
+        }
+    }
+}
+
+fn type_error(s_msg: String) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDPARM.into(), s_msg}
+}
+
+fn check_len(expected: usize, buf: &[u8]) -> Result<()> {
+    if buf.len() != expected {
+        return Err(size_error(expected, buf.len()));
+    }
+    Ok(())
+}
+
+fn check_depth(depth: usize) -> Result<usize> {
+    if depth >= MAX_TYPE_DEPTH {
+        return Err(AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("Data type nesting too deep")});
+    }
+    Ok(depth + 1)
+}
+
+/// Type of the elements of an array data type.
+fn element_type(info: &DataTypeInfo) -> &str {
+    match parse_array_type(&info.type_name) {
+        Some((_, element)) => element,
+        None => &info.type_name
+    }
+}
+
+/// Members which occupy memory of the struct.
+fn is_instance_member(item: &DataTypeInfo) -> bool {
+    item.flags & (FLAG_PROPITEM | FLAG_STATIC) == 0
+}
+
+fn bit_position(item: &DataTypeInfo) -> (usize, u8) {
+    ((item.offs / 8) as usize, 1 << (item.offs % 8))
+}
+
+impl SymbolTable {
+
+    /// Convert the PLC memory `buf` of data type `type_name` (e.g. `ST_Axis` or `ARRAY [1..3] OF INT`) into an [AdsValue].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ads_client::{SymbolTable, AdsValue};
+    ///
+    /// let table = SymbolTable::default();
+    /// let value = table.decode_value("ARRAY [1..2] OF INT", &[1, 0, 0xFE, 0xFF]).unwrap();
+    ///
+    /// assert_eq!(value, AdsValue::Array(vec![AdsValue::Int(1), AdsValue::Int(-2)]));
+    /// assert_eq!(table.encode_value("ARRAY [1..2] OF INT", &value).unwrap(), [1, 0, 0xFE, 0xFF]);
+    /// ```
+    pub fn decode_value(&self, type_name: &str, buf: &[u8]) -> Result<AdsValue> {
+        self.decode_type(type_name, ADST_VOID, buf, 0)
+    }
+
+    /// Convert `value` into the PLC memory of data type `type_name`.
+    ///
+    /// Numbers are converted between the integer and floating point types if the value is in range.
+    /// Enumerations accept the name of the value as [AdsValue::String] as well.
+    pub fn encode_value(&self, type_name: &str, value: &AdsValue) -> Result<Vec<u8>> {
+        let size = match (self.data_type(type_name), elementary_type(type_name), parse_array_type(type_name)) {
+            (Some(info), _, _) => info.size as usize,
+            (None, Some((_, size)), _) => size as usize,
+            (None, None, Some((dims, element))) => {
+                let (_, elem_size) = self.type_size(element)
+                                        .ok_or_else(|| type_error(format!("Unknown data type {}", element)))?;
+                dims.iter().map(|dim| dim.elements as usize).product::<usize>() * elem_size as usize
+            },
+            _ => return Err(type_error(format!("Unknown data type {}", type_name)))
+        };
+
+        let mut buf = vec![0; size];
+        self.encode_type(type_name, ADST_VOID, value, &mut buf, 0)?;
+        Ok(buf)
+    }
+
+    /// Data type identifier and size of `type_name`.
+    pub(crate) fn type_size(&self, type_name: &str) -> Option<(u32, u32)> {
+        match self.data_type(type_name) {
+            Some(info) => Some((info.data_type, info.size)),
+            None => elementary_type(type_name)
+        }
+    }
+
+    pub(crate) fn decode_type(&self, type_name: &str, data_type: u32, buf: &[u8], depth: usize) -> Result<AdsValue> {
+        let depth = check_depth(depth)?;
+
+        if let Some(info) = self.data_type(type_name) {
+            return self.decode_info(info, buf, depth);
+        }
+
+        if let Some((dims, element)) = parse_array_type(type_name) {
+            return self.decode_array(&dims, element, data_type, buf, depth);
+        }
+
+        match elementary_type(type_name) {
+            Some((data_type, size)) => {
+                check_len(size as usize, buf)?;
+                decode_primitive(data_type, buf)
+            },
+            None => decode_primitive(data_type, buf)
+        }
+    }
+
+    fn decode_info(&self, info: &DataTypeInfo, buf: &[u8], depth: usize) -> Result<AdsValue> {
+        check_len(info.size as usize, buf)?;
+
+        if !info.array_infos.is_empty() {
+            return self.decode_array(&info.array_infos, element_type(info), info.data_type, buf, depth);
+        }
+
+        if !info.enum_infos.is_empty() {
+            let value = int_from_le(buf, is_signed(info.data_type))?;
+            let name = info.enum_infos.iter()
+                        .find(|item| item.value == value)
+                        .map(|item| item.name.clone())
+                        .unwrap_or_default();
+            return Ok(AdsValue::Enum { name, value });
+        }
+
+        if !info.sub_items.is_empty() {
+            let mut fields = Vec::with_capacity(info.sub_items.len());
+
+            for item in info.sub_items.iter().filter(|item| is_instance_member(item)) {
+                let value = if item.flags & FLAG_BITVALUES != 0 {
+                    let (byte, mask) = bit_position(item);
+                    AdsValue::Bool(buf.get(byte).ok_or_else(|| size_error(byte + 1, buf.len()))? & mask != 0)
+                } else {
+                    let start = item.offs as usize;
+                    let data = buf.get(start..start + item.size as usize).ok_or_else(|| size_error(start + item.size as usize, buf.len()))?;
+                    self.decode_type(&item.type_name, item.data_type, data, depth)?
+                };
+                fields.push((item.name.clone(), value));
+            }
+            return Ok(AdsValue::Struct { fields });
+        }
+
+        // Alias (e.g. TYPE T_Speed : LREAL; END_TYPE)
+        if !info.type_name.is_empty() && !info.type_name.eq_ignore_ascii_case(&info.name) {
+            return self.decode_type(&info.type_name, info.data_type, buf, depth);
+        }
+
+        decode_primitive(info.data_type, buf)
+    }
+
+    fn decode_array(&self, dims: &[ArrayInfo], element: &str, data_type: u32, buf: &[u8], depth: usize) -> Result<AdsValue> {
+        let Some((dim, inner)) = dims.split_first() else {
+            return self.decode_type(element, data_type, buf, depth);
+        };
+
+        if dim.elements == 0 {
+            return Ok(AdsValue::Array(Vec::new()));
+        }
+
+        let elements = dim.elements as usize;
+        if !buf.len().is_multiple_of(elements) {
+            return Err(size_error(buf.len().next_multiple_of(elements), buf.len()));
+        }
+
+        buf.chunks_exact(buf.len() / elements)
+            .map(|chunk| self.decode_array(inner, element, data_type, chunk, depth))
+            .collect::<Result<Vec<AdsValue>>>()
+            .map(AdsValue::Array)
+    }
+
+    pub(crate) fn encode_type(&self, type_name: &str, data_type: u32, value: &AdsValue, buf: &mut [u8], depth: usize) -> Result<()> {
+        let depth = check_depth(depth)?;
+
+        if let Some(info) = self.data_type(type_name) {
+            return self.encode_info(info, value, buf, depth);
+        }
+
+        if let Some((dims, element)) = parse_array_type(type_name) {
+            return self.encode_array(&dims, element, data_type, value, buf, depth);
+        }
+
+        match elementary_type(type_name) {
+            Some((data_type, size)) => {
+                check_len(size as usize, buf)?;
+                encode_primitive(data_type, value, buf)
+            },
+            None => encode_primitive(data_type, value, buf)
+        }
+    }
+
+    fn encode_info(&self, info: &DataTypeInfo, value: &AdsValue, buf: &mut [u8], depth: usize) -> Result<()> {
+        check_len(info.size as usize, buf)?;
+
+        if !info.array_infos.is_empty() {
+            return self.encode_array(&info.array_infos, element_type(info), info.data_type, value, buf, depth);
+        }
+
+        if !info.enum_infos.is_empty() {
+            let lookup = |name: &str| info.enum_infos.iter()
+                                        .find(|item| item.name.eq_ignore_ascii_case(name))
+                                        .map(|item| item.value)
+                                        .ok_or_else(|| type_error(format!("{} is not a value of {}", name, info.name)));
+
+            let value = match value {
+                AdsValue::Enum { name, .. } if !name.is_empty() => lookup(name)?,
+                AdsValue::Enum { value, .. } => *value,
+                AdsValue::String(name) => lookup(name)?,
+                other => other.as_i64().ok_or_else(|| type_error(format!("Expected a value of {}, got {:?}", info.name, other)))?
+            };
+            return encode_int(value, is_signed(info.data_type), buf);
+        }
+
+        if !info.sub_items.is_empty() {
+            buf.fill(0);
+
+            for item in info.sub_items.iter().filter(|item| is_instance_member(item)) {
+                let field = value.field(&item.name)
+                                .ok_or_else(|| type_error(format!("Member {} of {} missing", item.name, info.name)))?;
+
+                if item.flags & FLAG_BITVALUES != 0 {
+                    let (byte, mask) = bit_position(item);
+                    let bit = field.as_bool().ok_or_else(|| type_error(format!("Expected BOOL for {}, got {:?}", item.name, field)))?;
+                    let data = buf.get_mut(byte).ok_or_else(|| size_error(byte + 1, info.size as usize))?;
+
+                    if bit { *data |= mask } else { *data &= !mask }
+                } else {
+                    let start = item.offs as usize;
+                    let end = start + item.size as usize;
+                    let data = buf.get_mut(start..end).ok_or_else(|| size_error(end, info.size as usize))?;
+                    self.encode_type(&item.type_name, item.data_type, field, data, depth)?;
+                }
+            }
+            return Ok(());
+        }
+
+        // Alias
+        if !info.type_name.is_empty() && !info.type_name.eq_ignore_ascii_case(&info.name) {
+            return self.encode_type(&info.type_name, info.data_type, value, buf, depth);
+        }
+
+        encode_primitive(info.data_type, value, buf)
+    }
+
+    fn encode_array(&self, dims: &[ArrayInfo], element: &str, data_type: u32, value: &AdsValue, buf: &mut [u8], depth: usize) -> Result<()> {
+        let Some((dim, inner)) = dims.split_first() else {
+            return self.encode_type(element, data_type, value, buf, depth);
+        };
+
+        let AdsValue::Array(items) = value else {
+            return Err(type_error(format!("Expected an array, got {:?}", value)));
+        };
+
+        if items.len() != dim.elements as usize {
+            return Err(type_error(format!("Expected {} array elements, got {}", dim.elements, items.len())));
+        }
+
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        if !buf.len().is_multiple_of(items.len()) {
+            return Err(size_error(buf.len().next_multiple_of(items.len()), buf.len()));
+        }
+
+        let elem_size = buf.len() / items.len();
+        for (chunk, item) in buf.chunks_exact_mut(elem_size).zip(items) {
+            self.encode_array(inner, element, data_type, item, chunk, depth)?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_primitive(data_type: u32, buf: &[u8]) -> Result<AdsValue> {
+    Ok(match data_type {
+        ADST_BIT => {
+            check_len(1, buf)?;
+            AdsValue::Bool(buf[0] != 0)
+        },
+        ADST_INT8 | ADST_INT16 | ADST_INT32 | ADST_INT64 => AdsValue::Int(int_from_le(buf, true)?),
+        ADST_UINT8 | ADST_UINT16 | ADST_UINT32 => AdsValue::UInt(int_from_le(buf, false)? as u64),
+        ADST_UINT64 => {
+            check_len(8, buf)?;
+            AdsValue::UInt(u64::from_le_bytes(buf.try_into()?))
+        },
+        ADST_REAL32 => {
+            check_len(4, buf)?;
+            AdsValue::Real(f32::from_le_bytes(buf.try_into()?) as f64)
+        },
+        ADST_REAL64 => {
+            check_len(8, buf)?;
+            AdsValue::Real(f64::from_le_bytes(buf.try_into()?))
+        },
+        ADST_STRING => AdsValue::String(decode_string(buf)),
+        ADST_WSTRING => AdsValue::String(decode_wstring(buf)?),
+        _ => AdsValue::Bytes(buf.to_vec())
+    })
+}
+
+fn encode_int(value: i64, signed: bool, buf: &mut [u8]) -> Result<()> {
+    let in_range = match (buf.len(), signed) {
+        (1, true)   => i8::try_from(value).is_ok(),
+        (1, false)  => u8::try_from(value).is_ok(),
+        (2, true)   => i16::try_from(value).is_ok(),
+        (2, false)  => u16::try_from(value).is_ok(),
+        (4, true)   => i32::try_from(value).is_ok(),
+        (4, false)  => u32::try_from(value).is_ok(),
+        (8, true)   => true,
+        (8, false)  => value >= 0,
+        (len, _)    => return Err(type_error(format!("Integer of {} byte not supported", len)))
+    };
+
+    if !in_range {
+        return Err(range_error("Value exceeds the range of the data type"));
+    }
+
+    let len = buf.len();
+    buf.copy_from_slice(&value.to_le_bytes()[..len]);
+    Ok(())
+}
+
+fn encode_primitive(data_type: u32, value: &AdsValue, buf: &mut [u8]) -> Result<()> {
+    let mismatch = || type_error(format!("Value {:?} does not match data type {}", value, data_type));
+
+    match data_type {
+        ADST_BIT => {
+            check_len(1, buf)?;
+            let b = match value {
+                AdsValue::Bool(b) => *b,
+                other => other.as_u64().filter(|n| *n <= 1).ok_or_else(mismatch)? == 1
+            };
+            buf[0] = u8::from(b);
+        },
+        ADST_INT8 | ADST_INT16 | ADST_INT32 | ADST_INT64 => {
+            encode_int(value.as_i64().ok_or_else(mismatch)?, true, buf)?;
+        },
+        ADST_UINT8 | ADST_UINT16 | ADST_UINT32 => {
+            let n = value.as_u64().ok_or_else(mismatch)?;
+            encode_int(i64::try_from(n).map_err(|_| range_error("Value exceeds the range of the data type"))?, false, buf)?;
+        },
+        ADST_UINT64 => {
+            check_len(8, buf)?;
+            buf.copy_from_slice(&value.as_u64().ok_or_else(mismatch)?.to_le_bytes());
+        },
+        ADST_REAL32 => {
+            check_len(4, buf)?;
+            buf.copy_from_slice(&(value.as_f64().ok_or_else(mismatch)? as f32).to_le_bytes());
+        },
+        ADST_REAL64 => {
+            check_len(8, buf)?;
+            buf.copy_from_slice(&value.as_f64().ok_or_else(mismatch)?.to_le_bytes());
+        },
+        ADST_STRING => encode_string(value.as_str().ok_or_else(mismatch)?, buf)?,
+        ADST_WSTRING => encode_wstring(value.as_str().ok_or_else(mismatch)?, buf)?,
+        _ => match value {
+            AdsValue::Bytes(bytes) => {
+                check_len(buf.len(), bytes)?;
+                buf.copy_from_slice(bytes);
+            },
+            _ => return Err(mismatch())
+        }
+    }
+    Ok(())
+}
+
+impl Client {
+
+    /// Read the symbol `symbol` (e.g. `MAIN.ton_a`) and convert it into an [AdsValue].
+    ///
+    /// The data type is taken from the [SymbolTable] of the target. Members and array elements
+    /// can be addressed as well (see [Client::resolve]).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let ton_a = ads_client.read_symbol_value("MAIN.ton_a").await?;
+    ///     println!("MAIN.ton_a.ET: {:?}", ton_a.field("ET"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn read_symbol_value(&self, symbol: &str) -> Result<AdsValue> {
+        let table = self.symbol_table().await?;
+        let info = table.resolve(symbol)?;
+
+        let mut buf = vec![0; info.size as usize];
+        let rd_len = self.read(info.idx_grp, info.idx_offs, &mut buf).await? as usize;
+        check_len(buf.len(), &buf[..rd_len.min(buf.len())])?;
+
+        table.decode_type(&info.type_name, info.data_type, &buf, 0)
+    }
+
+    /// Convert `value` according to the data type of `symbol` and write it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, AdsValue, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     ads_client.write_symbol_value("MAIN.n_cnt_a", &AdsValue::UInt(42)).await?;
+    ///     ads_client.write_symbol_value("MAIN.ton_a.PT", &AdsValue::UInt(500)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn write_symbol_value(&self, symbol: &str, value: &AdsValue) -> Result<()> {
+        let table = self.symbol_table().await?;
+        let info = table.resolve(symbol)?;
+
+        let mut buf = vec![0; info.size as usize];
+        table.encode_type(&info.type_name, info.data_type, value, &mut buf, 0)?;
+
+        self.write(info.idx_grp, info.idx_offs, &buf).await?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use std::fmt;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::ser::{SerializeMap, SerializeSeq};
+    use serde::de::{Visitor, SeqAccess, MapAccess};
+    use super::AdsValue;
+
+    impl Serialize for AdsValue {
+        fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+            match self {
+                AdsValue::Bool(b)       => serializer.serialize_bool(*b),
+                AdsValue::Int(n)        => serializer.serialize_i64(*n),
+                AdsValue::UInt(n)       => serializer.serialize_u64(*n),
+                AdsValue::Real(r)       => serializer.serialize_f64(*r),
+                AdsValue::String(s)     => serializer.serialize_str(s),
+                AdsValue::Struct { fields } => {
+                    let mut map = serializer.serialize_map(Some(fields.len()))?;
+                    for (name, value) in fields {
+                        map.serialize_entry(name, value)?;
+                    }
+                    map.end()
+                },
+                AdsValue::Array(items) => {
+                    let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                    for item in items {
+                        seq.serialize_element(item)?;
+                    }
+                    seq.end()
+                },
+                // Undeclared values are represented by their number
+                AdsValue::Enum { name, value } if name.is_empty() => serializer.serialize_i64(*value),
+                AdsValue::Enum { name, .. } => serializer.serialize_str(name),
+                AdsValue::Bytes(bytes) => serializer.serialize_bytes(bytes)
+            }
+        }
+    }
+
+    struct AdsValueVisitor;
+
+    impl<'de> Visitor<'de> for AdsValueVisitor {
+        type Value = AdsValue;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a PLC value")
+        }
+
+        fn visit_bool<E>(self, b: bool) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::Bool(b))
+        }
+
+        fn visit_i64<E>(self, n: i64) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::Int(n))
+        }
+
+        fn visit_u64<E>(self, n: u64) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::UInt(n))
+        }
+
+        fn visit_f64<E>(self, r: f64) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::Real(r))
+        }
+
+        fn visit_str<E>(self, s: &str) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::String(String::from(s)))
+        }
+
+        fn visit_bytes<E>(self, bytes: &[u8]) -> std::result::Result<AdsValue, E> {
+            Ok(AdsValue::Bytes(bytes.to_vec()))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<AdsValue, A::Error> {
+            let mut items = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(item) = seq.next_element()? {
+                items.push(item);
+            }
+            Ok(AdsValue::Array(items))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<AdsValue, A::Error> {
+            let mut fields = Vec::with_capacity(map.size_hint().unwrap_or_default());
+            while let Some(field) = map.next_entry()? {
+                fields.push(field);
+            }
+            Ok(AdsValue::Struct { fields })
+        }
+    }
+
+    impl<'de> Deserialize<'de> for AdsValue {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<AdsValue, D::Error> {
+            deserializer.deserialize_any(AdsValueVisitor)
+        }
+    }
+}
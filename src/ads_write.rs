@@ -1,6 +1,6 @@
 use bytes::{Bytes, BytesMut};
 use log::info;
-use crate::{Client, Result, AdsCommand, AdsError, AdsErrorCode, HEADER_SIZE, LEN_W_REQ_MIN, misc::HandleData};
+use crate::{Client, Result, AdsCommand, AdsError, AdsErrorCode, AdsEncode, HEADER_SIZE, LEN_W_REQ_MIN, misc::HandleData};
 
 impl Client {
 
@@ -88,4 +88,26 @@ impl Client {
 
         tokio::try_join!(cmd_man_future, socket_future).and_then(| (w_response, _) | Client::post_write(w_response))
     }
-}
\ No newline at end of file
+
+    /// Write a value of type `T` (see [AdsEncode]).
+    /// 
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, Time, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let hdl = ads_client.symbol_handle("MAIN.ton_a.PT").await?;
+    ///     ads_client.write_value(0xF005, hdl, &Time::try_from(Duration::from_secs(2))?).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn write_value<T: AdsEncode>(&self, idx_grp: u32, idx_offs: u32, value: &T) -> Result<()> {
+        let mut buf = vec![0; T::SIZE];
+        value.encode(&mut buf)?;
+        self.write(idx_grp, idx_offs, &buf).await
+    }
+}
@@ -0,0 +1,289 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use crate::{Client, Result, NotificationHandle, AdsNotificationAttrib, NotDelivery, CAPACITY_SAMPLES};
+use crate::notification_stream::{SampleIter, StreamSample};
+
+/// Internal representation of a callback receiving all samples of a frame.
+pub(crate) type BatchCallback = Arc<dyn Fn(NotificationBatch) + Send + Sync>;
+
+/// Sample of a [NotificationBatch], borrowing its data from the received frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSample<'a> {
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    pub data        : &'a [u8]
+}
+
+/// Time stamp of a [NotificationBatch] and the data of the samples taken at that time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchStamp<'a> {
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    pub samples     : Vec<&'a [u8]>
+}
+
+/// All samples of a notification received with one DeviceNotification frame.
+///
+/// With [AdsTransMode::ServerCycle](crate::AdsTransMode::ServerCycle) and a `max_delay`, the target collects
+/// several stamps with samples before sending them. The batch keeps a reference to the frame, the samples
+/// are decoded on iteration without allocation.
+#[derive(Debug, Clone)]
+pub struct NotificationBatch {
+    handle      : u32,
+    not_hdl     : u32,
+    frame       : Bytes,
+    len         : usize,
+    /// Number of batches dropped before this batch because the subscriber lagged behind
+    pub lost    : u64
+}
+
+impl NotificationBatch {
+    /// `len` is the number of samples of `not_hdl` in the checked `frame`.
+    pub(crate) fn new(handle: u32, not_hdl: u32, frame: Bytes, len: usize) -> Self {
+        NotificationBatch { handle, not_hdl, frame, len, lost : 0 }
+    }
+
+    /// Handle of the notification (see [NotificationHandle::handle]).
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Samples of the notification in the order of the frame (stamp by stamp).
+    pub fn iter(&self) -> impl Iterator<Item = BatchSample<'_>> {
+        self.samples().map(|sample| BatchSample { timestamp : sample.timestamp, data : sample.data })
+    }
+
+    /// Stamps of the frame which contain samples of the notification, with their samples.
+    pub fn stamps(&self) -> impl Iterator<Item = BatchStamp<'_>> {
+        let mut samples = self.samples().peekable();
+
+        std::iter::from_fn(move || {
+            let first = samples.next()?;
+            let mut stamp = BatchStamp { timestamp : first.timestamp, samples : vec![first.data] };
+
+            while let Some(sample) = samples.next_if(|sample| sample.stamp == first.stamp) {
+                stamp.samples.push(sample.data);
+            }
+            Some(stamp)
+        })
+    }
+
+    /// Number of samples in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn samples(&self) -> impl Iterator<Item = StreamSample<'_>> {
+        let not_hdl = self.not_hdl;
+
+        // The frame was checked before the batch was handed over
+        SampleIter::new(&self.frame).into_iter()
+            .flatten()
+            .map_while(Result::ok)
+            .filter(move |sample| sample.not_hdl == not_hdl)
+    }
+}
+
+/// Notifications of a variable as asynchronous [Stream] of [NotificationBatch]es.
+///
+/// Created by [Client::subscribe_batch]. If the subscriber does not keep up, new batches are dropped
+/// and reported by [NotificationBatch::lost]. The notification is deleted when the subscription is dropped.
+#[derive(Debug)]
+pub struct BatchSubscription {
+    guard   : NotificationHandle,
+    rx      : mpsc::Receiver<NotificationBatch>
+}
+
+impl BatchSubscription {
+    /// Handle of the notification (see [NotificationHandle::handle]).
+    pub fn handle(&self) -> u32 {
+        self.guard.handle()
+    }
+
+    /// Wait for the next batch.
+    ///
+    /// Returns `None` if the notification was deleted.
+    pub async fn recv(&mut self) -> Option<NotificationBatch> {
+        self.rx.recv().await
+    }
+
+    /// Delete the notification and wait for the response of the target.
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.guard.unsubscribe().await
+    }
+}
+
+impl Stream for BatchSubscription {
+    type Item = NotificationBatch;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<NotificationBatch>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Client {
+
+    /// Register a notification and receive the samples of each frame as one [NotificationBatch].
+    ///
+    /// Intended for high sample rates, e.g. to record a variable each PLC cycle.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, AdsNotificationAttribBuilder, AdsTransMode, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     // Sample each millisecond, send the samples every 100 ms
+    ///     let attrib = AdsNotificationAttribBuilder::new(2, AdsTransMode::ServerCycle) // UINT
+    ///                     .set_cycle_time(Duration::from_millis(1))
+    ///                     .set_max_delay(Duration::from_millis(100))
+    ///                     .build()?;
+    ///
+    ///     let hdl = ads_client.symbol_handle("MAIN.n_cnt_c").await?;
+    ///     let mut subscription = ads_client.subscribe_batch(0xF005, hdl, &attrib).await?;
+    ///
+    ///     while let Some(batch) = subscription.recv().await {
+    ///         for sample in batch.iter() {
+    ///             println!("{}: {:?}", sample.timestamp, sample.data);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn subscribe_batch(&self, idx_grp: u32, idx_offs: u32, attributes: &AdsNotificationAttrib) -> Result<BatchSubscription> {
+        let (tx, rx) = mpsc::channel::<NotificationBatch>(CAPACITY_SAMPLES);
+        let lost = Arc::new(AtomicU64::new(0));
+
+        // Called by the socket reader, must not block
+        let callback : BatchCallback = Arc::new(move | mut batch : NotificationBatch | {
+            batch.lost = lost.swap(0, Ordering::Relaxed);
+
+            if let Err(mpsc::error::TrySendError::Full(batch)) = tx.try_send(batch) {
+                lost.fetch_add(batch.lost + 1, Ordering::Relaxed);
+            }
+        });
+
+        // Samples are not passed to the regular callback
+        let handle = self.add_notification(idx_grp, idx_offs, attributes, Arc::new(| _ | {}), NotDelivery::Batch(callback), None).await?;
+
+        Ok(BatchSubscription { guard : NotificationHandle::new(self.clone(), handle), rx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AdsCommand, AdsTransMode, NotificationSample, HEADER_SIZE};
+    use tokio::io::DuplexStream;
+    use crate::tests::{test_client, receive, respond};
+
+    /// Notification handle and data
+    type TestSample<'a> = (u32, &'a [u8]);
+
+    /// Notification stream of stamps with their samples.
+    fn stream(stamps: &[(u64, &[TestSample])]) -> Bytes {
+        let mut stream = (stamps.len() as u32).to_le_bytes().to_vec();
+
+        for (timestamp, samples) in stamps {
+            stream.extend(timestamp.to_le_bytes());
+            stream.extend((samples.len() as u32).to_le_bytes());
+
+            for (not_hdl, data) in samples.iter() {
+                stream.extend(not_hdl.to_le_bytes());
+                stream.extend((data.len() as u32).to_le_bytes());
+                stream.extend_from_slice(data);
+            }
+        }
+        [&(stream.len() as u32).to_le_bytes()[..], &stream].concat().into()
+    }
+
+    #[test]
+    fn samples_and_stamps() {
+        let frame = stream(&[
+            (100, &[(1, &[1, 0]), (2, &[9]), (1, &[2, 0])]),
+            (200, &[(2, &[8])]),
+            (300, &[(1, &[3, 0])])
+        ]);
+        let batch = NotificationBatch::new(5, 1, frame, 3);
+
+        assert_eq!(batch.handle(), 5);
+        assert_eq!(batch.len(), 3);
+        assert!(!batch.is_empty());
+
+        assert_eq!(batch.iter().collect::<Vec<_>>(), [
+            BatchSample { timestamp : 100, data : &[1, 0] },
+            BatchSample { timestamp : 100, data : &[2, 0] },
+            BatchSample { timestamp : 300, data : &[3, 0] }
+        ]);
+
+        // Stamps without samples of the notification are skipped
+        assert_eq!(batch.stamps().collect::<Vec<_>>(), [
+            BatchStamp { timestamp : 100, samples : vec![&[1, 0], &[2, 0]] },
+            BatchStamp { timestamp : 300, samples : vec![&[3, 0]] }
+        ]);
+    }
+
+    #[test]
+    fn equal_timestamps_are_separate_stamps() {
+        let frame = stream(&[(100, &[(1, &[1])]), (100, &[(1, &[2])])]);
+        let batch = NotificationBatch::new(5, 1, frame, 2);
+
+        assert_eq!(batch.stamps().map(|stamp| stamp.samples.len()).collect::<Vec<_>>(), [1, 1]);
+    }
+
+    /// Answer an AddDeviceNotification request with `not_hdl`.
+    async fn respond_notification(remote: &mut DuplexStream, not_hdl: u32) {
+        let (header, _) = receive(remote).await;
+        respond(remote, &header, &[0u32.to_le_bytes(), not_hdl.to_le_bytes()].concat()).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn batch_and_sample_delivery() {
+        let (client, mut remote, _) = test_client();
+        let attrib = AdsNotificationAttrib { cb_length : 2, trans_mode : AdsTransMode::OnChange, max_delay : 0, cycle_time : 0 };
+
+        let (batches, _) = tokio::join!(client.subscribe_batch(0x4020, 0, &attrib), respond_notification(&mut remote, 1));
+        let mut batches = batches.unwrap();
+
+        let (samples_tx, mut samples) = mpsc::unbounded_channel();
+        let (not_hdl, _) = tokio::join!(client.add_device_notification(0x4020, 2, &attrib, move | sample : NotificationSample | {
+            let _ = samples_tx.send(sample.data.to_vec());
+        }), respond_notification(&mut remote, 2));
+        let not_hdl = not_hdl.unwrap();
+
+        let mut notification = [0u8; HEADER_SIZE];
+        notification[22..24].copy_from_slice(&(AdsCommand::DeviceNotification as u16).to_le_bytes());
+
+        // The sample of the second notification exceeds the stream, nothing is delivered
+        let mut malformed = stream(&[(100, &[(1, &[1, 0]), (2, &[9, 0])])]).to_vec();
+        let len = malformed.len();
+        malformed[len - 6..len - 2].copy_from_slice(&3u32.to_le_bytes());
+        respond(&mut remote, &notification, &malformed).await;
+
+        respond(&mut remote, &notification, &stream(&[(200, &[(1, &[2, 0]), (2, &[8, 0]), (1, &[3, 0])])])).await;
+
+        let batch = batches.recv().await.unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.iter().map(|sample| sample.data).collect::<Vec<_>>(), [&[2, 0], &[3, 0]]);
+        assert_eq!(samples.recv().await.unwrap(), vec![8, 0]);
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(batches.rx.try_recv().is_err());
+        assert!(samples.try_recv().is_err());
+
+        not_hdl.detach();
+        batches.guard.detach();
+    }
+}
@@ -18,8 +18,8 @@ impl CommandCleaner {
     pub fn new(interval : u64, timeout : u64, handle_register : Arc<Mutex<Vec<Handle>>>) -> CommandCleaner {
         CommandCleaner {
             waker               : None,
-            handle_register     : handle_register,
-            interval            : interval, // seconds
+            handle_register,
+            interval, // seconds
             timeout             : Duration::from_secs(timeout)
         }
     }
@@ -29,6 +29,11 @@ impl Future for CommandCleaner {
     type Output = ();
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()>
     {
+        // The client and its pending commands are gone
+        if Arc::strong_count(&self.handle_register) == 1 {
+            return Poll::Ready(());
+        }
+
         if let Some(waker) = &self.waker {
             let mut waker = waker.lock().unwrap();
 
@@ -40,7 +45,7 @@ impl Future for CommandCleaner {
             self.waker = Some(waker.clone());
         }
 
-        let waker = Arc::clone(&self.waker.as_ref().unwrap());
+        let waker = Arc::clone(self.waker.as_ref().unwrap());
         let interval = self.interval;
 
         thread::spawn(move || {
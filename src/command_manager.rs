@@ -19,9 +19,9 @@ impl CommandManager {
     pub fn new(timeout : u64, invoke_id : u32, handle_register : Arc<Mutex<Vec<Handle>>>) -> CommandManager {
         CommandManager {
             now             : Instant::now(),
-            timeout         : timeout,
-            invoke_id       : invoke_id,
-            handle_register : handle_register
+            timeout,
+            invoke_id,
+            handle_register
         }
     }
 }
@@ -49,11 +49,18 @@ impl Future for CommandManager {
                 Some(position) => {
                     let hdl = handles.swap_remove(position);
                     trace!("[3] Handle found - processed after {:?} - AdsCmd: {:?} InvokeId: {}", (Instant::now() - hdl.timestamp), hdl.cmd_type, hdl.invoke_id);
-                    return Poll::Ready(Ok(hdl.data))
+
+                    // Commands pending when the connection is lost are answered without payload
+                    if hdl.data.payload.as_ref().is_some_and(| payload | payload.is_empty()) {
+                        warn!("Connection lost - invoke ID: {}", self.invoke_id);
+                        Poll::Ready(Err(AdsError{n_error : hdl.data.ams_err, s_msg : String::from("Connection lost before the response was received.")}))
+                    } else {
+                        Poll::Ready(Ok(hdl.data))
+                    }
                 },
                 None => {
                     cx.waker().wake_by_ref();
-                    return Poll::Pending
+                    Poll::Pending
                 }
             }
         }
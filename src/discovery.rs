@@ -0,0 +1,248 @@
+//! Discovery of ADS devices via the AMS UDP service (port 48899).
+//!
+//! ```rust,no_run
+//! use std::net::Ipv4Addr;
+//! use std::time::Duration;
+//! use ads_client::{discovery, Result};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     // Broadcast on the interface with the address 192.168.1.20
+//!     for device in discovery::scan(Ipv4Addr::new(192, 168, 1, 20), Duration::from_secs(2)).await? {
+//!         println!("{} {} ({}): TwinCAT {}, {} {}", device.address, device.hostname, device.net_id,
+//!                     device.twincat_version, device.os_name, device.os_version);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use log::{debug, info};
+use tokio::net::UdpSocket;
+use tokio::time::{Instant, timeout_at};
+use crate::{Result, TcVersion};
+use crate::udp::{self, UdpMessage, UDP_PORT, LEN_UDP_MAX, SERVICE_IDENTIFY, TAG_TC_VERSION, TAG_OS_VERSION, TAG_COMPUTER_NAME};
+
+const LEN_OS_VERSION_MIN : usize = 20; // Size [4] + Major [4] + Minor [4] + Build [4] + Platform [4]
+
+/// ADS device which answered a discovery request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub address         : IpAddr,
+    pub net_id          : String,
+    pub hostname        : String,
+    pub twincat_version : TcVersion,
+    /// E.g. *Windows NT* or *TC/RTOS*
+    pub os_name         : String,
+    /// Version and service pack, e.g. *10.0.19045*
+    pub os_version      : String
+}
+
+/// TwinCAT version: Major [1] | Minor [1] | Build [2]
+fn parse_tc_version(data: &[u8]) -> TcVersion {
+    match data.get(0..4) {
+        Some(data) => TcVersion { version : data[0].into(), revision : data[1].into(), build : u16::from_le_bytes([data[2], data[3]]) },
+        None => TcVersion::default()
+    }
+}
+
+/// OSVERSIONINFO: Size [4] | Major [4] | Minor [4] | Build [4] | Platform [4] | Service pack (UTF-16, ASCII for TC/RTOS)
+fn parse_os_version(data: &[u8]) -> (String, String) {
+    if data.len() < LEN_OS_VERSION_MIN {
+        return (String::new(), String::new());
+    }
+
+    let field = | idx: usize | u32::from_le_bytes([data[idx], data[idx + 1], data[idx + 2], data[idx + 3]]);
+    let (major, minor, build, platform) = (field(4), field(8), field(12), field(16));
+
+    let os_name = match platform {
+        1 => "TC/RTOS",
+        2 => "Windows NT",
+        3 => "Windows CE",
+        _ => "Unknown"
+    };
+
+    let service_pack : String = match platform {
+        1 => data[LEN_OS_VERSION_MIN..].iter().take_while(| byte | **byte != 0).map(| byte | char::from(*byte)).collect(),
+        _ => char::decode_utf16(data[LEN_OS_VERSION_MIN..].chunks_exact(2).map(| ch | u16::from_le_bytes([ch[0], ch[1]])).take_while(| ch | *ch != 0))
+                .map(| ch | ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+    };
+
+    let mut os_version = format!("{}.{}.{}", major, minor, build);
+    if !service_pack.is_empty() {
+        os_version = format!("{} {}", os_version, service_pack);
+    }
+    (String::from(os_name), os_version)
+}
+
+fn parse_device(address: IpAddr, reply: &UdpMessage) -> Device {
+    let (os_name, os_version) = parse_os_version(reply.get(TAG_OS_VERSION).unwrap_or_default());
+
+    Device {
+        address,
+        net_id          : udp::format_net_id(&reply.net_id),
+        hostname        : reply.get_str(TAG_COMPUTER_NAME).unwrap_or_default(),
+        twincat_version : parse_tc_version(reply.get(TAG_TC_VERSION).unwrap_or_default()),
+        os_name,
+        os_version
+    }
+}
+
+/// Discover the ADS devices in the network of `interface` by a broadcast.
+///
+/// `interface` is the IPv4 address of the local network interface. All replies received within `timeout` are returned.
+///
+/// The request is sent to the limited broadcast address 255.255.255.255, which is not forwarded by routers. Depending on
+/// the operating system, it may leave on another interface than `interface` if several interfaces are configured. In that
+/// case, pass the subnet-directed broadcast address (e.g. 192.168.1.255) to [scan_targets].
+pub async fn scan(interface: Ipv4Addr, timeout: Duration) -> Result<Vec<Device>> {
+    scan_targets(interface, &[SocketAddr::from((Ipv4Addr::BROADCAST, UDP_PORT))], timeout).await
+}
+
+/// Send the discovery request to each of `targets` (e.g. a range of unicast addresses) and collect the replies.
+///
+/// Devices are reported once, even if they answer several requests.
+///
+/// # Example
+///
+/// A stand-in for the UDP service of a device:
+///
+/// ```rust
+/// use std::net::Ipv4Addr;
+/// use std::time::Duration;
+/// use tokio::net::UdpSocket;
+/// use ads_client::{discovery, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let device = UdpSocket::bind("127.0.0.1:0").await?;
+///     let addr = device.local_addr()?;
+///
+///     tokio::spawn(async move {
+///         let mut buf = [0u8; 2048];
+///         let (len, from) = device.recv_from(&mut buf).await.unwrap();
+///
+///         // Header with the reply flag, the AmsNetId of the device and two items
+///         let mut reply = buf[..len].to_vec();
+///         reply.truncate(24);
+///         reply[8..12].copy_from_slice(&(1u32 | 0x8000_0000).to_le_bytes());
+///         reply[12..18].copy_from_slice(&[5, 80, 201, 232, 1, 1]);
+///         reply[20..24].copy_from_slice(&2u32.to_le_bytes());
+///         reply.extend([5, 0, 4, 0]); // Computer name
+///         reply.extend(b"plc\0");
+///         reply.extend([3, 0, 4, 0]); // TwinCAT version
+///         reply.extend([3, 1, 0xB8, 0x0F]);
+///         device.send_to(&reply, from).await.unwrap();
+///     });
+///
+///     let devices = discovery::scan_targets(Ipv4Addr::LOCALHOST, &[addr], Duration::from_millis(500)).await?;
+///
+///     assert_eq!(devices.len(), 1);
+///     assert_eq!(devices[0].net_id, "5.80.201.232.1.1");
+///     assert_eq!(devices[0].hostname, "plc");
+///     assert_eq!(devices[0].twincat_version.to_string(), "3.1.4024");
+///     Ok(())
+/// }
+/// ```
+pub async fn scan_targets(interface: Ipv4Addr, targets: &[SocketAddr], timeout: Duration) -> Result<Vec<Device>> {
+    let socket = UdpSocket::bind((interface, 0)).await?;
+    socket.set_broadcast(true)?;
+
+    let request = UdpMessage::new(SERVICE_IDENTIFY, [0; 6]);
+    let data = request.encode()?;
+
+    for target in targets {
+        socket.send_to(&data, target).await?;
+    }
+
+    info!("Discovery request sent to {} target(s)", targets.len());
+
+    let deadline = Instant::now() + timeout;
+    let mut devices : Vec<Device> = Vec::new();
+    let mut buf = [0u8; LEN_UDP_MAX];
+
+    while let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        // E.g. ICMP port unreachable of a unicast target, the other targets may still answer
+        let (len, from) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                debug!("Failed to receive a discovery reply: {}", e);
+                continue;
+            }
+        };
+
+        match UdpMessage::parse(&buf[..len]) {
+            Ok(reply) if request.is_reply(&reply) => {
+                let device = parse_device(from.ip(), &reply);
+
+                if !devices.iter().any(| known | known.net_id == device.net_id) {
+                    debug!("Discovered {} ({})", device.net_id, device.address);
+                    devices.push(device);
+                }
+            },
+            _ => debug!("Ignored datagram from {}", from)
+        }
+    }
+
+    Ok(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_version(platform: u32, service_pack: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for field in [0, 10, 0, 19045, platform] {
+            data.extend(u32::to_le_bytes(field));
+        }
+        data.extend_from_slice(service_pack);
+        data
+    }
+
+    #[test]
+    fn tc_version() {
+        assert_eq!(parse_tc_version(&[3, 1, 0xB8, 0x0F]), TcVersion { version : 3, revision : 1, build : 4024 });
+        assert_eq!(parse_tc_version(&[3, 1, 0xB8]), TcVersion::default());
+        assert_eq!(parse_tc_version(&[]), TcVersion::default());
+    }
+
+    #[test]
+    fn os_versions() {
+        let service_pack : Vec<u8> = "SP1\0".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(parse_os_version(&os_version(2, &service_pack)), (String::from("Windows NT"), String::from("10.0.19045 SP1")));
+        assert_eq!(parse_os_version(&os_version(2, &[])), (String::from("Windows NT"), String::from("10.0.19045")));
+
+        // TC/RTOS reports the service pack in ASCII
+        assert_eq!(parse_os_version(&os_version(1, b"TC/BSD\0junk")), (String::from("TC/RTOS"), String::from("10.0.19045 TC/BSD")));
+
+        // Odd length and invalid UTF-16
+        assert_eq!(parse_os_version(&os_version(3, &[b'A', 0, 0x00, 0xD8, 1])).0, "Windows CE");
+        assert_eq!(parse_os_version(&os_version(7, &[])).0, "Unknown");
+
+        assert_eq!(parse_os_version(&os_version(2, &[])[..LEN_OS_VERSION_MIN - 1]), (String::new(), String::new()));
+    }
+
+    #[test]
+    fn device() {
+        let mut reply = UdpMessage::new(SERVICE_IDENTIFY, [5, 80, 201, 232, 1, 1]);
+        reply.add_str(TAG_COMPUTER_NAME, "plc")
+             .add_bytes(TAG_TC_VERSION, &[3, 1, 0xB8, 0x0F])
+             .add_bytes(TAG_OS_VERSION, &os_version(1, b"\0"));
+
+        let device = parse_device(Ipv4Addr::LOCALHOST.into(), &reply);
+        assert_eq!(device, Device {
+            address         : Ipv4Addr::LOCALHOST.into(),
+            net_id          : String::from("5.80.201.232.1.1"),
+            hostname        : String::from("plc"),
+            twincat_version : TcVersion { version : 3, revision : 1, build : 4024 },
+            os_name         : String::from("TC/RTOS"),
+            os_version      : String::from("10.0.19045")
+        });
+
+        // Missing items
+        let device = parse_device(Ipv4Addr::LOCALHOST.into(), &UdpMessage::new(SERVICE_IDENTIFY, [0; 6]));
+        assert_eq!((device.hostname.as_str(), device.twincat_version, device.os_name.as_str()), ("", TcVersion::default(), ""));
+    }
+}
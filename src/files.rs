@@ -0,0 +1,655 @@
+//! File access via the TwinCAT system service (ADS port 10000).
+//!
+//! The requests are submitted to the system service of the target, independent of the port of the [Client](crate::Client):
+//!
+//! ```rust,no_run
+//! use ads_client::{ClientBuilder, Result};
+//! use ads_client::files::{self, FilePath};
+//! use tokio::io::AsyncReadExt;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+//!
+//!     for entry in ads_client.read_dir("", FilePath::BootPath).await? {
+//!         println!("{} ({} byte)", entry.name, entry.size);
+//!     }
+//!
+//!     let mut file = ads_client.open_file("recipe.csv", files::READ | files::TEXT, FilePath::BootPath).await?;
+//!     let mut content = String::new();
+//!     file.read_to_string(&mut content).await?;
+//!     file.close().await?;
+//!     Ok(())
+//! }
+//! ```
+use std::io;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncSeek, ReadBuf};
+use tokio::runtime;
+use log::{info, warn};
+use crate::{Client, Result, AdsError, AdsErrorCode, PORT_SYSTEM_SERVICE};
+
+// Index groups of the system service
+const IDX_GRP_FOPEN     : u32 = 120;
+const IDX_GRP_FCLOSE    : u32 = 121;
+const IDX_GRP_FREAD     : u32 = 122;
+const IDX_GRP_FWRITE    : u32 = 123;
+const IDX_GRP_FSEEK     : u32 = 124;
+const IDX_GRP_FTELL     : u32 = 125;
+const IDX_GRP_FDELETE   : u32 = 131;
+const IDX_GRP_FRENAME   : u32 = 132;
+const IDX_GRP_FFILEFIND : u32 = 133;
+
+/// Maximum amount of data per read or write request
+const MAX_CHUNK         : usize = 0x4000;
+const LEN_FIND_ENTRY    : usize = 324;
+
+// Mode flags for Client::open_file
+/// Open for reading
+pub const READ          : u32 = 0x0001;
+/// Open for writing, an existing file is truncated
+pub const WRITE         : u32 = 0x0002;
+/// Append to the end of the file
+pub const APPEND        : u32 = 0x0004;
+/// Open for reading and writing (in combination with [READ], [WRITE] or [APPEND])
+pub const PLUS          : u32 = 0x0008;
+/// Binary mode
+pub const BINARY        : u32 = 0x0010;
+/// Text mode
+pub const TEXT          : u32 = 0x0020;
+/// Create missing directories of the path
+pub const ENSURE_DIR    : u32 = 0x0040;
+
+/// Attribute of directories (see [DirEntry::attributes]).
+pub const ATTRIBUTE_DIRECTORY : u32 = 0x0010;
+
+/// Base directory of a file name on the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum FilePath {
+    /// The file name is an absolute path
+    Generic     = 1,
+    /// Directory of the boot project
+    BootProject = 2,
+    /// Directory of the retain and persistent data
+    BootData    = 3,
+    /// TwinCAT boot directory (e.g. `C:\TwinCAT\3.1\Boot`)
+    BootPath    = 4,
+    UserPath1   = 11,
+    UserPath2   = 12,
+    UserPath3   = 13,
+    UserPath4   = 14,
+    UserPath5   = 15,
+    UserPath6   = 16,
+    UserPath7   = 17,
+    UserPath8   = 18,
+    UserPath9   = 19
+}
+
+/// Entry of a directory listing (see [Client::read_dir]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name            : String,
+    /// Windows file attributes
+    pub attributes      : u32,
+    /// Size \[byte\]
+    pub size            : u64,
+    /// Time of the last modification as Windows FILETIME (100 ns since 1601-01-01)
+    pub last_write_time : u64
+}
+
+impl DirEntry {
+    pub fn is_dir(&self) -> bool {
+        self.attributes & ATTRIBUTE_DIRECTORY != 0
+    }
+}
+
+/// Null terminated ISO 8859-1 representation of `name`.
+fn file_name(name: &str) -> Result<Vec<u8>> {
+    let mut buf = name.chars()
+                    .map(|c| u8::try_from(u32::from(c)).ok().filter(|c| *c != 0))
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or_else(|| AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDPARM.into(), s_msg : format!("File name {} not representable in ISO 8859-1", name)})?;
+    buf.push(0);
+    Ok(buf)
+}
+
+fn to_io_error(err: AdsError) -> io::Error {
+    let kind = match AdsErrorCode::try_from(err.n_error) {
+        Ok(AdsErrorCode::ADSERR_DEVICE_NOTFOUND)        => io::ErrorKind::NotFound,
+        Ok(AdsErrorCode::ADSERR_DEVICE_INVALIDPARM)     => io::ErrorKind::InvalidInput,
+        Ok(AdsErrorCode::ADSERR_DEVICE_INVALIDACCESS)   => io::ErrorKind::PermissionDenied,
+        Ok(AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT)     => io::ErrorKind::TimedOut,
+        _                                               => io::ErrorKind::Other
+    };
+    io::Error::new(kind, err)
+}
+
+impl Client {
+
+    /// Open the file `name` relative to `path` on the target.
+    ///
+    /// `mode` is a combination of [READ], [WRITE], [APPEND], [PLUS], [BINARY], [TEXT] and [ENSURE_DIR].
+    /// The request is submitted to the system service (port 10000) of the target, as are all operations on the
+    /// returned [AdsFile]. File names are encoded in ISO 8859-1, other characters are rejected.
+    pub async fn open_file(&self, name: &str, mode: u32, path: FilePath) -> Result<AdsFile> {
+        let system_service = self.with_port(PORT_SYSTEM_SERVICE);
+        let mut hdl : [u8; 4] = [0; 4];
+        system_service.read_write(IDX_GRP_FOPEN, ((path as u32) << 16) | mode, &mut hdl, &file_name(name)?).await?;
+
+        let handle = u32::from_le_bytes(hdl);
+        info!("Opened file {} with handle {}", name, handle);
+
+        Ok(AdsFile { client : system_service, handle, pos : 0, state : FileState::Idle, pending : Vec::new(), closed : false })
+    }
+
+    /// Delete the file `name` relative to `path` on the target.
+    ///
+    /// The request is submitted to the system service (port 10000) of the target.
+    pub async fn delete_file(&self, name: &str, path: FilePath) -> Result<()> {
+        self.with_port(PORT_SYSTEM_SERVICE).read_write(IDX_GRP_FDELETE, (path as u32) << 16, &mut [], &file_name(name)?).await?;
+        Ok(())
+    }
+
+    /// Rename the file `from` to `to`, both relative to `path`.
+    ///
+    /// The request is submitted to the system service (port 10000) of the target.
+    pub async fn rename_file(&self, from: &str, to: &str, path: FilePath) -> Result<()> {
+        let mut names = file_name(from)?;
+        names.extend(file_name(to)?);
+
+        self.with_port(PORT_SYSTEM_SERVICE).read_write(IDX_GRP_FRENAME, (path as u32) << 16, &mut [], &names).await?;
+        Ok(())
+    }
+
+    /// List the files and directories in `dir` relative to `path`.
+    ///
+    /// The requests are submitted to the system service (port 10000) of the target.
+    pub async fn read_dir(&self, dir: &str, path: FilePath) -> Result<Vec<DirEntry>> {
+        let system_service = self.with_port(PORT_SYSTEM_SERVICE);
+        let mut entries = Vec::new();
+        let mut buf = [0; LEN_FIND_ENTRY];
+
+        let pattern = match dir.trim_end_matches(['\\', '/']) {
+            ""  => String::from("*.*"),
+            dir => format!("{}\\*.*", dir)
+        };
+        let mut pattern = file_name(&pattern)?;
+
+        // The first request refers to the path, the following ones to the search handle
+        let mut idx_offs = path as u32;
+
+        loop {
+            match system_service.read_write(IDX_GRP_FFILEFIND, idx_offs, &mut buf, &pattern).await {
+                Ok(_) => {
+                    let entry = DirEntry {
+                        name            : crate::plc_types::decode_string(&buf[48..308]),
+                        attributes      : u32::from_le_bytes(buf[4..8].try_into()?),
+                        size            : (u64::from(u32::from_le_bytes(buf[32..36].try_into()?)) << 32) | u64::from(u32::from_le_bytes(buf[36..40].try_into()?)),
+                        last_write_time : u64::from(u32::from_le_bytes(buf[24..28].try_into()?)) | (u64::from(u32::from_le_bytes(buf[28..32].try_into()?)) << 32)
+                    };
+
+                    if entry.name != "." && entry.name != ".." {
+                        entries.push(entry);
+                    }
+                },
+                // No more files
+                Err(err) if err.n_error == u32::from(AdsErrorCode::ADSERR_DEVICE_NOTFOUND) => return Ok(entries),
+                Err(err) => return Err(err)
+            }
+
+            idx_offs = u32::from_le_bytes(buf[0..4].try_into()?);
+            pattern.clear();
+        }
+    }
+
+    async fn file_read(self, handle: u32, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; len];
+        let rd_len = self.read_write(IDX_GRP_FREAD, handle, &mut data, &[]).await.map_err(to_io_error)? as usize;
+        data.truncate(rd_len);
+        Ok(data)
+    }
+
+    async fn file_write(self, handle: u32, data: Vec<u8>) -> io::Result<usize> {
+        self.read_write(IDX_GRP_FWRITE, handle, &mut [], &data).await.map_err(to_io_error)?;
+        Ok(data.len())
+    }
+
+    async fn file_seek(self, handle: u32, pos: io::SeekFrom) -> io::Result<u64> {
+        let (offset, origin) : (i64, u32) = match pos {
+            io::SeekFrom::Start(offset)     => (i64::try_from(offset).unwrap_or(i64::MAX), 0),
+            io::SeekFrom::Current(offset)   => (offset, 1),
+            io::SeekFrom::End(offset)       => (offset, 2)
+        };
+        let offset = i32::try_from(offset).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Seek offset exceeds 32 bit"))?;
+
+        let mut seek = [0; 8];
+        seek[0..4].copy_from_slice(&offset.to_le_bytes());
+        seek[4..8].copy_from_slice(&origin.to_le_bytes());
+        self.read_write(IDX_GRP_FSEEK, handle, &mut [], &seek).await.map_err(to_io_error)?;
+
+        let mut pos = [0; 4];
+        self.read_write(IDX_GRP_FTELL, handle, &mut pos, &[]).await.map_err(to_io_error)?;
+
+        u64::try_from(i32::from_le_bytes(pos)).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid file position"))
+    }
+
+    async fn file_close(&self, handle: u32) -> Result<()> {
+        self.read_write(IDX_GRP_FCLOSE, handle, &mut [], &[]).await?;
+        info!("Closed file handle {}", handle);
+        Ok(())
+    }
+}
+
+type FileFuture<T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send>>;
+
+enum FileState {
+    Idle,
+    Reading(FileFuture<Vec<u8>>),
+    Writing(FileFuture<usize>),
+    Seeking(FileFuture<u64>),
+    Closing(FileFuture<()>)
+}
+
+/// File opened with [Client::open_file].
+///
+/// `AdsFile` implements [AsyncRead], [AsyncWrite] and [AsyncSeek], use it with the extension traits of tokio
+/// (e.g. [AsyncReadExt](tokio::io::AsyncReadExt)). Close the file with [AdsFile::close] or
+/// [shutdown](tokio::io::AsyncWriteExt::shutdown), otherwise it is closed in the background on drop.
+pub struct AdsFile {
+    client  : Client,
+    handle  : u32,
+    pos     : u64,
+    state   : FileState,
+    pending : Vec<u8>, // Data read from the target which did not fit into the buffer of the reader
+    closed  : bool
+}
+
+impl std::fmt::Debug for AdsFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdsFile")
+            .field("handle", &self.handle)
+            .field("pos", &self.pos)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+fn busy_error() -> io::Error {
+    io::Error::other("Another file operation is pending")
+}
+
+fn closed_error() -> io::Error {
+    io::Error::other("File already closed")
+}
+
+impl AdsFile {
+    /// File handle assigned by the target.
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Close the file.
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+        self.client.file_close(self.handle).await
+    }
+}
+
+impl AsyncRead for AdsFile {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.closed {
+            return Poll::Ready(Err(closed_error()));
+        }
+
+        if let FileState::Idle = self.state {
+            if buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+
+            if !self.pending.is_empty() {
+                let len = self.pending.len().min(buf.remaining());
+                buf.put_slice(&self.pending[..len]);
+                self.pending.drain(..len);
+                self.pos += len as u64;
+                return Poll::Ready(Ok(()));
+            }
+
+            let fut = self.client.clone().file_read(self.handle, buf.remaining().min(MAX_CHUNK));
+            self.state = FileState::Reading(Box::pin(fut));
+        }
+
+        let FileState::Reading(fut) = &mut self.state else {
+            return Poll::Ready(Err(busy_error()));
+        };
+
+        let result = std::task::ready!(fut.as_mut().poll(cx));
+        self.state = FileState::Idle;
+
+        let data = result?;
+        // The capacity of `buf` might have changed since the request was submitted
+        let len = data.len().min(buf.remaining());
+        buf.put_slice(&data[..len]);
+        self.pos += len as u64;
+
+        self.pending = data[len..].to_vec();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for AdsFile {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        if self.closed {
+            return Poll::Ready(Err(closed_error()));
+        }
+
+        if let FileState::Idle = self.state {
+            let len = data.len().min(MAX_CHUNK);
+            let (client, handle, data) = (self.client.clone(), self.handle, data[..len].to_vec());
+
+            // Data read ahead is discarded, the write starts at the current position
+            let read_ahead = std::mem::take(&mut self.pending).len() as i64;
+
+            self.state = FileState::Writing(Box::pin(async move {
+                if read_ahead > 0 {
+                    client.clone().file_seek(handle, io::SeekFrom::Current(-read_ahead)).await?;
+                }
+                client.file_write(handle, data).await
+            }));
+        }
+
+        let FileState::Writing(fut) = &mut self.state else {
+            return Poll::Ready(Err(busy_error()));
+        };
+
+        let result = std::task::ready!(fut.as_mut().poll(cx));
+        self.state = FileState::Idle;
+
+        let len = result?;
+        self.pos += len as u64;
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Data is written immediately, only complete a pending write
+        if let FileState::Writing(fut) = &mut self.state {
+            let result = std::task::ready!(fut.as_mut().poll(cx));
+            self.state = FileState::Idle;
+            self.pos += result? as u64;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let FileState::Idle = self.state {
+            if self.closed {
+                return Poll::Ready(Ok(()));
+            }
+            let (client, handle) = (self.client.clone(), self.handle);
+            self.closed = true;
+            self.state = FileState::Closing(Box::pin(async move { client.file_close(handle).await.map_err(to_io_error) }));
+        }
+
+        let FileState::Closing(fut) = &mut self.state else {
+            return Poll::Ready(Err(busy_error()));
+        };
+
+        let result = std::task::ready!(fut.as_mut().poll(cx));
+        self.state = FileState::Idle;
+        Poll::Ready(result)
+    }
+}
+
+impl AsyncSeek for AdsFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        if self.closed {
+            return Err(closed_error());
+        }
+
+        let FileState::Idle = self.state else {
+            return Err(busy_error());
+        };
+
+        // Data read ahead is discarded
+        let read_ahead = std::mem::take(&mut self.pending).len() as i64;
+        let position = match position {
+            io::SeekFrom::Current(offset) => io::SeekFrom::Current(offset - read_ahead),
+            position => position
+        };
+
+        let fut = self.client.clone().file_seek(self.handle, position);
+        self.state = FileState::Seeking(Box::pin(fut));
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let FileState::Seeking(fut) = &mut self.state else {
+            return Poll::Ready(Ok(self.pos));
+        };
+
+        let result = std::task::ready!(fut.as_mut().poll(cx));
+        self.state = FileState::Idle;
+
+        self.pos = result?;
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+impl Drop for AdsFile {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        let (client, handle) = (self.client.clone(), self.handle);
+
+        match runtime::Handle::try_current() {
+            Ok(rt) => {
+                rt.spawn(async move {
+                    if let Err(err) = client.file_close(handle).await {
+                        warn!("Failed to close file handle {}: {}", handle, err);
+                    }
+                });
+            },
+            Err(_) => warn!("File handle {} not closed - no runtime available", handle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, AsyncSeekExt, DuplexStream};
+    use tokio::task::JoinHandle;
+    use crate::tests::{test_client, respond};
+
+    const HANDLE : u32 = 7;
+
+    /// Request to the system service (index group, index offset, write data).
+    type Request = (u32, u32, Vec<u8>);
+
+    /// System service which serves a single file, returns the received requests when the connection is closed.
+    fn file_server(mut remote: DuplexStream, content: Arc<Mutex<Vec<u8>>>) -> JoinHandle<Vec<Request>> {
+        tokio::spawn(async move {
+            let mut requests = Vec::new();
+            let mut pos = 0;
+
+            loop {
+                let mut header = [0; 38];
+                if remote.read_exact(&mut header).await.is_err() {
+                    return requests;
+                }
+                assert_eq!(&header[12..14], &PORT_SYSTEM_SERVICE.to_le_bytes());
+                let mut payload = vec![0; u32::from_le_bytes(header[26..30].try_into().unwrap()) as usize];
+                remote.read_exact(&mut payload).await.unwrap();
+
+                let idx_grp = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+                let idx_offs = u32::from_le_bytes(payload[4..8].try_into().unwrap());
+                let rd_len = u32::from_le_bytes(payload[8..12].try_into().unwrap()) as usize;
+                let data = payload[16..].to_vec();
+
+                let answer = {
+                    let mut content = content.lock().unwrap();
+
+                    match idx_grp {
+                        IDX_GRP_FOPEN   => HANDLE.to_le_bytes().to_vec(),
+                        IDX_GRP_FREAD   => {
+                            let end = (pos + rd_len).min(content.len());
+                            let chunk = content[pos.min(end)..end].to_vec();
+                            pos = end;
+                            chunk
+                        },
+                        IDX_GRP_FWRITE  => {
+                            let end = pos + data.len();
+                            if content.len() < end {
+                                content.resize(end, 0);
+                            }
+                            content[pos..end].copy_from_slice(&data);
+                            pos = end;
+                            Vec::new()
+                        },
+                        IDX_GRP_FSEEK   => {
+                            let offset = i32::from_le_bytes(data[0..4].try_into().unwrap()) as i64;
+                            let origin = match u32::from_le_bytes(data[4..8].try_into().unwrap()) {
+                                0 => 0,
+                                1 => pos as i64,
+                                _ => content.len() as i64
+                            };
+                            pos = (origin + offset) as usize;
+                            Vec::new()
+                        },
+                        IDX_GRP_FTELL   => (pos as u32).to_le_bytes().to_vec(),
+                        _               => Vec::new()
+                    }
+                };
+                requests.push((idx_grp, idx_offs, data));
+
+                let response = [&0u32.to_le_bytes()[..], &(answer.len() as u32).to_le_bytes(), &answer].concat();
+                respond(&mut remote, &header, &response).await;
+            }
+        })
+    }
+
+    fn commands(requests: &[Request]) -> Vec<u32> {
+        requests.iter().map(|(idx_grp, _, _)| *idx_grp).collect()
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name("Boot\\Übung.txt").unwrap(), b"Boot\\\xDCbung.txt\0");
+        assert_eq!(file_name("").unwrap(), b"\0");
+        assert!(file_name("€.txt").is_err());
+        assert!(file_name("a\0b").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn write_seek_read() {
+        let (client, remote, _) = test_client();
+        let content = Arc::new(Mutex::new(Vec::new()));
+        let server = file_server(remote, Arc::clone(&content));
+
+        let mut file = client.open_file("recipe.csv", WRITE | PLUS | BINARY, FilePath::BootPath).await.unwrap();
+        assert_eq!(file.handle(), HANDLE);
+
+        file.write_all(b"hello world").await.unwrap();
+        assert_eq!(file.stream_position().await.unwrap(), 11);
+
+        assert_eq!(file.seek(io::SeekFrom::Start(6)).await.unwrap(), 6);
+        let mut word = [0; 3];
+        file.read_exact(&mut word).await.unwrap();
+        assert_eq!(&word, b"wor");
+
+        // Overwrite at the current position
+        file.write_all(b"LD").await.unwrap();
+        assert_eq!(file.seek(io::SeekFrom::End(-5)).await.unwrap(), 6);
+
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "worLD");
+        file.close().await.unwrap();
+
+        assert_eq!(&*content.lock().unwrap(), b"hello worLD");
+
+        drop(client);
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0], (IDX_GRP_FOPEN, (4 << 16) | WRITE | PLUS | BINARY, b"recipe.csv\0".to_vec()));
+        assert_eq!(requests.last().unwrap(), &(IDX_GRP_FCLOSE, HANDLE, Vec::new()));
+        assert!(requests.iter().skip(1).all(|(_, idx_offs, _)| *idx_offs == HANDLE));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn read_ahead_is_discarded() {
+        let (client, remote, _) = test_client();
+        let content = Arc::new(Mutex::new(b"0123456789".to_vec()));
+        let server = file_server(remote, Arc::clone(&content));
+
+        let mut file = client.open_file("data.bin", READ | PLUS | BINARY, FilePath::Generic).await.unwrap();
+
+        // Shrink the buffer while the read is pending, the surplus is kept
+        let mut buf = [0; 8];
+        let mut read_buf = ReadBuf::new(&mut buf);
+        std::future::poll_fn(|cx| {
+            let poll = Pin::new(&mut file).poll_read(cx, &mut read_buf);
+            if poll.is_pending() && read_buf.filled().is_empty() {
+                read_buf.advance(6);
+            }
+            poll
+        }).await.unwrap();
+        assert_eq!(&read_buf.filled()[6..], b"01");
+
+        // The target is 6 byte ahead, the write starts at the position of the reader
+        file.write_all(b"ab").await.unwrap();
+        assert_eq!(file.stream_position().await.unwrap(), 4);
+
+        let mut rest = Vec::new();
+        file.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"456789");
+        file.close().await.unwrap();
+
+        assert_eq!(&*content.lock().unwrap(), b"01ab456789");
+
+        drop(client);
+        assert_eq!(commands(&server.await.unwrap())[..7], [IDX_GRP_FOPEN, IDX_GRP_FREAD, IDX_GRP_FSEEK, IDX_GRP_FTELL, IDX_GRP_FWRITE, IDX_GRP_FSEEK, IDX_GRP_FTELL]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pending_operations_and_close() {
+        let (client, remote, _) = test_client();
+        let server = file_server(remote, Arc::new(Mutex::new(b"data".to_vec())));
+
+        let mut file = client.open_file("data.bin", READ, FilePath::Generic).await.unwrap();
+
+        Pin::new(&mut file).start_seek(io::SeekFrom::Start(1)).unwrap();
+        assert!(Pin::new(&mut file).start_seek(io::SeekFrom::Start(2)).is_err());
+        assert!(file.write(b"x").await.is_err());
+        assert_eq!(std::future::poll_fn(|cx| Pin::new(&mut file).poll_complete(cx)).await.unwrap(), 1);
+
+        file.shutdown().await.unwrap();
+        assert!(file.read(&mut [0; 4]).await.is_err());
+        assert!(file.write(b"x").await.is_err());
+        file.shutdown().await.unwrap();
+        drop(file);
+
+        drop(client);
+        assert_eq!(commands(&server.await.unwrap()), [IDX_GRP_FOPEN, IDX_GRP_FSEEK, IDX_GRP_FTELL, IDX_GRP_FCLOSE]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn delete_and_rename() {
+        let (client, remote, _) = test_client();
+        let server = file_server(remote, Arc::new(Mutex::new(Vec::new())));
+
+        client.delete_file("old.txt", FilePath::BootData).await.unwrap();
+        client.rename_file("a.txt", "b.txt", FilePath::Generic).await.unwrap();
+        assert!(client.delete_file("Ωmega.txt", FilePath::Generic).await.is_err());
+
+        drop(client);
+        assert_eq!(server.await.unwrap(), [
+            (IDX_GRP_FDELETE, 3 << 16, b"old.txt\0".to_vec()),
+            (IDX_GRP_FRENAME, 1 << 16, b"a.txt\0b.txt\0".to_vec())
+        ]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Conversion of Windows FILETIME time stamps and PLC date types.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::{Result, NotificationSample, Sample, TypedSample, MirrorValue, BatchSample, Date, DateAndTime};
+use crate::plc_types::range_error;
+
+/// Intervals of 100 ns between 1601-01-01 and 1970-01-01.
+const FILETIME_UNIX_EPOCH : u64 = 116_444_736_000_000_000;
+
+/// Convert a Windows FILETIME (100 ns since 1601-01-01 UTC) to [SystemTime].
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use ads_client::filetime_to_system_time;
+///
+/// assert_eq!(filetime_to_system_time(116_444_736_000_000_000), UNIX_EPOCH);
+/// assert_eq!(filetime_to_system_time(116_444_736_010_000_000), UNIX_EPOCH + Duration::from_secs(1));
+/// ```
+pub fn filetime_to_system_time(filetime: u64) -> SystemTime {
+    match filetime.checked_sub(FILETIME_UNIX_EPOCH) {
+        Some(ticks) => UNIX_EPOCH + ticks_to_duration(ticks),
+        None => UNIX_EPOCH - ticks_to_duration(FILETIME_UNIX_EPOCH - filetime)
+    }
+}
+
+/// Convert a [SystemTime] to a Windows FILETIME (100 ns since 1601-01-01 UTC).
+pub fn system_time_to_filetime(time: SystemTime) -> Result<u64> {
+    let ticks = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => u64::try_from(since.as_nanos() / 100).ok().and_then(| ticks | FILETIME_UNIX_EPOCH.checked_add(ticks)),
+        Err(before) => u64::try_from(before.duration().as_nanos() / 100).ok().and_then(| ticks | FILETIME_UNIX_EPOCH.checked_sub(ticks))
+    };
+    ticks.ok_or_else(|| range_error("SystemTime exceeds the range of FILETIME"))
+}
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    Duration::new(ticks / 10_000_000, (ticks % 10_000_000) as u32 * 100)
+}
+
+#[cfg(feature = "chrono")]
+fn filetime_to_chrono(filetime: u64) -> chrono::DateTime<chrono::Utc> {
+    filetime_to_system_time(filetime).into()
+}
+
+#[cfg(feature = "time")]
+fn filetime_to_time(filetime: u64) -> time::OffsetDateTime {
+    filetime_to_system_time(filetime).into()
+}
+
+macro_rules! impl_timestamp {
+    ($([$($gen:tt)*] $t:ty),*) => {
+        $(
+            impl<$($gen)*> $t {
+                /// Time stamp as [SystemTime].
+                pub fn system_time(&self) -> SystemTime {
+                    filetime_to_system_time(self.timestamp)
+                }
+
+                /// Time stamp as [chrono::DateTime] (requires the feature *chrono*).
+                #[cfg(feature = "chrono")]
+                pub fn date_time(&self) -> chrono::DateTime<chrono::Utc> {
+                    filetime_to_chrono(self.timestamp)
+                }
+
+                /// Time stamp as [time::OffsetDateTime] (requires the feature *time*).
+                #[cfg(feature = "time")]
+                pub fn offset_date_time(&self) -> time::OffsetDateTime {
+                    filetime_to_time(self.timestamp)
+                }
+            }
+        )*
+    };
+}
+
+impl_timestamp!([] NotificationSample, [] Sample, [T] TypedSample<T>, [] MirrorValue, ['a] BatchSample<'a>);
+
+impl From<DateAndTime> for SystemTime {
+    fn from(dt: DateAndTime) -> Self {
+        UNIX_EPOCH + Duration::from_secs(u64::from(dt.0))
+    }
+}
+
+impl TryFrom<SystemTime> for DateAndTime {
+    type Error = crate::AdsError;
+
+    /// Sub-second fractions are truncated.
+    fn try_from(time: SystemTime) -> Result<Self> {
+        time.duration_since(UNIX_EPOCH).ok()
+            .and_then(| since | u32::try_from(since.as_secs()).ok())
+            .map(DateAndTime)
+            .ok_or_else(|| range_error("SystemTime exceeds the range of DATE_AND_TIME"))
+    }
+}
+
+impl From<Date> for SystemTime {
+    fn from(date: Date) -> Self {
+        UNIX_EPOCH + Duration::from_secs(u64::from(date.0))
+    }
+}
+
+impl TryFrom<SystemTime> for Date {
+    type Error = crate::AdsError;
+
+    /// The time of day is truncated.
+    fn try_from(time: SystemTime) -> Result<Self> {
+        let dt = DateAndTime::try_from(time).map_err(|_| range_error("SystemTime exceeds the range of DATE"))?;
+        Ok(Date(dt.0 - dt.0 % (24 * 60 * 60)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateAndTime> for chrono::DateTime<chrono::Utc> {
+    fn from(dt: DateAndTime) -> Self {
+        SystemTime::from(dt).into()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Date> for chrono::NaiveDate {
+    fn from(date: Date) -> Self {
+        chrono::DateTime::<chrono::Utc>::from(SystemTime::from(date)).date_naive()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for DateAndTime {
+    type Error = crate::AdsError;
+
+    fn try_from(dt: chrono::DateTime<chrono::Utc>) -> Result<Self> {
+        DateAndTime::try_from(SystemTime::from(dt))
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<DateAndTime> for time::OffsetDateTime {
+    fn from(dt: DateAndTime) -> Self {
+        SystemTime::from(dt).into()
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Date> for time::Date {
+    fn from(date: Date) -> Self {
+        time::OffsetDateTime::from(SystemTime::from(date)).date()
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for DateAndTime {
+    type Error = crate::AdsError;
+
+    fn try_from(dt: time::OffsetDateTime) -> Result<Self> {
+        DateAndTime::try_from(SystemTime::from(dt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-02-29 12:34:56.789 UTC
+    const UNIX_SECS : u64 = 1_709_210_096;
+    const FILETIME  : u64 = FILETIME_UNIX_EPOCH + UNIX_SECS * 10_000_000 + 7_890_000;
+
+    #[test]
+    fn filetime_roundtrip() {
+        let time = filetime_to_system_time(FILETIME);
+        assert_eq!(time, UNIX_EPOCH + Duration::new(UNIX_SECS, 789_000_000));
+        assert_eq!(system_time_to_filetime(time).unwrap(), FILETIME);
+
+        // Before 1970
+        assert_eq!(filetime_to_system_time(0), UNIX_EPOCH - Duration::from_secs(FILETIME_UNIX_EPOCH / 10_000_000));
+        assert_eq!(system_time_to_filetime(filetime_to_system_time(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn filetime_range() {
+        // Fractions below 100 ns are truncated
+        assert_eq!(system_time_to_filetime(UNIX_EPOCH + Duration::from_nanos(199)).unwrap(), FILETIME_UNIX_EPOCH + 1);
+
+        assert!(system_time_to_filetime(filetime_to_system_time(0) - Duration::from_nanos(100)).is_err());
+        assert_eq!(system_time_to_filetime(filetime_to_system_time(u64::MAX)).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn plc_dates() {
+        let time = UNIX_EPOCH + Duration::new(UNIX_SECS, 789_000_000);
+
+        let dt = DateAndTime::try_from(time).unwrap();
+        assert_eq!(dt, DateAndTime(UNIX_SECS as u32));
+        assert_eq!(SystemTime::from(dt), UNIX_EPOCH + Duration::from_secs(UNIX_SECS));
+
+        let date = Date::try_from(time).unwrap();
+        assert_eq!(SystemTime::from(date), UNIX_EPOCH + Duration::from_secs(1_709_164_800)); // 2024-02-29
+
+        assert!(DateAndTime::try_from(UNIX_EPOCH - Duration::from_secs(1)).is_err());
+        assert!(DateAndTime::try_from(UNIX_EPOCH + Duration::from_secs(1 << 32)).is_err());
+        assert!(Date::try_from(UNIX_EPOCH + Duration::from_secs(1 << 32)).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversion() {
+        use chrono::{Datelike, Timelike};
+
+        let dt = filetime_to_chrono(FILETIME);
+        assert_eq!((dt.year(), dt.month(), dt.day(), dt.hour(), dt.minute(), dt.second()), (2024, 2, 29, 12, 34, 56));
+        assert_eq!(dt.timestamp_subsec_millis(), 789);
+
+        let date = chrono::NaiveDate::from(Date(1_709_164_800));
+        assert_eq!(date, chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert_eq!(DateAndTime::try_from(dt).unwrap(), DateAndTime(UNIX_SECS as u32));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_conversion() {
+        let dt = filetime_to_time(FILETIME);
+        assert_eq!((dt.year(), dt.month(), dt.day(), dt.hour(), dt.minute(), dt.second()), (2024, time::Month::February, 29, 12, 34, 56));
+        assert_eq!(dt.millisecond(), 789);
+
+        assert_eq!(time::Date::from(Date(1_709_164_800)), time::Date::from_calendar_date(2024, time::Month::February, 29).unwrap());
+        assert_eq!(DateAndTime::try_from(dt).unwrap(), DateAndTime(UNIX_SECS as u32));
+    }
+}
@@ -17,6 +17,87 @@
 //! - [Client::delete_device_notification]
 //! - [Client::read_device_info]
 //! 
+//! Notifications can be received as asynchronous stream with [Client::subscribe] or [Client::subscribe_symbol] as well,
+//! [Client::subscribe_typed] decodes the samples into a Rust type, [Client::subscribe_batch] hands over all samples of a frame at once. [Client::process_mirror] keeps the latest values
+//! of a set of symbols locally.
+//! 
+//! Time stamps of notifications are Windows FILETIME values, `system_time()` converts them to [SystemTime](std::time::SystemTime)
+//! (see [filetime_to_system_time]). The features `chrono` and `time` provide conversions to the respective crates.
+//! Callbacks receive the samples of a notification in order if [NotificationDelivery::Ordered] is set with
+//! [ClientBuilder::set_notification_delivery].
+//! 
+//! Symbol handles can be cached with [Client::symbol_handle]. If the online change detection is enabled
+//! ([ClientBuilder::set_online_change_detection]), cached handles and the notifications registered on them
+//! are renewed automatically after an online change.
+//! 
+//! A lost connection (e.g. during a restart of TwinCAT) is re-established automatically, afterwards
+//! cached handles are renewed and notifications are registered again (see [ClientEvent]).
+//! 
+//! Values of the IEC 61131-3 elementary data types can be accessed typed with [Client::read_value] 
+//! and [Client::write_value] (see [AdsEncode] and [AdsDecode]).
+//! 
+//! Without a corresponding Rust type, symbols can be accessed with [Client::read_symbol_value] and 
+//! [Client::write_symbol_value]. The value is represented by [AdsValue] based on the data types uploaded 
+//! from the target ([Client::symbol_table]). With the feature `serde`, [AdsValue] can be converted to JSON.
+//! 
+//! Paths to members and array elements (e.g. `MAIN.axes[3].status.bError`) are resolved locally to index group 
+//! and offset with [Client::resolve]. This avoids requesting a handle for each element.
+//! 
+//! Methods of function blocks with the attribute `TcRpcEnable` are called with [Client::call_method] 
+//! or [Client::call_method_value].
+//! 
+//! Files on the target are accessed via the system service (port 10000), see module [files].
+//! 
+//! PLC structs and enums can be mirrored with `#[derive(AdsType)]` (feature `derive`, enabled by default). 
+//! The layout follows the TwinCAT pack modes, the default is an alignment of 8 byte:
+//! 
+//! ```rust
+//! use ads_client::{AdsType, AdsEncode, AdsDecode, PlcString};
+//! 
+//! // TYPE ST_Axis :
+//! // STRUCT
+//! //     bEnabled  : BOOL;
+//! //     fPosition : LREAL;
+//! //     sName     : STRING(10);
+//! //     eState    : E_State;
+//! // END_STRUCT
+//! // END_TYPE
+//! #[derive(AdsType, Debug, PartialEq)]
+//! #[ads(size = 32)]
+//! struct Axis {
+//!     enabled  : bool,
+//!     position : f64,
+//!     name     : PlcString<10>,
+//!     state    : State,
+//! }
+//! 
+//! // {attribute 'pack_mode' := '1'}
+//! #[derive(AdsType)]
+//! #[ads(pack_mode = 1, size = 9)]
+//! struct Packed {
+//!     enabled  : bool,
+//!     position : f64,
+//! }
+//! 
+//! // TYPE E_State : (Idle, Moving, Error := 10) UINT;
+//! #[derive(AdsType, Debug, PartialEq)]
+//! #[repr(u16)]
+//! enum State { Idle, Moving, Error = 10 }
+//! 
+//! let axis = Axis { enabled : true, position : 1.5, name : PlcString::from("X"), state : State::Error };
+//! let mut buf = [0; Axis::SIZE];
+//! axis.encode(&mut buf).unwrap();
+//! 
+//! assert_eq!(buf[0], 1);
+//! assert_eq!(&buf[8..16], &1.5f64.to_le_bytes());
+//! assert_eq!(&buf[28..30], &10u16.to_le_bytes()); // Aligned to 2 byte
+//! assert_eq!(Axis::decode(&buf).unwrap(), axis);
+//! 
+//! // Arrays of derived types are supported as well
+//! let axes = <[Axis; 4]>::decode(&[0; 4 * Axis::SIZE]).unwrap();
+//! assert_eq!(axes[3].state, State::Idle);
+//! ```
+//! 
 //! The methods are implemented asynchronous and non-blocking based on the [tokio](https://tokio.rs/) runtime.
 //! 
 //! # Usage
@@ -37,25 +118,73 @@ mod ads_add_device_notification;
 mod ads_delete_device_notification;
 mod ads_write_control;
 mod ads_read_device_info;
+mod ads_symbol_handle;
+mod online_change;
+mod reconnect;
+mod plc_types;
+mod symbols;
+mod ads_value;
+mod resolve;
+mod rpc;
+pub mod files;
+pub mod routes;
+pub mod discovery;
+mod subscription;
+mod notification_handle;
+mod process_mirror;
+mod filetime;
+mod notification_stream;
+mod batch;
+mod state_watch;
+mod system_service;
+mod plc;
+mod system_info;
+mod udp;
+#[cfg(feature = "tls")]
+mod secure;
 
 use std::time::{Instant, Duration};
 use std::io;
+use std::fmt;
 use std::net::SocketAddr;
-use std::mem::size_of_val;
-use std::sync::{Arc, Mutex, atomic::{AtomicU16, Ordering}};
+use std::sync::{Arc, Mutex, atomic::{AtomicU16, AtomicU32, Ordering}};
 use tokio::net::TcpStream;
 use tokio::{runtime, stream};
+use tokio::sync::{broadcast, watch};
 use tokio::io::{ReadHalf, WriteHalf};
-use tokio::io::{AsyncWriteExt, AsyncReadExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, AsyncReadExt};
 use tokio::time::sleep;
+use tokio::task::JoinHandle;
 use log::{trace, debug, info, warn, error};
 use bytes::{Bytes, BytesMut};
 
 use command_cleaner::CommandCleaner;
 use command_manager::CommandManager;
 
-use misc::{AdsCommand, Handle, HandleData, NotHandle, AmsNetId, AdsStampHeader, AdsNotificationSample};
-pub use misc::{AdsTimeout, AdsNotificationAttrib, AdsTransMode, StateInfo, DeviceStateInfo, AdsState, Notification, Result, AdsError, AdsErrorCode}; // Re-export type
+use misc::{AdsCommand, Handle, HandleData, NotHandle, NotCallback, NotDelivery, SymHandle, AmsNetId};
+use batch::BatchCallback;
+use notification_stream::SampleIter;
+use reconnect::RECONNECT_DELAY;
+pub use misc::{AdsTimeout, AdsNotificationAttrib, AdsNotificationAttribBuilder, AdsTransMode, StateInfo, DeviceStateInfo, AdsState, NotificationSample, NotificationDelivery, ClientEvent, Result, AdsError, AdsErrorCode}; // Re-export type
+pub use plc_types::{AdsType, AdsEncode, AdsDecode, PlcString, PlcWString, Time, LTime, TimeOfDay, Date, DateAndTime};
+pub use symbols::{SymbolTable, SymbolInfo, DataTypeInfo, ArrayInfo, EnumInfo, MethodInfo, MethodParamInfo};
+pub use ads_value::AdsValue;
+pub use resolve::ResolvedSymbol;
+pub use rpc::MethodResult;
+pub use subscription::{Subscription, Sample, TypedSubscription, TypedSample};
+pub use notification_handle::NotificationHandle;
+pub use process_mirror::{ProcessMirror, MirrorValue};
+pub use batch::{NotificationBatch, BatchSample, BatchStamp, BatchSubscription};
+pub use filetime::{filetime_to_system_time, system_time_to_filetime};
+pub use notification_stream::{parse_notification_stream, Stamp, StampSample};
+pub use plc::Plc;
+pub use system_info::{SystemInfo, TcVersion};
+#[cfg(feature = "tls")]
+pub use secure::{SecureAds, SECURE_ADS_PORT};
+#[doc(hidden)]
+pub use plc_types::__private;
+#[cfg(feature = "derive")]
+pub use ads_client_derive::AdsType;
 
 
 /// Size of the AMS/TCP + ADS headers
@@ -66,11 +195,48 @@ const LEN_READ_REQ          : usize = 12;
 const LEN_RW_REQ_MIN        : usize = 16;
 const LEN_W_REQ_MIN         : usize = 12;
 const LEN_ADD_DEV_NOT       : usize = 38;
-const LEN_STAMP_HEADER_MIN  : usize = 12;   // Time Stamp [8] + No Samples [4]
-const LEN_NOT_SAMPLE_MIN    : usize = 8;    // Notification Handle [4] + Sample Size [4]
 const LEN_DEL_DEV_NOT       : usize = 4;
 const LEN_WR_CTRL_MIN       : usize = 8;
 
+const PORT_SYSTEM_SERVICE   : u16 = 10000;
+
+const CAPACITY_EVENTS       : usize = 16;
+const CAPACITY_SAMPLES      : usize = 128; // Queue of a subscription
+
+// Index groups for symbol access
+// https://infosys.beckhoff.com/content/1033/tc3_ads_intro/117241867.html
+const IDX_GRP_SYM_HDL_BY_NAME   : u32 = 0xF003;
+const IDX_GRP_SYM_VAL_BY_HDL    : u32 = 0xF005;
+const IDX_GRP_SYM_RELEASE_HDL   : u32 = 0xF006;
+const IDX_GRP_SYM_VERSION       : u32 = 0xF008;
+const IDX_GRP_SYM_INFO_BY_NAME  : u32 = 0xF009;
+const IDX_GRP_SYM_UPLOAD        : u32 = 0xF00B;
+const IDX_GRP_SYM_DT_UPLOAD     : u32 = 0xF00E;
+const IDX_GRP_SYM_UPLOAD_INFO2  : u32 = 0xF00F;
+const IDX_GRP_DEVICE_DATA       : u32 = 0xF100; // ADS state [2] + device state [2]
+
+// Index groups of the system service (not documented by Beckhoff)
+const IDX_GRP_TC_SYS_INFO       : u32 = 700;
+const IDX_GRP_TC_HOSTNAME       : u32 = 702;
+
+/// Connection to the ADS router or, with Secure ADS, to the target.
+trait AdsStream: AsyncRead + AsyncWrite + Send + Unpin + fmt::Debug {}
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + fmt::Debug> AdsStream for T {}
+
+/// Write half of the connection, `None` after the connection is lost
+type SocketWriter = Arc<tokio::sync::Mutex<Option<WriteHalf<Box<dyn AdsStream>>>>>;
+
+#[derive(Debug, Clone)]
+enum Transport {
+    /// Local ADS router (127.0.0.1:48898)
+    Router,
+    #[cfg(feature = "tls")]
+    Secure(Box<SecureAds>),
+    /// In-memory connections handed over by the tests
+    #[cfg(test)]
+    Test(Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<tokio::io::DuplexStream>>>)
+}
+
 enum ProcessStateMachine{
     ReadHeader,
     ReadPayload { len_payload: usize, err_code: u32, invoke_id: u32, cmd: AdsCommand}
@@ -82,11 +248,14 @@ pub struct ClientBuilder<'a> {
     port: u16,
     timeout: AdsTimeout,
     retry_delay: Option<Duration>,
+    online_change: bool,
+    delivery: NotificationDelivery,
+    transport: Transport,
 }
 
 impl<'a> ClientBuilder<'a> {
     pub fn new(addr: &'a str, port: u16) -> Self {
-        Self { addr, port, timeout: AdsTimeout::DefaultTimeout, retry_delay: None }
+        Self { addr, port, timeout: AdsTimeout::DefaultTimeout, retry_delay: None, online_change: false, delivery: NotificationDelivery::Concurrent, transport: Transport::Router }
     }
 
     pub fn set_timeout(mut self, timeout: AdsTimeout) -> Self {
@@ -94,13 +263,49 @@ impl<'a> ClientBuilder<'a> {
         self
     }
 
+    /// Set the delay between attempts to reconnect after the connection was lost, defaults to 1 s.
+    /// 
+    /// After a reconnect, cached symbol handles are re-acquired and notifications are registered again
+    /// (see [ClientEvent::Reconnected]).
     pub fn set_retry_delay(mut self, retry_delay: Option<Duration>) -> Self {
         self.retry_delay = retry_delay;
         self
     }
 
+    /// Watch the symbol version of the target (index group 0xF008) to detect online changes.
+    /// 
+    /// After an online change, all handles cached with [Client::symbol_handle] are re-acquired 
+    /// and notifications registered on them are re-registered. 
+    /// Afterwards, [ClientEvent::OnlineChange] is emitted (see [Client::events]).
+    /// 
+    /// Only applicable for PLC ports (e.g. 851), disabled by default.
+    pub fn set_online_change_detection(mut self, enable: bool) -> Self {
+        self.online_change = enable;
+        self
+    }
+
+    /// Set the delivery of notification samples to the callbacks (see [NotificationDelivery]).
+    /// 
+    /// Defaults to [NotificationDelivery::Concurrent]. [Subscriptions](Client::subscribe) always deliver in order.
+    pub fn set_notification_delivery(mut self, delivery: NotificationDelivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// Connect to the target with Secure ADS (TLS) instead of the local ADS router (requires the feature *tls*).
+    #[cfg(feature = "tls")]
+    pub fn secure(mut self, config: SecureAds) -> Self {
+        self.transport = Transport::Secure(Box::new(config));
+        self
+    }
+
     pub async fn build(self) -> Result<Client> {
-        Client::new(self.addr, self.port, self.timeout, self.retry_delay).await
+        let client = Client::new(self.addr, self.port, self.timeout, self.retry_delay, self.delivery, self.transport).await?;
+
+        if self.online_change {
+            client.watch_online_change().await?;
+        }
+        Ok(client)
     }
 }
 
@@ -108,18 +313,28 @@ impl<'a> ClientBuilder<'a> {
 /// 
 /// The client opens a port on the local ADS router in order to submit ADS requests.
 /// Use the [Client::new] method to create an instance.
+/// 
+/// Cloning the client is cheap, all clones share the same connection to the router.
+/// The connection is closed and the background tasks stop when all clones are dropped.
 #[derive(Debug)]
+#[derive(Clone)]
 pub struct Client {
     _dst_addr       : AmsNetId,
     _dst_port       : u16,
-    _src_addr       : AmsNetId,
-    _src_port       : u16,
+    source          : Arc<Mutex<[u8; 8]>>, // NetId and port assigned by the router, renewed on reconnect
     timeout         : u64, // ADS Timeout [s]
-    socket_wrt      : Arc<Mutex<WriteHalf<TcpStream>>>,
+    socket_wrt      : SocketWriter,
     handles         : Arc<Mutex<Vec<Handle>>>, // Internal stack of Handles (^=ADS CommandsInvoke) for decoupling requests and responses
     not_handles     : Arc<Mutex<Vec<NotHandle>>>,
+    sym_handles     : Arc<Mutex<Vec<SymHandle>>>, // Cached symbol handles
+    symbol_table    : Arc<Mutex<Option<Arc<SymbolTable>>>>, // Cached symbol and data type upload
+    events          : broadcast::Sender<ClientEvent>,
     ams_header      : [u8; HEADER_SIZE],
-    hdl_cnt         : Arc<AtomicU16>
+    hdl_cnt         : Arc<AtomicU16>,
+    not_hdl_cnt     : Arc<AtomicU32>,
+    delivery        : NotificationDelivery,
+    lifetime        : Option<Arc<watch::Sender<()>>>, // Shared by the clones of the user, None for background tasks
+    shutdown        : watch::Receiver<()> // Closed when all clones of the user are dropped
 }
 
 // TODO: Implement Defaul trait
@@ -186,7 +401,56 @@ impl Client {
         Ok(stream)
     } 
 
-    async fn process_response(handles: Arc<Mutex<Vec<Handle>>>, not_handles: Arc<Mutex<Vec<NotHandle>>>, mut rd_stream : ReadHalf<TcpStream>, retry_delay: Option<Duration>) {
+    /// Process incoming frames and re-establish the connection if it is lost.
+    /// 
+    /// Runs on a [detached](Client::detached) client until the client is dropped.
+    async fn process_response(self, mut rd_stream : ReadHalf<Box<dyn AdsStream>>, transport: Transport, retry_delay: Duration) {
+        let mut renewal : Option<JoinHandle<()>> = None;
+
+        loop {
+            let err = tokio::select! {
+                err = Client::read_frames(&self.handles, &self.not_handles, &mut rd_stream) => err,
+                _ = self.released() => {
+                    debug!("Client dropped - connection closed");
+                    break;
+                }
+            };
+
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                error!("Connection closed by the remote side");
+            } else {
+                error!("Socket Error (0x1): {:?}", err);
+            }
+
+            // Further requests fail immediately
+            self.socket_wrt.lock().await.take();
+            Client::fail_pending_commands(&self.handles);
+            self.invalidate_notifications();
+
+            if let Some(renewal) = renewal.take() {
+                renewal.abort();
+            }
+            let _ = self.events.send(ClientEvent::Disconnected);
+
+            match self.reconnect(&transport, retry_delay).await {
+                Some(stream) => rd_stream = stream,
+                None => break
+            }
+
+            // Responses to the renewal are processed by this task
+            let client = self.clone();
+            renewal = Some(runtime::Handle::current().spawn(async move {
+                client.process_reconnect(retry_delay).await;
+            }));
+        }
+
+        if let Some(renewal) = renewal {
+            renewal.abort();
+        }
+    }
+
+    /// Read incoming frames until the connection fails.
+    async fn read_frames(handles: &Arc<Mutex<Vec<Handle>>>, not_handles: &Mutex<Vec<NotHandle>>, rd_stream : &mut ReadHalf<Box<dyn AdsStream>>) -> io::Error {
         
         let mut state = ProcessStateMachine::ReadHeader;
         let rt = runtime::Handle::current();
@@ -198,105 +462,83 @@ impl Client {
 
                     let mut header_buf : [u8; HEADER_SIZE] = [0; HEADER_SIZE];
 
-                    match rd_stream.read(&mut header_buf).await {
-                        Ok(0) => {
-                           warn!("[0] Incoming ADS response - no bytes to read");
-                        }
-                        Ok(_) => {
-                            let len_payload = Client::extract_length(&header_buf).unwrap_or_default();
-                            let err_code = Client::extract_error_code(&header_buf).unwrap_or_default();
-                            let invoke_id   = Client::extract_invoke_id(&header_buf).unwrap_or_default();
-                            let ads_cmd     = Client::extract_cmd_tyte(&header_buf).unwrap_or_default();
-
-                            if(len_payload == 0){
-                                warn!("Invoke id {}: No ADS payload available - skip", invoke_id);
-                                continue;
-                            }
-
-                            trace!("[0] Incoming ADS response with {:?} byte payload", len_payload);
-
-                            state = ProcessStateMachine::ReadPayload{
-                                len_payload : len_payload,
-                                err_code    : err_code,
-                                invoke_id   : invoke_id,
-                                cmd         : ads_cmd
-                            };
+                    if let Err(e) = rd_stream.read_exact(&mut header_buf).await {
+                        return e;
+                    }
 
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                            warn!("TcpStream: false positive reaction / stream was not yet ready for reading{:?}", e);
-                            continue;
-                        }
-                        Err(e) => {
-                            error!("Socket Error (0x1): {:?}", e);
-                            //panic!("Socket Error (0x1): {:?}", e);
-                            if let Some(ref delay) = retry_delay {
-                                sleep(*delay).await;
-                            }
-                        }
+                    let len_payload = Client::extract_length(&header_buf).unwrap_or_default();
+                    let err_code = Client::extract_error_code(&header_buf).unwrap_or_default();
+                    let invoke_id   = Client::extract_invoke_id(&header_buf).unwrap_or_default();
+                    let ads_cmd     = Client::extract_cmd_tyte(&header_buf).unwrap_or_default();
+
+                    if(len_payload == 0){
+                        warn!("Invoke id {}: No ADS payload available - skip", invoke_id);
+                        continue;
                     }
+
+                    trace!("[0] Incoming ADS response with {:?} byte payload", len_payload);
+
+                    state = ProcessStateMachine::ReadPayload{
+                        len_payload,
+                        err_code,
+                        invoke_id,
+                        cmd         : ads_cmd
+                    };
                 }
                 
                 ProcessStateMachine::ReadPayload {len_payload, err_code, invoke_id, cmd} => {
                     
-                    let mut payload = BytesMut::with_capacity(*len_payload);
+                    // Large responses (e.g. symbol uploads) arrive in several segments
+                    let mut payload = BytesMut::zeroed(*len_payload);
 
-                    match rd_stream.read_buf(&mut payload).await {
-                        Ok(0) => {
-                            info!("[1] ADS response {:?}, Invoke ID: {:?}: - zero payload", cmd, invoke_id);
-                            state = ProcessStateMachine::ReadHeader;
-                        }
-                        Ok(_) => {
-                            
-                            let buf = payload.freeze(); // Convert to Bytes
-                            match cmd {
-                                AdsCommand::DeviceNotification => {
-                                    trace!("[1] Processing device notification");
-                                    let _not_handles = Arc::clone(&not_handles); 
-                                    rt.spawn(Client::process_device_notification(_not_handles, buf));
-
-                                },
-                                _ => {
-                                    trace!("[1] Processing ADS response");
-                                    let _handles = Arc::clone(&handles);
-                                    rt.spawn(Client::process_command(*err_code, *invoke_id, _handles, buf));
-                                }
-
-                            };
-
-                            state = ProcessStateMachine::ReadHeader;
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                            warn!("ADS command {:?}, Invoke ID: {:?}: - WouldBlock error during reading occured", cmd, invoke_id);
-                            continue;
-                        }
-                        Err(e) => {
-                            error!("ADS command {:?}, Invoke ID: {:?}: - Error occurred: {:?}", cmd, invoke_id, e);
-                            //panic!("Socket Error (0x1): {:?}", e);
-                            if let Some(ref delay) = retry_delay {
-                                sleep(*delay).await;
-                            }
+                    if let Err(e) = rd_stream.read_exact(&mut payload).await {
+                        error!("ADS command {:?}, Invoke ID: {:?}: - Error occurred: {:?}", cmd, invoke_id, e);
+                        return e;
+                    }
+
+                    let buf = payload.freeze(); // Convert to Bytes
+                    match cmd {
+                        AdsCommand::DeviceNotification => {
+                            trace!("[1] Processing device notification");
+                            // Processed in sequence to keep the order of the samples,
+                            // callbacks which might block are spawned (see NotDelivery)
+                            Client::process_device_notification(not_handles, buf);
+
+                        },
+                        _ => {
+                            trace!("[1] Processing ADS response");
+                            let _handles = Arc::clone(handles);
+                            rt.spawn(Client::process_command(*err_code, *invoke_id, _handles, buf));
                         }
-                    } // match
+
+                    };
+
+                    state = ProcessStateMachine::ReadHeader;
                 }
             } // match
         } // loop
     } // fn
 
+    /// Answer all pending commands with an empty payload, the [CommandManager] reports the connection loss.
+    fn fail_pending_commands(cmd_register: &Mutex<Vec<Handle>>) {
+        let mut handles = cmd_register.lock().expect("Threading Error");
+
+        for hdl in handles.iter_mut().filter(| hdl | hdl.data.payload.is_none()) {
+            hdl.data.payload = Some(Bytes::new());
+            hdl.data.ams_err = AdsErrorCode::ERR_PORTNOTCONNECTED.into();
+        }
+    }
+
     async fn socket_write(&self, data: &[u8] ) -> Result<()> {
 
                 let a_wrt_stream = Arc::clone(&self.socket_wrt);
                 {
-                    let mut wrt_stream = a_wrt_stream.lock();
-
-                    match wrt_stream {
-                        Ok(ref mut stream) => {
-                            stream.write(data).await?;
-                        },
-                        Err(_) => {
-                            return Err( AdsError { n_error : 10, s_msg : String::from("Writing to Tcp Stream socket failed") } );
-                        }
-                    }
+                    // Asynchronous mutex: the lock is held across the write
+                    let mut wrt_stream = a_wrt_stream.lock().await;
+                    let wrt_stream = wrt_stream.as_mut().ok_or_else(|| AdsError{n_error : AdsErrorCode::ERR_PORTNOTCONNECTED.into(), s_msg : String::from("Connection to the router is closed")})?;
+                    wrt_stream.write_all(data).await?;
+                    // TLS buffers the records until flushed
+                    wrt_stream.flush().await?;
                 }
                 //Err(Box::new(AdsError{ n_error : 1792 })) // DEBUG
                 Ok(())          
@@ -317,9 +559,7 @@ impl Client {
     ///     Ok(())
     /// }
     /// ```
-    async fn new(addr : &str, port : u16, timeout : AdsTimeout, retry_delay: Option<Duration>) -> Result<Self> {
-        let state_flag : u16 = 4;
-        let error_code : u32 = 0;
+    async fn new(addr : &str, port : u16, timeout : AdsTimeout, retry_delay: Option<Duration>, delivery: NotificationDelivery, transport: Transport) -> Result<Self> {
         let mut b_vec = Vec::<u8>::new();
 
         // BAUSTELLE // Pass ADS Address
@@ -328,50 +568,73 @@ impl Client {
             let n_byte = s_byte.parse::<u8>()?;
             b_vec.push(n_byte);
         }
+        let dst_addr : AmsNetId = b_vec.try_into().map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg : format!("Invalid AmsNetId {}", addr)})?;
 
         let timeout = match timeout {
             AdsTimeout::DefaultTimeout => 5,
             AdsTimeout::CustomTimeout(time) => time
         };
 
-        let hdl_rt = runtime::Handle::current();
-
         let mut answer : [u8; 14] = [0; 14];
-
-        let _stream = Client::connect(&mut answer).await?;
+        let stream = Client::open(&transport, &mut answer).await?;
         info!("ADS client port opened: {}", u16::from_ne_bytes(answer[12..14].try_into().unwrap_or_default()));
 
+        Ok(Client::start(dst_addr, port, timeout, delivery, stream, &answer, transport, retry_delay.unwrap_or(RECONNECT_DELAY)))
+    }
+
+    /// Open the connection to the router or, with Secure ADS, to the target.
+    async fn open(transport: &Transport, answer: &mut [u8; 14]) -> Result<Box<dyn AdsStream>> {
+        match transport {
+            Transport::Router => Ok(Box::new(Client::connect(answer).await?)),
+            #[cfg(feature = "tls")]
+            Transport::Secure(config) => config.connect(answer).await,
+            #[cfg(test)]
+            Transport::Test(streams) => {
+                let stream = streams.lock().expect("Threading Error").try_recv()
+                                .map_err(|_| AdsError{n_error : AdsErrorCode::ERR_PORTNOTCONNECTED.into(), s_msg : String::from("No connection available")})?;
+                answer.copy_from_slice(&tests::ANSWER_RECONNECT);
+                Ok(Box::new(stream))
+            }
+        }
+    }
+
+    /// Create the client on an opened connection and spawn the tasks which process it.
+    #[allow(clippy::too_many_arguments)]
+    fn start(b_vec : AmsNetId, port : u16, timeout : u64, delivery: NotificationDelivery, stream: Box<dyn AdsStream>, answer: &[u8; 14], transport: Transport, retry_delay: Duration) -> Self {
+        let state_flag : u16 = 4;
+        let error_code : u32 = 0;
+        let hdl_rt = runtime::Handle::current();
+
         // Split the stream into a read and write part
         //
         // Read-half goes to process_response()
         // Write-half goes to Self
 
-        let (read, write) = tokio::io::split(_stream);
+        let (read, write) = tokio::io::split(stream);
 
-        let a_socket_wrt = Arc::new(Mutex::new(write));
+        let a_socket_wrt = Arc::new(tokio::sync::Mutex::new(Some(write)));
 
         // Create atomic instances of the handle vector
         let a_handles = Arc::new(Mutex::new( Vec::<Handle>::new() ));
         let a_not_handles =  Arc::new(Mutex::new( Vec::<NotHandle>::new() ));
 
-        // Process incoming ADS responses
-        let response_vector_a  = Arc::clone(&a_handles);
-        let not_response_vector_a = Arc::clone(&a_not_handles);
-        hdl_rt.spawn(Client::process_response(response_vector_a, not_response_vector_a, read, retry_delay));
-
         // Instantiate and spawn the CommandCleanter
         let response_vector_b = Arc::clone(&a_handles);
         hdl_rt.spawn(CommandCleaner::new(1, timeout, response_vector_b));
 
-        Ok(Self {
-            _dst_addr    : b_vec.clone().try_into().expect("AmsNetId consist of exact 6 bytes"), // https://stackoverflow.com/questions/25428920/how-to-get-a-slice-as-an-array-in-rust
+        let (lifetime, shutdown) = watch::channel(());
+
+        let client = Self {
+            _dst_addr    : b_vec,
             _dst_port    : port,
-            _src_addr    : [answer[6], answer[7], answer[8], answer[9], answer[10], answer[11]],
-            _src_port    : u16::from_ne_bytes(answer[12..14].try_into().expect("Parsing source port failed")),
-            timeout      : timeout,
+            source       : Arc::new(Mutex::new(answer[6..14].try_into().expect("Source address consists of 8 bytes"))),
+            timeout,
             socket_wrt   : a_socket_wrt,
             handles      : a_handles,
             not_handles  : a_not_handles,
+            sym_handles  : Arc::new(Mutex::new( Vec::<SymHandle>::new() )),
+            symbol_table : Arc::new(Mutex::new(None)),
+            events       : broadcast::channel(CAPACITY_EVENTS).0,
             ams_header      : [
                 0, // Reserved
                 0,
@@ -412,8 +675,17 @@ impl Client {
                 0,
                 0
             ],
-            hdl_cnt         : Arc::new(AtomicU16::new(1))
-        })
+            hdl_cnt         : Arc::new(AtomicU16::new(1)),
+            not_hdl_cnt     : Arc::new(AtomicU32::new(1)),
+            delivery,
+            lifetime        : Some(Arc::new(lifetime)),
+            shutdown
+        };
+
+        // Process incoming ADS responses
+        hdl_rt.spawn(client.detached().process_response(read, transport, retry_delay));
+
+        client
     }
 
     fn register_command_handle(&self, invoke_id : u32, cmd : AdsCommand){
@@ -421,7 +693,7 @@ impl Client {
 
         let rs_req_hdl = Handle {
             cmd_type  : cmd,
-            invoke_id : invoke_id,
+            invoke_id,
             data      : HandleData::default(),
             timestamp : Instant::now(),
         };
@@ -432,19 +704,50 @@ impl Client {
         }
     }
 
-    fn register_not_handle(&self, not_hdl: u32, callback: Notification, user_data: Option<&Arc<Mutex<BytesMut>>>) {
+    /// Registers a notification handle and returns the handle passed to the user.
+    fn register_not_handle(&self, mut not_hdl: NotHandle) -> u32 {
         let a_not_handles = Arc::clone(&self.not_handles);
-
-        let not_hdl = NotHandle {
-            callback  : callback,
-            not_hdl   : not_hdl,
-            user_data : user_data.and_then(|arc_bytes| Some(Arc::clone(arc_bytes)) )
-        };
+        let id = self.not_hdl_cnt.fetch_add(1, Ordering::SeqCst);
+        not_hdl.id = id;
 
         {
             let mut not_handles = a_not_handles.lock().expect("Threading Error");
             not_handles.push(not_hdl);
         }
+        id
+    }
+
+    fn unregister_not_handle(&self, id: u32) -> Option<NotHandle> {
+        let mut not_handles = self.not_handles.lock().expect("Threading Error");
+        not_handles.iter()
+            .position(| hdl | hdl.id == id)
+            .map(| pos | not_handles.swap_remove(pos))
+    }
+
+    /// Clone of the client which submits its requests to another port of the target.
+    /// 
+    /// The clone shares the connection and all registers with the client.
+    fn with_port(&self, port: u16) -> Client {
+        let mut client = self.clone();
+        client._dst_port = port;
+        client.ams_header[12..14].copy_from_slice(&port.to_le_bytes());
+        client
+    }
+
+    /// Clone of the client for background tasks which does not keep the connection open.
+    /// 
+    /// Background tasks stop once [Client::released] completes.
+    fn detached(&self) -> Client {
+        let mut client = self.clone();
+        client.lifetime = None;
+        client
+    }
+
+    /// Completes when all clones of the client held by the user are dropped.
+    async fn released(&self) {
+        let mut shutdown = self.shutdown.clone();
+        // Nothing is sent, the channel only closes
+        while shutdown.changed().await.is_ok() {}
     }
 
     fn create_cmd_man_future(&self, invoke_id: u32) -> CommandManager {
@@ -461,6 +764,8 @@ impl Client {
         let length_header : u32 = AMS_HEADER_SIZE as u32 + length_payload;
 
         let mut ams_header : [u8; HEADER_SIZE] = self.ams_header;
+        // source address
+        ams_header[14..22].copy_from_slice(&*self.source.lock().expect("Threading Error"));
         // length header + payload
         ams_header[2..6].copy_from_slice(&length_header.to_ne_bytes());
         // command id
@@ -509,16 +814,6 @@ impl Client {
         Ok(usize::try_from(tmp)?)
     }
 
-    fn not_extract_length(answer: &[u8]) -> Result<usize>{
-        let tmp = u32::from_ne_bytes(answer[0..4].try_into()?);
-        Ok(usize::try_from(tmp)?)
-    }
-
-    /// Panics if the input slice is less than 8 bytes
-    fn not_extract_stamps(answer: &[u8]) -> Result<u32>{
-        Ok(u32::from_ne_bytes(answer[4..8].try_into()?))
-    }
-
     async fn process_command(err_code: u32, invoke_id: u32, cmd_register: Arc<Mutex<Vec<Handle>>>, data: Bytes){
         trace!("[2] AdsCmd: Invoke ID: {}", invoke_id);
 
@@ -535,122 +830,238 @@ impl Client {
             },
             Err(e) => {
                 error!("Failed to lock command register - response dropped");
-                return;
             }
-        };
+        }
     }
 
-    async fn process_device_notification(not_register: Arc<Mutex<Vec<NotHandle>>>, data: Bytes){
+    fn process_device_notification(not_register: &Mutex<Vec<NotHandle>>, data: Bytes){
         trace!("[2] Start processing AdsDeviceNotification");
-        let stream_length = match Client::not_extract_length(&data){
-            Ok(size) => size,
-            Err(e) => {
-                error!("Failed to extract notification length - Notification dropped - {:?}", e);
-                return;
+
+        // Notifications with batch delivery and the number of their samples in the frame
+        let mut batches : Vec<(u32, u32, BatchCallback, usize)> = { // LOCK
+            let not_handles = not_register.lock().expect("Threading Error");
+            not_handles.iter()
+                .filter_map(| hdl | match &hdl.delivery {
+                    NotDelivery::Batch(callback) => Some((hdl.id, hdl.not_hdl, Arc::clone(callback), 0)),
+                    _ => None
+                })
+                .collect()
+        }; // UNLOCK
+
+        // The whole frame is checked before any sample is delivered
+        let mut per_sample = false;
+        let checked = SampleIter::new(&data).and_then(| mut samples | samples.try_for_each(| sample | {
+            let not_hdl = sample?.not_hdl;
+            match batches.iter_mut().find(| (_, hdl, _, _) | *hdl == not_hdl) {
+                Some((_, _, _, len)) => *len += 1,
+                None => per_sample = true
             }
-        };
+            Ok(())
+        }));
+
+        if let Err(e) = checked {
+            error!("Malformed notification stream - Notification dropped - {:?}", e);
+            return;
+        }
+
+        // The batches refer to the frame, the samples are decoded on iteration
+        for (id, not_hdl, callback, len) in batches {
+            if len > 0 {
+                callback(NotificationBatch::new(id, not_hdl, data.clone(), len));
+            }
+        }
 
-        let no_stamps = match Client::not_extract_stamps(&data){
+        if !per_sample {
+            return;
+        }
+
+        let stamps = match parse_notification_stream(&data) {
             Ok(stamps) => stamps,
             Err(e) => {
-                error!("Failed to extract number of stamps- Notification dropped - {:?}", e);
+                error!("Malformed notification stream - Notification dropped - {:?}", e);
                 return;
             }
         };
 
-        let rt          = runtime::Handle::current();
-        // Maximum stamp_header_offset == stream_size - sizeof(stamps)
-        // ^= stream_size - 4
-        
-        // Calculate the last byte index of the AdsNotificaionStream (Length, Samples + AdsStampHeader)
-        let max_stamp_header_offset = stream_length + size_of_val(&no_stamps); 
-        let mut stamp_header_offset : usize = 8; // Start index of AdsNotificationStream
+        let rt = runtime::Handle::current();
 
+        for stamp in stamps {
+            for stamp_sample in stamp.samples {
+                // The callback must be called after the lock. 
+                // If it is called during the lock, it could block the access to the notification handles infinitely.
 
-        for _ in 0..no_stamps { // Iterate over AdsStampHeader 
-            // Return if there is no data beside of the AdsStampHeader consisting of time stamp [8] and no samples [4]
-            if (stamp_header_offset + LEN_STAMP_HEADER_MIN) > max_stamp_header_offset {
-                info!("Received Device Notification without sample data");
-                continue;
-            }
-           
-            
-            let stamp_header = AdsStampHeader {
-                timestamp : u64::from_ne_bytes(data[stamp_header_offset.. stamp_header_offset + 8]
-                                                .try_into()
-                                                .unwrap_or_default()),
-
-                samples : u32::from_ne_bytes(data[stamp_header_offset + 8..stamp_header_offset + 12]
-                                                .try_into()
-                                                .unwrap_or_default())
-            };
+                let _cb_and_data = { // LOCK
+                    let _not_handles = not_register.lock().expect("Threading Error");
+                    
+                    _not_handles.iter()
+                            .find( | hdl | hdl.not_hdl == stamp_sample.not_hdl)
+                            .map(| hdl : &NotHandle | (Arc::clone(&hdl.callback), hdl.delivery.clone(), hdl.id) ) // Return callback, delivery and user handle
+                }; // UNLOCK
+                
+                if let Some((callback, delivery, id)) = _cb_and_data {
+                    let sample = NotificationSample { handle : id, timestamp : stamp.timestamp, data : stamp_sample.data };
 
-            if (stamp_header == AdsStampHeader::default()){
-                info!("Empty AdsStampHeader - Continue with next stamp");
-                continue;
+                    match delivery {
+                        NotDelivery::Inline => callback(sample),
+                        NotDelivery::Spawn => {
+                            rt.spawn(async move  {
+                                callback(sample);
+                            });
+                        },
+                        NotDelivery::Queue(queue) => queue.push(sample),
+                        NotDelivery::Batch(_) => () // Handed over with the whole frame
+                    }
+                }
             }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+    use tokio::sync::mpsc;
+
+    const ANSWER            : [u8; 14] = [0, 0, 8, 0, 0, 0, 10, 0, 0, 1, 1, 1, 0x50, 0x80];
+    pub(crate) const ANSWER_RECONNECT  : [u8; 14] = [0, 0, 8, 0, 0, 0, 10, 0, 0, 1, 1, 1, 0x51, 0x80];
+
+    /// Client on an in-memory connection, connections for reconnects are handed over with the sender.
+    pub(crate) fn test_client() -> (Client, DuplexStream, mpsc::UnboundedSender<DuplexStream>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (local, remote) = tokio::io::duplex(1024);
+        let client = Client::start([5, 80, 201, 232, 1, 1], 851, 5, NotificationDelivery::Concurrent, Box::new(local), &ANSWER, Transport::Test(Arc::new(Mutex::new(rx))), Duration::from_millis(10));
+        (client, remote, tx)
+    }
 
-            // Increase stamp header offset, move it to first AdsNotificaionSample (+= 12 byte)
-            stamp_header_offset += LEN_STAMP_HEADER_MIN;
-            // == 20 (after first call)
+    /// Receive a request and return its header and payload.
+    pub(crate) async fn receive(remote: &mut DuplexStream) -> ([u8; HEADER_SIZE], Vec<u8>) {
+        let mut header = [0; HEADER_SIZE];
+        remote.read_exact(&mut header).await.unwrap();
 
-            for _ in 0..stamp_header.samples {
-                // Return if there is not enough data
-                if (stamp_header_offset + LEN_NOT_SAMPLE_MIN) > max_stamp_header_offset {
-                    info!("[A] Not enough data in available in stream");
-                    return;
-                }
+        let mut payload = vec![0; Client::extract_length(&header).unwrap()];
+        remote.read_exact(&mut payload).await.unwrap();
+        (header, payload)
+    }
 
-                let not_sample = AdsNotificationSample {
-                    not_hdl : u32::from_ne_bytes(data[stamp_header_offset..stamp_header_offset + 4]
-                                                        .try_into()
-                                                        .unwrap_or_default()),
+    /// Send a frame with the command and invoke ID of `request`.
+    pub(crate) async fn respond(remote: &mut DuplexStream, request: &[u8; HEADER_SIZE], payload: &[u8]) {
+        let mut header = *request;
+        header[2..6].copy_from_slice(&(AMS_HEADER_SIZE as u32 + payload.len() as u32).to_le_bytes());
+        header[26..30].copy_from_slice(&(payload.len() as u32).to_le_bytes());
 
-                    sample_size : u32::from_ne_bytes(data[stamp_header_offset + 4 ..stamp_header_offset + 8]
-                                                        .try_into()
-                                                        .unwrap_or_default())
-                };
+        remote.write_all(&header).await.unwrap();
+        remote.write_all(payload).await.unwrap();
+    }
 
-                if (not_sample == AdsNotificationSample::default()){
-                    info!("No data in AdsNotificationSample - skip");
-                    continue;
-                }
+    /// Send a DeviceNotification with a single sample.
+    pub(crate) async fn notify(remote: &mut DuplexStream, not_hdl: u32, data: &[u8]) {
+        let mut frame = [0u8; HEADER_SIZE];
+        frame[22..24].copy_from_slice(&(AdsCommand::DeviceNotification as u16).to_le_bytes());
 
-                stamp_header_offset += LEN_NOT_SAMPLE_MIN;
+        let length = 24 + data.len() as u32;
+        let stream = [&length.to_le_bytes()[..], &1u32.to_le_bytes(), &0u64.to_le_bytes(), &1u32.to_le_bytes(), &not_hdl.to_le_bytes(), &(data.len() as u32).to_le_bytes(), data].concat();
+        respond(remote, &frame, &stream).await;
+    }
 
-                if (stamp_header_offset + not_sample.sample_size as usize) > max_stamp_header_offset {
-                    info!("[B] Not enough data in available in stream");
-                    return;
-                }
+    /// Answer a request for a symbol handle with `handle`.
+    async fn respond_symbol_handle(remote: &mut DuplexStream, handle: u32) {
+        let (header, payload) = receive(remote).await;
+        assert!(matches!(Client::extract_cmd_tyte(&header).unwrap(), AdsCommand::ReadWrite));
+        assert_eq!(&payload[16..], b"MAIN.n_cnt_a");
 
-                let mut _cb_and_data : Option<(Notification, Option<Arc<Mutex<BytesMut>>>)> = None;
-                
-                // The callback must be called after the lock. 
-                // If it is called during the lock, it could block the access to the notification handles infinitely.
+        respond(remote, &header, &[&0u32.to_le_bytes()[..], &4u32.to_le_bytes(), &handle.to_le_bytes()].concat()).await;
+    }
 
-                { // LOCK
-                    let mut _not_handles = not_register.lock().expect("Threading Error");
-                    let mut _iter = _not_handles.iter_mut();
-                    
-                    _cb_and_data = _iter.find( | hdl | hdl.not_hdl  == not_sample.not_hdl)
-                            .and_then(| hdl : &mut NotHandle | Some( (hdl.callback, hdl.user_data.clone()) ) ); // Return callback and user data
-                } // UNLOCK
-                
-                
-                _cb_and_data.and_then(|(callback, user_data)| {
-                    let payload = Bytes::from(data.slice(stamp_header_offset..stamp_header_offset + not_sample.sample_size as usize));
-                    // let n_cnt = u16::from_ne_bytes(payload[..].try_into().expect("Failed to parse data")); // DEBUG
+    /// Answer an AddDeviceNotification request with `not_hdl` and return the requested index offset.
+    async fn respond_notification(remote: &mut DuplexStream, not_hdl: u32) -> u32 {
+        let (header, payload) = receive(remote).await;
+        assert!(matches!(Client::extract_cmd_tyte(&header).unwrap(), AdsCommand::AddDeviceNotification));
 
-                    Some(
-                            rt.spawn(async move  {
-                            callback(not_sample.not_hdl, stamp_header.timestamp, payload, user_data);
-                        })
-                    )
-                    
-                }); // Process join handles?
+        respond(remote, &header, &[0u32.to_le_bytes(), not_hdl.to_le_bytes()].concat()).await;
+        u32::from_le_bytes(payload[4..8].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn lost_connection_fails_pending_commands() {
+        let (client, mut remote, _) = test_client();
+        let mut events = client.events();
+
+        let reader = client.clone();
+        let pending = tokio::spawn(async move { reader.read_state().await });
+
+        // Wait for the request, then close the connection
+        let mut request = [0; HEADER_SIZE];
+        remote.read_exact(&mut request).await.unwrap();
+        assert_eq!(&request[14..22], &[10, 0, 0, 1, 1, 1, 0x50, 0x80]);
+        drop(remote);
 
-                stamp_header_offset += not_sample.sample_size as usize;
-            } // for idx_notification_sample in 0..stamp_header.samples
-        } // for idx_stamp_header in 0..stamps
+        let err = pending.await.unwrap().unwrap_err();
+        assert_eq!(err.n_error, u32::from(AdsErrorCode::ERR_PORTNOTCONNECTED));
+        assert_eq!(events.recv().await.unwrap(), ClientEvent::Disconnected);
+
+        // No connection available for the reconnect
+        let err = client.read_state().await.unwrap_err();
+        assert_eq!(err.n_error, u32::from(AdsErrorCode::ERR_PORTNOTCONNECTED));
+    }
+
+    #[tokio::test]
+    async fn dropped_client_stops_reader() {
+        let (client, _remote, _) = test_client();
+        let handles = Arc::downgrade(&client.handles);
+
+        drop(client);
+
+        // Reader and command cleaner release the register
+        tokio::time::timeout(Duration::from_secs(3), async {
+            while handles.strong_count() > 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        }).await.expect("Background tasks still running");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn reconnect_renews_handles_and_notifications() {
+        let (client, mut remote, tx) = test_client();
+        let mut events = client.events();
+
+        let (hdl, _) = tokio::join!(client.symbol_handle("MAIN.n_cnt_a"), respond_symbol_handle(&mut remote, 11));
+        let hdl = hdl.unwrap();
+
+        let (samples_tx, mut samples) = mpsc::unbounded_channel();
+        let attrib = AdsNotificationAttrib { cb_length : 2, trans_mode : AdsTransMode::OnChange, max_delay : 0, cycle_time : 0 };
+        let server = respond_notification(&mut remote, 21);
+        let (not_hdl, idx_offs) = tokio::join!(client.add_device_notification(IDX_GRP_SYM_VAL_BY_HDL, hdl, &attrib, move | sample : NotificationSample | {
+            let _ = samples_tx.send(sample.data.to_vec());
+        }), server);
+        assert_eq!(idx_offs, 11);
+        let not_hdl = not_hdl.unwrap();
+
+        // Lose the connection, the next connection is assigned another port
+        drop(remote);
+        assert_eq!(events.recv().await.unwrap(), ClientEvent::Disconnected);
+
+        let (local, mut remote) = tokio::io::duplex(1024);
+        tx.send(local).unwrap();
+
+        respond_symbol_handle(&mut remote, 12).await;
+        assert_eq!(respond_notification(&mut remote, 22).await, 12);
+        assert_eq!(events.recv().await.unwrap(), ClientEvent::Reconnected);
+        assert_eq!(client.symbol_handle("MAIN.n_cnt_a").await.unwrap(), 12);
+
+        // Samples of the new notification reach the callback
+        notify(&mut remote, 22, &[7, 0]).await;
+        assert_eq!(samples.recv().await.unwrap(), vec![7, 0]);
+
+        // Requests are sent from the new port
+        let pending = client.read_state();
+        let server = async {
+            let (header, _) = receive(&mut remote).await;
+            assert_eq!(&header[20..22], &[0x51, 0x80]);
+            respond(&mut remote, &header, &[0, 0, 0, 0, 5, 0, 0, 0]).await;
+        };
+        let (state, _) = tokio::join!(pending, server);
+        assert_eq!(state.unwrap().ads_state, AdsState::Run);
+
+        not_hdl.detach();
+    }
+}
@@ -1,10 +1,15 @@
 use std::{fmt, io, num, error, convert, array};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 use bytes::{Bytes, BytesMut};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use tokio::sync::mpsc;
+use log::warn;
+use crate::batch::BatchCallback;
 
 #[macro_use]
+#[allow(clippy::module_inception)]
 mod misc {
     #[macro_export]
     macro_rules!  u16_low_byte {
@@ -52,14 +57,16 @@ mod misc {
 pub type AmsNetId = [u8; 6];
 pub type Result<T> = std::result::Result<T, AdsError>;
 
-/// Type definition for notification callback.
-/// 
-/// Arguments:
-/// 1. Handle
-/// 2. Timestamp
-/// 3. Value of monitored variable
-/// 4. User data
-pub type Notification = fn(u32, u64, Bytes, Option<Arc<Mutex<BytesMut>>>) -> (); // handle, timestamp and user data
+/// Value of a notification passed to the callback of [Client::add_device_notification](crate::Client::add_device_notification).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationSample {
+    /// Handle returned by [Client::add_device_notification](crate::Client::add_device_notification)
+    pub handle      : u32,
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    /// Value of the monitored variable
+    pub data        : Bytes
+}
 
 /// Error type of returned Result
 ///  
@@ -121,6 +128,7 @@ impl From<convert::Infallible> for AdsError{
 /// 
 /// Please also read the related documentation in the [InfoSys](https://infosys.beckhoff.com/content/1031/tc3_adsdll2/117553803.html).
 #[derive(Copy, Clone)]
+#[derive(Debug)]
 pub enum AdsTransMode {
     ServerCycle = 3,
     OnChange    = 4
@@ -134,6 +142,10 @@ pub enum AdsTransMode {
 /// - `AdsTransMode` Specifies when to trigger a notification (see [AdsTransMode]).
 /// - `max_delay` Maximal acceptable delay \[100ns\].
 /// - `cycle_time` The interval at which the variable is checked \[100ns]\. 
+/// 
+/// Use [AdsNotificationAttribBuilder] to pass the times as [Duration].
+#[derive(Copy, Clone)]
+#[derive(Debug)]
 pub struct AdsNotificationAttrib {
     pub cb_length   : u32,
     pub trans_mode  : AdsTransMode,
@@ -141,22 +153,93 @@ pub struct AdsNotificationAttrib {
     pub cycle_time  : u32
 }
 
-#[derive(Debug)]
-#[derive(Default)]
-#[derive(PartialEq)]
-#[derive(Eq)]
-pub struct AdsStampHeader {
-    pub timestamp   : u64,
-    pub samples     : u32
+fn attrib_error(s_msg: String) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg}
 }
 
-#[derive(Debug)]
-#[derive(Default)]
-#[derive(PartialEq)]
-#[derive(Eq)]
-pub struct AdsNotificationSample {
-    pub not_hdl     : u32,
-    pub sample_size : u32
+impl AdsNotificationAttrib {
+    /// Check the attributes for combinations which are rejected by the target.
+    /// 
+    /// Called before each registration of a notification.
+    pub fn validate(&self) -> Result<()> {
+        if self.cb_length == 0 {
+            return Err(attrib_error(String::from("Notification attributes: cb_length must not be 0")));
+        }
+
+        if matches!(self.trans_mode, AdsTransMode::ServerCycle) && self.cycle_time == 0 {
+            return Err(attrib_error(String::from("Notification attributes: ServerCycle requires a cycle_time > 0")));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [AdsNotificationAttrib] with times passed as [Duration].
+/// 
+/// The times are truncated to multiples of 100ns. [AdsNotificationAttribBuilder::build] fails if a time exceeds 
+/// the range of the protocol (about 429 s) or if the combination is rejected by [AdsNotificationAttrib::validate].
+/// 
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use ads_client::{AdsNotificationAttribBuilder, AdsTransMode, Result};
+///
+/// fn main() -> Result<()> {
+///     let attrib = AdsNotificationAttribBuilder::new(2, AdsTransMode::ServerCycle) // UINT
+///                     .set_cycle_time(Duration::from_millis(10))
+///                     .set_max_delay(Duration::from_millis(100))
+///                     .build()?;
+///
+///     assert_eq!(attrib.cycle_time, 100_000);
+///     assert_eq!(attrib.max_delay, 1_000_000);
+///
+///     // Rejected before the request is submitted
+///     assert!(AdsNotificationAttribBuilder::new(2, AdsTransMode::ServerCycle).build().is_err());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdsNotificationAttribBuilder {
+    cb_length   : u32,
+    trans_mode  : AdsTransMode,
+    max_delay   : Duration,
+    cycle_time  : Duration
+}
+
+impl AdsNotificationAttribBuilder {
+    pub fn new(cb_length: u32, trans_mode: AdsTransMode) -> Self {
+        Self { cb_length, trans_mode, max_delay: Duration::ZERO, cycle_time: Duration::ZERO }
+    }
+
+    /// Maximal acceptable delay before the notification is sent, defaults to zero.
+    pub fn set_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Interval at which the variable is checked, defaults to zero (each cycle).
+    pub fn set_cycle_time(mut self, cycle_time: Duration) -> Self {
+        self.cycle_time = cycle_time;
+        self
+    }
+
+    /// Convert a duration to a multiple of 100ns.
+    fn ticks(time: Duration, name: &str) -> Result<u32> {
+        u32::try_from(time.as_nanos() / 100)
+            .map_err(|_| attrib_error(format!("Notification attributes: {} of {:?} exceeds the maximum of {:?}", name, time, Duration::from_nanos(u32::MAX as u64 * 100))))
+    }
+
+    pub fn build(self) -> Result<AdsNotificationAttrib> {
+        let attrib = AdsNotificationAttrib {
+            cb_length   : self.cb_length,
+            trans_mode  : self.trans_mode,
+            max_delay   : Self::ticks(self.max_delay, "max_delay")?,
+            cycle_time  : Self::ticks(self.cycle_time, "cycle_time")?
+        };
+
+        attrib.validate()?;
+        Ok(attrib)
+    }
 }
 
 #[derive(Default)]
@@ -174,11 +257,100 @@ pub struct Handle {
     pub timestamp : Instant, // Timestamp of creation
 }
 
-#[derive(Debug)]
+/// Internal representation of a notification callback.
+pub type NotCallback = Arc<dyn Fn(NotificationSample) + Send + Sync>;
+
+/// How the callback of a notification is invoked.
+#[derive(Clone)]
+pub enum NotDelivery {
+    /// In a separate task for each sample
+    Spawn,
+    /// Directly by the reader of the socket, the callback must not block
+    Inline,
+    /// In sequence by a worker task of the notification
+    Queue(NotQueue),
+    /// All samples of a frame at once, directly by the reader of the socket
+    Batch(BatchCallback)
+}
+
+/// Bounded queue of a notification worker, samples which do not fit are dropped and counted.
+#[derive(Clone)]
+pub struct NotQueue {
+    pub tx      : mpsc::Sender<NotificationSample>,
+    pub lost    : Arc<AtomicU64>
+}
+
+impl NotQueue {
+    /// Called by the socket reader, does not block.
+    pub fn push(&self, sample: NotificationSample) {
+        match self.tx.try_send(sample) {
+            Ok(()) => (),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.lost.fetch_add(1, Ordering::Relaxed);
+            },
+            Err(mpsc::error::TrySendError::Closed(sample)) => warn!("Worker of notification handle {} stopped - Sample dropped", sample.handle)
+        }
+    }
+}
+
+impl fmt::Debug for NotDelivery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotDelivery::Spawn      => write!(f, "Spawn"),
+            NotDelivery::Inline     => write!(f, "Inline"),
+            NotDelivery::Queue(_)   => write!(f, "Queue"),
+            NotDelivery::Batch(_)   => write!(f, "Batch")
+        }
+    }
+}
+
 pub struct NotHandle {
-    pub callback  : Notification,
-    pub not_hdl   : u32,
-    pub user_data : Option<Arc<Mutex<BytesMut>>>,
+    pub callback  : NotCallback,
+    pub delivery  : NotDelivery,
+    pub id        : u32, // Handle passed to the user, stays valid across re-registrations
+    pub not_hdl   : u32, // Notification handle assigned by the target
+    pub idx_grp   : u32,
+    pub idx_offs  : u32,
+    pub attrib    : AdsNotificationAttrib,
+    pub symbol    : Option<String>, // Symbol of a handle owned by the notification, released on delete
+}
+
+impl fmt::Debug for NotHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NotHandle")
+            .field("id", &self.id)
+            .field("not_hdl", &self.not_hdl)
+            .field("idx_grp", &self.idx_grp)
+            .field("idx_offs", &self.idx_offs)
+            .field("attrib", &self.attrib)
+            .field("symbol", &self.symbol)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct SymHandle {
+    pub symbol  : String,
+    pub handle  : u32
+}
+
+/// Events emitted by the [Client](crate::Client).
+/// 
+/// Subscribe with [Client::events](crate::Client::events).
+/// 
+/// - `OnlineChange` The symbol version of the target changed, e.g. after an online change or a download of the PLC project.
+///   Cached symbol handles were already re-acquired when this event is emitted.
+/// - `Disconnected` The connection to the router (or with Secure ADS to the target) was lost, e.g. during a restart of TwinCAT.
+///   Pending requests failed, the client tries to reconnect (see [ClientBuilder::set_retry_delay](crate::ClientBuilder::set_retry_delay)).
+/// - `Reconnected` The connection was re-established. Cached symbol handles were re-acquired and notifications were
+///   registered again when this event is emitted.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum ClientEvent {
+    OnlineChange { symbol_version : u8 },
+    Disconnected,
+    Reconnected
 }
 /// Specifies the maximum waiting time for an ADS response.
 /// 
@@ -190,9 +362,26 @@ pub enum AdsTimeout {
     CustomTimeout(u64)
 }
 
+/// Delivery of the samples to the callbacks of [Client::add_device_notification](crate::Client::add_device_notification).
+/// 
+/// - [NotificationDelivery::Concurrent] Each sample is passed to the callback in a separate task. 
+///   Samples of the same notification may be processed concurrently and out of order.
+/// - [NotificationDelivery::Ordered] Each notification has a queue and a worker task. The samples of a notification
+///   are passed to the callback one after another in the order of arrival, samples of different notifications are processed concurrently.
+///   The queue holds 128 samples, if the callback does not keep up, new samples are dropped and a warning is logged.
+/// 
+/// In both modes, a slow callback does not block the reception of responses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationDelivery {
+    #[default]
+    Concurrent,
+    Ordered
+}
+
 /// ADS State and device state of a target system.
 #[derive(Default)]
 #[derive(Debug)]
+#[derive(Clone, Copy)]
 #[derive(PartialEq)]
 pub struct StateInfo {
     pub ads_state    : AdsState,
@@ -213,7 +402,9 @@ pub struct DeviceStateInfo {
 #[derive(Copy, Clone)]
 #[allow(dead_code)]
 #[derive(Debug)]
+#[derive(Default)]
 pub enum AdsCommand {
+    #[default]
     Invalid = 0,
     ReadDeviceInfo = 1,
     Read = 2,
@@ -226,10 +417,6 @@ pub enum AdsCommand {
     ReadWrite = 9
 }
 
-impl Default for AdsCommand {
-    fn default() -> Self { AdsCommand::Invalid }
-}
-
 impl TryFrom<u16> for AdsCommand{
     type Error = AdsError;
 
@@ -253,13 +440,16 @@ impl TryFrom<u16> for AdsCommand{
 #[allow(dead_code)]
 #[derive(PartialEq)]
 #[derive(Debug)]
+#[derive(Default)]
 /// ADS State of target system.
 /// 
 /// To switch a TwinCAT 3 system to Config mode, set it to [AdsState::Reconfig], 
-/// for Run mode set it to [AdsState::Reset].
+/// for Run mode set it to [AdsState::Reset]. [Client::set_config_mode](crate::Client::set_config_mode) and
+/// [Client::set_run_mode](crate::Client::set_run_mode) additionally wait until the mode is reached.
 /// 
 /// Checkout the [ADS Write Control example](https://github.com/hANSIc99/ads_client/blob/main/examples/write_control.rs) in the repsoitory.
 pub enum AdsState {
+    #[default]
     Invalid         = 0,
     Idle            = 1,
     Reset           = 2,
@@ -279,10 +469,6 @@ pub enum AdsState {
     Reconfig        = 16, // system should restart in config mode
 }
 
-impl Default for AdsState {
-    fn default() -> Self { AdsState::Invalid }
-}
-
 impl TryFrom<u16> for AdsState {
     type Error = AdsError;
 
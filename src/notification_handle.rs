@@ -0,0 +1,69 @@
+use log::warn;
+use tokio::runtime;
+use crate::{Client, Result};
+
+/// Registered notification, returned by [Client::add_device_notification].
+///
+/// The notification is deleted when the handle is dropped: the callback is removed immediately
+/// and the delete request is sent to the target in the background. Use [NotificationHandle::unsubscribe]
+/// to delete the notification deterministically and to receive the result of the request.
+#[derive(Debug)]
+pub struct NotificationHandle {
+    client  : Client,
+    handle  : u32,
+    active  : bool
+}
+
+impl NotificationHandle {
+    pub(crate) fn new(client: Client, handle: u32) -> Self {
+        NotificationHandle { client, handle, active : true }
+    }
+
+    /// Handle assigned by the client.
+    ///
+    /// It stays valid if the notification is re-registered after an online change
+    /// (see [ClientBuilder::set_online_change_detection](crate::ClientBuilder::set_online_change_detection)).
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Delete the notification and wait for the response of the target.
+    pub async fn unsubscribe(mut self) -> Result<()> {
+        self.active = false;
+        self.client.delete_device_notification(self.handle).await
+    }
+
+    /// Release the guard without deleting the notification and return its handle.
+    ///
+    /// The notification has to be deleted manually with [Client::delete_device_notification].
+    pub fn detach(mut self) -> u32 {
+        self.active = false;
+        self.handle
+    }
+}
+
+impl Drop for NotificationHandle {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        // Remove the callback right away, no samples are delivered after the drop
+        let Some(not_hdl) = self.client.unregister_not_handle(self.handle) else {
+            return;
+        };
+
+        let (client, handle) = (self.client.clone(), not_hdl.not_hdl);
+
+        match runtime::Handle::try_current() {
+            Ok(rt) => {
+                rt.spawn(async move {
+                    if let Err(err) = client.delete_not_handle(not_hdl).await {
+                        warn!("Failed to delete notification handle {}: {}", handle, err);
+                    }
+                });
+            },
+            Err(_) => warn!("Notification handle {} not deleted - no runtime available", handle)
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Parser of the AdsNotificationStream (payload of a DeviceNotification).
+//!
+//! ```text
+//! Length [4] | Stamps [4] | AdsStampHeader ...
+//! AdsStampHeader:         Timestamp [8] | Samples [4] | AdsNotificationSample ...
+//! AdsNotificationSample:  Handle [4] | Size [4] | Data [Size]
+//! ```
+//!
+//! The data is received from the network, hence each length is checked before it is used.
+use bytes::Bytes;
+use crate::{Result, AdsError, AdsErrorCode};
+
+const LEN_STREAM_HEADER     : usize = 8;  // Length [4] + Stamps [4]
+const LEN_STAMP_HEADER      : usize = 12; // Timestamp [8] + Samples [4]
+const LEN_SAMPLE_HEADER     : usize = 8;  // Handle [4] + Size [4]
+
+fn stream_error(s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : format!("Invalid notification stream: {}", s_msg)}
+}
+
+/// Time stamp and samples of an AdsStampHeader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamp {
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    pub samples     : Vec<StampSample>
+}
+
+/// Sample of an AdsStampHeader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StampSample {
+    /// Notification handle assigned by the target
+    pub not_hdl     : u32,
+    /// Shares the memory of the received stream
+    pub data        : Bytes
+}
+
+/// Bounds-checked reader of the stream following the length field.
+#[derive(Debug, Clone)]
+struct Cursor<'a> {
+    buf : &'a [u8],
+    pos : usize
+}
+
+impl<'a> Cursor<'a> {
+    /// Returns the cursor positioned after the number of stamps and the number of stamps.
+    fn new(stream: &'a [u8]) -> Result<(Self, u32)> {
+        if stream.len() < LEN_STREAM_HEADER {
+            return Err(stream_error("missing header"));
+        }
+
+        // The length covers the stream following the length field
+        let length = u32::from_le_bytes(stream[0..4].try_into()?) as usize;
+        let buf = stream[4..].get(..length).ok_or_else(|| stream_error("length exceeds the received data"))?;
+
+        let mut cursor = Cursor { buf, pos : 0 };
+        let stamps = cursor.u32("number of stamps")?;
+        Ok((cursor, stamps))
+    }
+
+    fn take(&mut self, len: usize, what: &str) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.buf.len())
+                    .ok_or_else(|| stream_error(&format!("{} exceeds the stream", what)))?;
+
+        let slice = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self, what: &str) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4, what)?.try_into()?))
+    }
+
+    /// Time stamp and number of samples.
+    fn stamp_header(&mut self) -> Result<(u64, u32)> {
+        let header = self.take(LEN_STAMP_HEADER, "stamp header")?;
+        Ok((u64::from_le_bytes(header[0..8].try_into()?), u32::from_le_bytes(header[8..12].try_into()?)))
+    }
+
+    /// Notification handle and data, the data starts at the returned offset of the stream.
+    fn sample(&mut self) -> Result<(u32, usize, &'a [u8])> {
+        let not_hdl = self.u32("sample header")?;
+        let size    = self.u32("sample header")? as usize;
+        let offset  = 4 + self.pos; // Offset in the stream incl. the length field
+        Ok((not_hdl, offset, self.take(size, "sample data")?))
+    }
+}
+
+/// Parse an AdsNotificationStream into its stamps and samples.
+///
+/// The data of the samples refers to `stream`, no data is copied. Malformed input results in an error,
+/// the function never panics. Data after the last stamp is ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use bytes::Bytes;
+/// use ads_client::parse_notification_stream;
+///
+/// let mut stream = Vec::new();
+/// stream.extend(26u32.to_le_bytes());     // Length of the following data
+/// stream.extend(1u32.to_le_bytes());      // Stamps
+/// stream.extend(1000u64.to_le_bytes());   // Time stamp
+/// stream.extend(1u32.to_le_bytes());      // Samples
+/// stream.extend(7u32.to_le_bytes());      // Notification handle
+/// stream.extend(2u32.to_le_bytes());      // Sample size
+/// stream.extend(42u16.to_le_bytes());     // Data
+///
+/// let stamps = parse_notification_stream(&Bytes::from(stream.clone())).unwrap();
+/// assert_eq!(stamps[0].timestamp, 1000);
+/// assert_eq!(stamps[0].samples[0].not_hdl, 7);
+/// assert_eq!(&stamps[0].samples[0].data[..], &42u16.to_le_bytes());
+///
+/// // Truncated data
+/// for len in 0..stream.len() {
+///     assert!(parse_notification_stream(&Bytes::copy_from_slice(&stream[..len])).is_err());
+/// }
+///
+/// // Sample size exceeds the stream
+/// stream[26] = 0xFF;
+/// assert!(parse_notification_stream(&Bytes::from(stream)).is_err());
+/// ```
+pub fn parse_notification_stream(stream: &Bytes) -> Result<Vec<Stamp>> {
+    let (mut cursor, n_stamps) = Cursor::new(stream)?;
+
+    // Each stamp occupies at least its header, limits the allocation for bogus numbers
+    let mut stamps = Vec::with_capacity((n_stamps as usize).min(cursor.buf.len() / LEN_STAMP_HEADER));
+
+    for _ in 0..n_stamps {
+        let (timestamp, n_samples) = cursor.stamp_header()?;
+        let remaining = cursor.buf.len() - cursor.pos;
+        let mut samples = Vec::with_capacity((n_samples as usize).min(remaining / LEN_SAMPLE_HEADER));
+
+        for _ in 0..n_samples {
+            let (not_hdl, offset, data) = cursor.sample()?;
+            samples.push(StampSample { not_hdl, data : stream.slice(offset..offset + data.len()) });
+        }
+
+        stamps.push(Stamp { timestamp, samples });
+    }
+
+    Ok(stamps)
+}
+
+/// Sample of the notification stream, borrowing its data from the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct StreamSample<'a> {
+    /// Index of the stamp within the stream
+    pub stamp       : u32,
+    pub timestamp   : u64,
+    pub not_hdl     : u32,
+    pub data        : &'a [u8]
+}
+
+/// Iterates over the samples of all stamps of a notification stream without allocation.
+///
+/// After an error, the iteration ends.
+#[derive(Debug, Clone)]
+pub(crate) struct SampleIter<'a> {
+    cursor          : Cursor<'a>,
+    stamps_left     : u32,
+    stamp           : u32,
+    samples_left    : u32,
+    timestamp       : u64,
+    failed          : bool
+}
+
+impl<'a> SampleIter<'a> {
+    pub fn new(stream: &'a [u8]) -> Result<Self> {
+        let (cursor, stamps_left) = Cursor::new(stream)?;
+        Ok(SampleIter { cursor, stamps_left, stamp : 0, samples_left : 0, timestamp : 0, failed : false })
+    }
+
+    fn next_sample(&mut self) -> Result<Option<StreamSample<'a>>> {
+        while self.samples_left == 0 {
+            if self.stamps_left == 0 {
+                return Ok(None);
+            }
+
+            (self.timestamp, self.samples_left) = self.cursor.stamp_header()?;
+            self.stamps_left -= 1;
+            self.stamp += 1;
+        }
+
+        let (not_hdl, _, data) = self.cursor.sample()?;
+        self.samples_left -= 1;
+        Ok(Some(StreamSample { stamp : self.stamp - 1, timestamp : self.timestamp, not_hdl, data }))
+    }
+}
+
+impl<'a> Iterator for SampleIter<'a> {
+    type Item = Result<StreamSample<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.next_sample() {
+            Ok(sample) => sample.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tokio::sync::mpsc;
+    use crate::{AdsCommand, AdsNotificationAttrib, AdsTransMode, NotificationSample, HEADER_SIZE};
+    use crate::tests::{test_client, receive, respond};
+
+    /// Encode the stamps as AdsNotificationStream.
+    fn encode(stamps: &[Stamp]) -> Vec<u8> {
+        let mut stream = (stamps.len() as u32).to_le_bytes().to_vec();
+
+        for stamp in stamps {
+            stream.extend(stamp.timestamp.to_le_bytes());
+            stream.extend((stamp.samples.len() as u32).to_le_bytes());
+
+            for sample in &stamp.samples {
+                stream.extend(sample.not_hdl.to_le_bytes());
+                stream.extend((sample.data.len() as u32).to_le_bytes());
+                stream.extend_from_slice(&sample.data);
+            }
+        }
+        [&(stream.len() as u32).to_le_bytes()[..], &stream].concat()
+    }
+
+    fn stamps() -> impl Strategy<Value = Vec<Stamp>> {
+        let sample = (any::<u32>(), prop::collection::vec(any::<u8>(), 0..16))
+                        .prop_map(|(not_hdl, data)| StampSample { not_hdl, data : Bytes::from(data) });
+        let stamp = (any::<u64>(), prop::collection::vec(sample, 0..4))
+                        .prop_map(|(timestamp, samples)| Stamp { timestamp, samples });
+        prop::collection::vec(stamp, 0..4)
+    }
+
+    /// Samples of all stamps via SampleIter, stops at the first error.
+    fn iterate(stream: &[u8]) -> Result<Vec<(u64, u32, Vec<u8>)>> {
+        SampleIter::new(stream)?
+            .map(|sample| sample.map(|sample| (sample.timestamp, sample.not_hdl, sample.data.to_vec())))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip(stamps in stamps()) {
+            let stream = encode(&stamps);
+            prop_assert_eq!(&parse_notification_stream(&Bytes::from(stream.clone())).unwrap(), &stamps);
+
+            let samples : Vec<(u64, u32, Vec<u8>)> = stamps.iter()
+                .flat_map(|stamp| stamp.samples.iter().map(|sample| (stamp.timestamp, sample.not_hdl, sample.data.to_vec())))
+                .collect();
+            prop_assert_eq!(iterate(&stream).unwrap(), samples);
+        }
+
+        #[test]
+        fn truncated_stream_is_rejected(stamps in stamps(), cut in any::<prop::sample::Index>()) {
+            let stream = encode(&stamps);
+            let len = cut.index(stream.len());
+
+            prop_assert!(parse_notification_stream(&Bytes::copy_from_slice(&stream[..len])).is_err());
+            prop_assert!(iterate(&stream[..len]).is_err());
+        }
+
+        #[test]
+        fn corrupted_length_is_rejected(stamps in stamps(), length in any::<u32>()) {
+            let mut stream = encode(&stamps);
+            prop_assume!(length as usize != stream.len() - 4);
+            stream[0..4].copy_from_slice(&length.to_le_bytes());
+
+            prop_assert!(parse_notification_stream(&Bytes::from(stream.clone())).is_err());
+            prop_assert!(iterate(&stream).is_err());
+        }
+
+        #[test]
+        fn corrupted_stream_is_consistent(stamps in stamps(), pos in any::<prop::sample::Index>(), value in any::<u8>()) {
+            let mut stream = encode(&stamps);
+            let pos = pos.index(stream.len());
+            stream[pos] = value;
+
+            // Either rejected as a whole or a valid stream which is contained in the frame
+            match parse_notification_stream(&Bytes::from(stream.clone())) {
+                Ok(parsed) => {
+                    let encoded = encode(&parsed);
+                    prop_assert!(stream[8..].starts_with(&encoded[8..]));
+                    prop_assert!(iterate(&stream).is_ok());
+                },
+                Err(_) => prop_assert!(iterate(&stream).is_err())
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn malformed_frame_is_not_delivered() {
+        let (client, mut remote, _) = test_client();
+
+        let (samples_tx, mut samples) = mpsc::unbounded_channel();
+        let attrib = AdsNotificationAttrib { cb_length : 2, trans_mode : AdsTransMode::OnChange, max_delay : 0, cycle_time : 0 };
+        let server = async {
+            let (header, _) = receive(&mut remote).await;
+            respond(&mut remote, &header, &[0u32.to_le_bytes(), 3u32.to_le_bytes()].concat()).await;
+        };
+        let (not_hdl, _) = tokio::join!(client.add_device_notification(0x4020, 0, &attrib, move | sample : NotificationSample | {
+            let _ = samples_tx.send(sample.data.to_vec());
+        }), server);
+        let not_hdl = not_hdl.unwrap();
+
+        let sample = |data: &[u8]| StampSample { not_hdl : 3, data : Bytes::copy_from_slice(data) };
+        let mut notification = [0u8; HEADER_SIZE];
+        notification[22..24].copy_from_slice(&(AdsCommand::DeviceNotification as u16).to_le_bytes());
+
+        // The second sample exceeds the stream, the valid first sample must not be delivered either
+        let mut malformed = encode(&[Stamp { timestamp : 1, samples : vec![sample(&[1, 0]), sample(&[2, 0])] }]);
+        let len = malformed.len();
+        malformed[len - 6..len - 2].copy_from_slice(&3u32.to_le_bytes());
+        respond(&mut remote, &notification, &malformed).await;
+
+        respond(&mut remote, &notification, &encode(&[Stamp { timestamp : 2, samples : vec![sample(&[3, 0])] }])).await;
+        assert_eq!(samples.recv().await.unwrap(), vec![3, 0]);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(samples.try_recv().is_err());
+
+        not_hdl.detach();
+    }
+}
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+use bytes::Bytes;
+use log::{debug, info, warn, error};
+use tokio::runtime;
+use tokio::sync::{broadcast, mpsc};
+use crate::{Client, Result, ClientEvent, AdsNotificationAttrib, AdsTransMode, NotCallback, NotDelivery, NotificationSample, IDX_GRP_SYM_VAL_BY_HDL, IDX_GRP_SYM_RELEASE_HDL, IDX_GRP_SYM_VERSION};
+
+impl Client {
+
+    /// Subscribe to the events of the client (see [ClientEvent]).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, ClientEvent, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851)
+    ///                         .set_online_change_detection(true)
+    ///                         .build().await?;
+    ///
+    ///     let mut events = ads_client.events();
+    ///
+    ///     while let Ok(event) = events.recv().await {
+    ///         match event {
+    ///             ClientEvent::OnlineChange { symbol_version } => println!("Online change, symbol version: {}", symbol_version),
+    ///             ClientEvent::Disconnected => println!("Connection lost"),
+    ///             ClientEvent::Reconnected => println!("Connection re-established"),
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn events(&self) -> broadcast::Receiver<ClientEvent> {
+        self.events.subscribe()
+    }
+
+    /// Register a notification on the symbol version and spawn the task which processes online changes.
+    pub(crate) async fn watch_online_change(&self) -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<u8>();
+
+        let attrib = AdsNotificationAttrib {
+            cb_length   : 1, // USINT
+            trans_mode  : AdsTransMode::OnChange,
+            max_delay   : 0,
+            cycle_time  : 0
+        };
+
+        let callback : NotCallback = Arc::new(move | sample : NotificationSample | {
+            if let Some(version) = sample.data.first() {
+                let _ = tx.send(*version);
+            }
+        });
+
+        self.add_notification(IDX_GRP_SYM_VERSION, 0, &attrib, callback, NotDelivery::Inline, None).await?;
+
+        // The sender is owned by the client, the task stops when the client is dropped
+        let client = self.detached();
+        runtime::Handle::current().spawn(async move {
+            // The first notification reports the current symbol version
+            let mut symbol_version : Option<u8> = None;
+
+            loop {
+                let new_version = tokio::select! {
+                    version = rx.recv() => match version {
+                        Some(version) => version,
+                        None => break
+                    },
+                    _ = client.released() => break
+                };
+
+                if symbol_version.is_some_and(| version | version != new_version) {
+                    client.process_online_change(new_version).await;
+                }
+                symbol_version = Some(new_version);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn process_online_change(&self, symbol_version: u8) {
+        info!("Online change detected - new symbol version: {}", symbol_version);
+
+        // Symbols and data types might have changed
+        *self.symbol_table.lock().expect("Threading Error") = None;
+
+        // Release the method handles of the previous project
+        for hdl in self.take_method_handles() {
+            if let Err(e) = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &hdl.to_ne_bytes()).await {
+                debug!("Failed to release method handle {}: {}", hdl, e);
+            }
+        }
+
+        // Re-acquire cached symbol handles
+        let symbols : Vec<(String, u32)> = { // LOCK
+            let sym_handles = self.sym_handles.lock().expect("Threading Error");
+            sym_handles.iter().map(| hdl | (hdl.symbol.clone(), hdl.handle)).collect()
+        }; // UNLOCK
+
+        let mut renewed : Vec<(u32, u32)> = Vec::new(); // (old handle, new handle)
+
+        for (symbol, old_hdl) in symbols {
+            let new_hdl = self.request_symbol_handle(&symbol).await;
+
+            let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+            let pos = sym_handles.iter().position(| hdl | hdl.symbol == symbol);
+
+            match (new_hdl, pos) {
+                (Ok(new_hdl), Some(pos)) => {
+                    debug!("Renewed handle of symbol {}: {} -> {}", symbol, old_hdl, new_hdl);
+                    sym_handles[pos].handle = new_hdl;
+                    renewed.push((old_hdl, new_hdl));
+                },
+                (Err(e), Some(pos)) => {
+                    // The symbol might have been removed by the online change
+                    warn!("Failed to renew handle of symbol {} - removed from cache: {}", symbol, e);
+                    sym_handles.swap_remove(pos);
+                },
+                (_, None) => debug!("Symbol {} was released during online change", symbol)
+            }
+        }
+
+        // Re-register notifications which refer to a renewed handle
+        let (mut affected, owned) = { // LOCK
+            let not_handles = self.not_handles.lock().expect("Threading Error");
+
+            let affected : Vec<(u32, u32, u32, AdsNotificationAttrib, bool)> = not_handles.iter()
+                .filter(| hdl | hdl.idx_grp == IDX_GRP_SYM_VAL_BY_HDL && hdl.symbol.is_none())
+                .filter_map(| hdl | {
+                    renewed.iter()
+                        .find(| (old_hdl, _) | *old_hdl == hdl.idx_offs)
+                        .map(| (_, new_hdl) | (hdl.id, hdl.not_hdl, *new_hdl, hdl.attrib, false))
+                })
+                .collect();
+
+            // Notifications which own their symbol handle (see Client::subscribe_symbol)
+            let owned : Vec<(u32, u32, String, AdsNotificationAttrib)> = not_handles.iter()
+                .filter_map(| hdl | hdl.symbol.clone().map(| symbol | (hdl.id, hdl.not_hdl, symbol, hdl.attrib)))
+                .collect();
+
+            (affected, owned)
+        }; // UNLOCK
+
+        for (id, not_hdl, symbol, attrib) in owned {
+            match self.request_symbol_handle(&symbol).await {
+                Ok(new_hdl) => affected.push((id, not_hdl, new_hdl, attrib, true)),
+                Err(e) => warn!("Failed to renew handle of symbol {} for notification {}: {}", symbol, id, e)
+            }
+        }
+
+        for (id, not_hdl, new_hdl, attrib, owned) in affected {
+            // The old notification is usually already invalid
+            if let Err(e) = self.request_delete_device_notification(not_hdl).await {
+                debug!("Failed to delete outdated notification {}: {}", not_hdl, e);
+            }
+
+            match self.request_device_notification(IDX_GRP_SYM_VAL_BY_HDL, new_hdl, &attrib).await {
+                Ok(new_not_hdl) => {
+                    let registered = { // LOCK
+                        let mut not_handles = self.not_handles.lock().expect("Threading Error");
+
+                        not_handles.iter_mut()
+                            .find(| hdl | hdl.id == id)
+                            .map(| hdl | {
+                                hdl.not_hdl  = new_not_hdl;
+                                hdl.idx_offs = new_hdl;
+                            })
+                            .is_some()
+                    }; // UNLOCK
+
+                    // Deleted by the user in the meantime
+                    if !registered {
+                        let _ = self.request_delete_device_notification(new_not_hdl).await;
+
+                        if owned {
+                            let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &new_hdl.to_ne_bytes()).await;
+                        }
+                    }
+                },
+                Err(e) => {
+                    error!("Failed to re-register notification {}: {}", id, e);
+
+                    if owned {
+                        let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &new_hdl.to_ne_bytes()).await;
+                    }
+                }
+            }
+        }
+
+        let _ = self.events.send(ClientEvent::OnlineChange { symbol_version });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SymHandle, AdsCommand, IDX_GRP_SYM_HDL_BY_NAME};
+    use crate::tests::{test_client, receive, respond};
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn online_change_releases_method_handles() {
+        let (client, mut remote, _) = test_client();
+        let mut events = client.events();
+
+        client.sym_handles.lock().unwrap().extend([
+            SymHandle { symbol : String::from("MAIN.fbAxis#MoveAbs"), handle : 33 },
+            SymHandle { symbol : String::from("MAIN.n_cnt_a"), handle : 11 }
+        ]);
+
+        let server = async {
+            let (header, payload) = receive(&mut remote).await;
+            assert!(matches!(Client::extract_cmd_tyte(&header).unwrap(), AdsCommand::Write));
+            assert_eq!(&payload[0..4], &IDX_GRP_SYM_RELEASE_HDL.to_le_bytes());
+            assert_eq!(&payload[12..], &33u32.to_ne_bytes());
+            respond(&mut remote, &header, &0u32.to_le_bytes()).await;
+
+            let (header, payload) = receive(&mut remote).await;
+            assert_eq!(&payload[0..4], &IDX_GRP_SYM_HDL_BY_NAME.to_le_bytes());
+            assert_eq!(&payload[16..], b"MAIN.n_cnt_a");
+            respond(&mut remote, &header, &[0u32.to_le_bytes(), 4u32.to_le_bytes(), 12u32.to_le_bytes()].concat()).await;
+        };
+        tokio::join!(client.process_online_change(2), server);
+
+        assert_eq!(events.recv().await.unwrap(), ClientEvent::OnlineChange { symbol_version : 2 });
+
+        let cached : Vec<(String, u32)> = client.sym_handles.lock().unwrap().iter().map(| hdl | (hdl.symbol.clone(), hdl.handle)).collect();
+        assert_eq!(cached, [(String::from("MAIN.n_cnt_a"), 12)]);
+    }
+}
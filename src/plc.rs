@@ -0,0 +1,163 @@
+//! Control of a PLC runtime.
+use std::time::Duration;
+use log::{debug, info};
+use tokio::sync::watch;
+use tokio::time::timeout;
+use crate::{Client, Result, AdsError, AdsErrorCode, AdsState, StateInfo};
+
+/// Maximum duration until the PLC runtime reports the new state
+const PLC_CONTROL_TIMEOUT   : Duration = Duration::from_secs(10);
+
+// Device states of ADS Write Control with AdsState::Reset
+const DEVICE_STATE_COLD     : u16 = 0;
+const DEVICE_STATE_ORIGIN   : u16 = 1;
+
+/// Start, stop and reset of a PLC runtime (port 851, 852, ...).
+///
+/// Each operation submits an [ADS Write Control](Client::write_control) request to the port of the client
+/// and waits until the PLC reports the expected state. The state is observed with [Client::watch_state],
+/// hence short transitions (e.g. of a reset) are noticed as well. A warm reset is not supported by TwinCAT 3.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ads_client::{ClientBuilder, Plc, Result};
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+///     let plc = Plc::new(ads_client);
+///
+///     plc.stop().await?;
+///     plc.reset_cold().await?;
+///     plc.start().await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Plc {
+    client : Client
+}
+
+impl Plc {
+    /// The client has to be connected to the port of the PLC runtime.
+    pub fn new(client: Client) -> Self {
+        Plc { client }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Current state of the PLC runtime.
+    pub async fn state(&self) -> Result<StateInfo> {
+        self.client.read_state().await
+    }
+
+    async fn control(&self, ads_state: AdsState, device_state: u16, expected: AdsState) -> Result<()> {
+        info!("PLC control: {:?} (device state {})", ads_state, device_state);
+
+        let mut states = match self.client.watch_state().await {
+            Ok(states) => states,
+            Err(e) => {
+                debug!("ADS state notification not available, the state is polled: {}", e);
+                self.client.write_control(&StateInfo { ads_state, device_state }, None).await?;
+                return self.client.wait_for_state(expected, PLC_CONTROL_TIMEOUT).await;
+            }
+        };
+        let current = states.borrow_and_update().ads_state;
+
+        self.client.write_control(&StateInfo { ads_state, device_state }, None).await?;
+
+        // A reset of a stopped PLC ends in the state it started from
+        let transition = current == expected && ads_state == AdsState::Reset;
+
+        timeout(PLC_CONTROL_TIMEOUT, Plc::wait_for_transition(&mut states, transition, expected)).await
+            .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT.into(), s_msg : format!("PLC did not reach {:?} within {:?} - last state: {:?}", expected, PLC_CONTROL_TIMEOUT, states.borrow().ads_state)})?
+    }
+
+    /// Wait for a notified change of the state if `transition` is set, then until the state is `expected`.
+    async fn wait_for_transition(states: &mut watch::Receiver<StateInfo>, transition: bool, expected: AdsState) -> Result<()> {
+        let closed = |_| AdsError{n_error : AdsErrorCode::ERR_PORTNOTCONNECTED.into(), s_msg : String::from("ADS state notification closed")};
+
+        if transition {
+            states.changed().await.map_err(closed)?;
+            debug!("PLC state changed: {:?}", states.borrow().ads_state);
+        }
+
+        while states.borrow_and_update().ads_state != expected {
+            states.changed().await.map_err(closed)?;
+        }
+        Ok(())
+    }
+
+    /// Start the PLC and wait until it is in [AdsState::Run].
+    pub async fn start(&self) -> Result<()> {
+        self.control(AdsState::Run, 0, AdsState::Run).await
+    }
+
+    /// Stop the PLC and wait until it is in [AdsState::Stop].
+    pub async fn stop(&self) -> Result<()> {
+        self.control(AdsState::Stop, 0, AdsState::Stop).await
+    }
+
+    /// Reset cold: all variables are initialized except persistent variables.
+    ///
+    /// The PLC is in [AdsState::Stop] afterwards. If the PLC was already stopped, 
+    /// a change of its state has to be notified before.
+    pub async fn reset_cold(&self) -> Result<()> {
+        self.control(AdsState::Reset, DEVICE_STATE_COLD, AdsState::Stop).await
+    }
+
+    /// Reset origin: all variables including persistent variables are initialized.
+    ///
+    /// The PLC is in [AdsState::Stop] afterwards. If the PLC was already stopped, 
+    /// a change of its state has to be notified before.
+    pub async fn reset_origin(&self) -> Result<()> {
+        self.control(AdsState::Reset, DEVICE_STATE_ORIGIN, AdsState::Stop).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::{AdsCommand, AdsState, Plc};
+    use crate::tests::{test_client, receive, respond, notify};
+
+    fn state(ads_state: AdsState) -> Vec<u8> {
+        [(ads_state as u16).to_le_bytes(), [0, 0]].concat()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reset_of_stopped_plc_waits_for_transition() {
+        let (client, mut remote, _) = test_client();
+        let plc = Plc::new(client);
+        let reset = plc.reset_cold();
+        tokio::pin!(reset);
+
+        let server = async {
+            let (header, _) = receive(&mut remote).await;
+            respond(&mut remote, &header, &[&[0; 4][..], &state(AdsState::Stop)].concat()).await;
+
+            let (header, _) = receive(&mut remote).await;
+            assert_eq!(u16::from_le_bytes([header[22], header[23]]), AdsCommand::AddDeviceNotification as u16);
+            respond(&mut remote, &header, &[0, 0, 0, 0, 3, 0, 0, 0]).await;
+            notify(&mut remote, 3, &state(AdsState::Stop)).await;
+
+            let (header, payload) = receive(&mut remote).await;
+            assert_eq!(u16::from_le_bytes([header[22], header[23]]), AdsCommand::WriteControl as u16);
+            assert_eq!(&payload[0..4], &[2, 0, 0, 0]); // Reset, cold
+            respond(&mut remote, &header, &[0; 4]).await;
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        };
+
+        tokio::select! {
+            result = &mut reset => panic!("Reset reported without a transition: {:?}", result),
+            _ = server => ()
+        }
+
+        notify(&mut remote, 3, &state(AdsState::Reset)).await;
+        notify(&mut remote, 3, &state(AdsState::Stop)).await;
+        reset.await.unwrap();
+    }
+}
@@ -0,0 +1,546 @@
+//! Conversion between Rust types and the memory layout of the IEC 61131-3 elementary data types.
+use std::time::Duration;
+use crate::{Result, AdsError, AdsErrorCode};
+
+/// PLC data type with a fixed size.
+/// 
+/// For structs and enums, the implementation can be derived with `#[derive(AdsType)]` 
+/// (requires the `derive` feature, enabled by default).
+///
+/// The derive fails to compile if the size differs from `#[ads(size = n)]`,
+///
+/// ```rust,compile_fail,E0080
+/// # use ads_client::AdsType;
+/// #[derive(AdsType)]
+/// #[ads(size = 12)]
+/// struct Axis { enabled : bool, position : f64 }
+/// ```
+///
+/// for pack modes other than 0, 1, 2, 4 and 8
+///
+/// ```rust,compile_fail
+/// # use ads_client::AdsType;
+/// #[derive(AdsType)]
+/// #[ads(pack_mode = 3)]
+/// struct Axis { enabled : bool, position : f64 }
+/// ```
+///
+/// and for enums with a `repr` other than an integer type of at most 64 bit.
+///
+/// ```rust,compile_fail
+/// # use ads_client::AdsType;
+/// #[derive(AdsType)]
+/// #[repr(u128)]
+/// enum State { Idle, Moving }
+/// ```
+pub trait AdsType {
+    /// Size of the data type in the PLC \[byte\].
+    const SIZE: usize;
+    /// Natural alignment of the data type in the PLC \[byte\].
+    const ALIGN: usize;
+}
+
+/// Conversion of a Rust value into the memory layout of the PLC.
+///
+/// The length of `buf` must be equal to [AdsType::SIZE].
+pub trait AdsEncode: AdsType {
+    fn encode(&self, buf: &mut [u8]) -> Result<()>;
+}
+
+/// Conversion of PLC memory into a Rust value.
+///
+/// The length of `buf` must be equal to [AdsType::SIZE].
+pub trait AdsDecode: AdsType + Sized {
+    fn decode(buf: &[u8]) -> Result<Self>;
+}
+
+pub(crate) fn size_error(expected: usize, actual: usize) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDSIZE.into(), s_msg : format!("Parameter size not correct - expected {} byte, got {} byte", expected, actual)}
+}
+
+fn check_size<T: AdsType>(len: usize) -> Result<()> {
+    if len != T::SIZE {
+        return Err(size_error(T::SIZE, len));
+    }
+    Ok(())
+}
+
+pub(crate) fn range_error(s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_OUTOFRANGE.into(), s_msg : String::from(s_msg)}
+}
+
+// BYTE/USINT, WORD/UINT, DWORD/UDINT, LWORD/ULINT, SINT, INT, DINT, LINT, REAL, LREAL
+macro_rules! impl_ads_number {
+    ($($t:ty),*) => {
+        $(
+            impl AdsType for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                const ALIGN: usize = std::mem::size_of::<$t>();
+            }
+
+            impl AdsEncode for $t {
+                fn encode(&self, buf: &mut [u8]) -> Result<()> {
+                    check_size::<Self>(buf.len())?;
+                    buf.copy_from_slice(&self.to_le_bytes());
+                    Ok(())
+                }
+            }
+
+            impl AdsDecode for $t {
+                fn decode(buf: &[u8]) -> Result<Self> {
+                    check_size::<Self>(buf.len())?;
+                    Ok(<$t>::from_le_bytes(buf.try_into()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_ads_number!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+// No data (e.g. method without parameters)
+impl AdsType for () {
+    const SIZE: usize = 0;
+    const ALIGN: usize = 1;
+}
+
+impl AdsEncode for () {
+    fn encode(&self, buf: &mut [u8]) -> Result<()> {
+        check_size::<Self>(buf.len())
+    }
+}
+
+impl AdsDecode for () {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        check_size::<Self>(buf.len())
+    }
+}
+
+// BOOL
+impl AdsType for bool {
+    const SIZE: usize = 1;
+    const ALIGN: usize = 1;
+}
+
+impl AdsEncode for bool {
+    fn encode(&self, buf: &mut [u8]) -> Result<()> {
+        check_size::<Self>(buf.len())?;
+        buf[0] = u8::from(*self);
+        Ok(())
+    }
+}
+
+impl AdsDecode for bool {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        check_size::<Self>(buf.len())?;
+        Ok(buf[0] != 0)
+    }
+}
+
+// ARRAY[..] OF T
+impl<T: AdsType, const N: usize> AdsType for [T; N] {
+    const SIZE: usize = N * T::SIZE;
+    const ALIGN: usize = T::ALIGN;
+}
+
+impl<T: AdsEncode, const N: usize> AdsEncode for [T; N] {
+    fn encode(&self, buf: &mut [u8]) -> Result<()> {
+        check_size::<Self>(buf.len())?;
+
+        for (idx, item) in self.iter().enumerate() {
+            item.encode(&mut buf[idx * T::SIZE..(idx + 1) * T::SIZE])?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: AdsDecode, const N: usize> AdsDecode for [T; N] {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        check_size::<Self>(buf.len())?;
+
+        let items = (0..N).map(|idx| T::decode(&buf[idx * T::SIZE..(idx + 1) * T::SIZE]))
+                        .collect::<Result<Vec<T>>>()?;
+
+        items.try_into().map_err(|_| size_error(N, 0))
+    }
+}
+
+/// Encode `s` as ISO 8859-1, the remaining bytes of `buf` are filled with null characters.
+pub(crate) fn encode_string(s: &str, buf: &mut [u8]) -> Result<()> {
+    buf.fill(0);
+
+    for (idx, c) in s.chars().enumerate() {
+        // The last byte is reserved for the null terminator
+        if idx + 1 >= buf.len() {
+            return Err(range_error("String exceeds the length of the PLC string"));
+        }
+        buf[idx] = u8::try_from(u32::from(c)).map_err(|_| range_error("Character not representable in ISO 8859-1"))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_string(buf: &[u8]) -> String {
+    buf.iter().take_while(|c| **c != 0).map(|c| char::from(*c)).collect()
+}
+
+/// Encode `s` as UTF-16, the remaining bytes of `buf` are filled with null characters.
+pub(crate) fn encode_wstring(s: &str, buf: &mut [u8]) -> Result<()> {
+    buf.fill(0);
+
+    for (idx, c) in s.encode_utf16().enumerate() {
+        if 2 * idx + 2 >= buf.len() {
+            return Err(range_error("String exceeds the length of the PLC string"));
+        }
+        buf[2 * idx..2 * idx + 2].copy_from_slice(&c.to_le_bytes());
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_wstring(buf: &[u8]) -> Result<String> {
+    let chars : Vec<u16> = buf.chunks_exact(2)
+                            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                            .take_while(|c| *c != 0)
+                            .collect();
+
+    String::from_utf16(&chars)
+        .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("Invalid UTF-16 data in WSTRING")})
+}
+
+/// `STRING(N)` - String of `N` characters, encoded as ISO 8859-1 and terminated by a null character.
+///
+/// The default `STRING` of TwinCAT corresponds to `PlcString<80>`.
+///
+/// # Example
+///
+/// ```rust
+/// use ads_client::{PlcString, AdsType, AdsEncode, AdsDecode};
+///
+/// let s_plc = PlcString::<10>::from("Grüße");
+/// let mut buf = [0; PlcString::<10>::SIZE];
+///
+/// s_plc.encode(&mut buf).unwrap();
+/// assert_eq!(&buf[..6], b"Gr\xfc\xdfe\0");
+/// assert_eq!(PlcString::<10>::decode(&buf).unwrap().as_str(), "Grüße");
+///
+/// // The string exceeds the length of the PLC string
+/// assert!(PlcString::<3>::from("Hello").encode(&mut [0; 4]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PlcString<const N: usize>(pub String);
+
+impl<const N: usize> PlcString<N> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<&str> for PlcString<N> {
+    fn from(s: &str) -> Self {
+        PlcString(String::from(s))
+    }
+}
+
+impl<const N: usize> From<String> for PlcString<N> {
+    fn from(s: String) -> Self {
+        PlcString(s)
+    }
+}
+
+impl<const N: usize> AdsType for PlcString<N> {
+    const SIZE: usize = N + 1;
+    const ALIGN: usize = 1;
+}
+
+impl<const N: usize> AdsEncode for PlcString<N> {
+    fn encode(&self, buf: &mut [u8]) -> Result<()> {
+        check_size::<Self>(buf.len())?;
+        encode_string(&self.0, buf)
+    }
+}
+
+impl<const N: usize> AdsDecode for PlcString<N> {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        check_size::<Self>(buf.len())?;
+        Ok(PlcString(decode_string(buf)))
+    }
+}
+
+/// `WSTRING(N)` - String of `N` characters, encoded as UTF-16 and terminated by a null character.
+///
+/// The default `WSTRING` of TwinCAT corresponds to `PlcWString<80>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PlcWString<const N: usize>(pub String);
+
+impl<const N: usize> PlcWString<N> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<&str> for PlcWString<N> {
+    fn from(s: &str) -> Self {
+        PlcWString(String::from(s))
+    }
+}
+
+impl<const N: usize> From<String> for PlcWString<N> {
+    fn from(s: String) -> Self {
+        PlcWString(s)
+    }
+}
+
+impl<const N: usize> AdsType for PlcWString<N> {
+    const SIZE: usize = 2 * (N + 1);
+    const ALIGN: usize = 2;
+}
+
+impl<const N: usize> AdsEncode for PlcWString<N> {
+    fn encode(&self, buf: &mut [u8]) -> Result<()> {
+        check_size::<Self>(buf.len())?;
+        encode_wstring(&self.0, buf)
+    }
+}
+
+impl<const N: usize> AdsDecode for PlcWString<N> {
+    fn decode(buf: &[u8]) -> Result<Self> {
+        check_size::<Self>(buf.len())?;
+        decode_wstring(buf).map(PlcWString)
+    }
+}
+
+/// `TIME` - Duration in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Time(pub u32);
+
+/// `LTIME` - Duration in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LTime(pub u64);
+
+/// `TIME_OF_DAY` / `TOD` - Milliseconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TimeOfDay(pub u32);
+
+/// `DATE` - Seconds since 1970-01-01 (always a multiple of one day).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Date(pub u32);
+
+/// `DATE_AND_TIME` / `DT` - Seconds since 1970-01-01 00:00:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct DateAndTime(pub u32);
+
+macro_rules! impl_ads_newtype {
+    ($($t:ident($inner:ty)),*) => {
+        $(
+            impl AdsType for $t {
+                const SIZE: usize = <$inner as AdsType>::SIZE;
+                const ALIGN: usize = <$inner as AdsType>::ALIGN;
+            }
+
+            impl AdsEncode for $t {
+                fn encode(&self, buf: &mut [u8]) -> Result<()> {
+                    self.0.encode(buf)
+                }
+            }
+
+            impl AdsDecode for $t {
+                fn decode(buf: &[u8]) -> Result<Self> {
+                    <$inner>::decode(buf).map($t)
+                }
+            }
+        )*
+    };
+}
+
+impl_ads_newtype!(Time(u32), LTime(u64), TimeOfDay(u32), Date(u32), DateAndTime(u32));
+
+impl From<Time> for Duration {
+    fn from(time: Time) -> Self {
+        Duration::from_millis(u64::from(time.0))
+    }
+}
+
+impl TryFrom<Duration> for Time {
+    type Error = AdsError;
+
+    fn try_from(duration: Duration) -> Result<Self> {
+        u32::try_from(duration.as_millis())
+            .map(Time)
+            .map_err(|_| range_error("Duration exceeds the range of TIME"))
+    }
+}
+
+impl From<LTime> for Duration {
+    fn from(time: LTime) -> Self {
+        Duration::from_nanos(time.0)
+    }
+}
+
+impl TryFrom<Duration> for LTime {
+    type Error = AdsError;
+
+    fn try_from(duration: Duration) -> Result<Self> {
+        u64::try_from(duration.as_nanos())
+            .map(LTime)
+            .map_err(|_| range_error("Duration exceeds the range of LTIME"))
+    }
+}
+
+impl From<TimeOfDay> for Duration {
+    fn from(tod: TimeOfDay) -> Self {
+        Duration::from_millis(u64::from(tod.0))
+    }
+}
+
+impl TryFrom<Duration> for TimeOfDay {
+    type Error = AdsError;
+
+    fn try_from(duration: Duration) -> Result<Self> {
+        if duration >= Duration::from_secs(24 * 60 * 60) {
+            return Err(range_error("Duration exceeds the range of TIME_OF_DAY"));
+        }
+        Ok(TimeOfDay(duration.as_millis() as u32))
+    }
+}
+
+/// Helper for `#[derive(AdsType)]`, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use crate::AdsError;
+
+    pub fn size_error(expected: usize, actual: usize) -> AdsError {
+        super::size_error(expected, actual)
+    }
+
+    /// Effective alignment of a member with the given natural alignment.
+    pub const fn align_of(align: usize, pack_mode: usize) -> usize {
+        if align < pack_mode { align } else { pack_mode }
+    }
+
+    /// Move `offset` to the next multiple of `align`.
+    pub const fn align_up(offset: usize, align: usize) -> usize {
+        if align <= 1 { offset } else { offset.div_ceil(align) * align }
+    }
+
+    pub const fn max(a: usize, b: usize) -> usize {
+        if a > b { a } else { b }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<T: AdsEncode>(value: &T) -> Result<Vec<u8>> {
+        let mut buf = vec![0; T::SIZE];
+        value.encode(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(encode(&0x1234u16).unwrap(), [0x34, 0x12]);
+        assert_eq!(encode(&-2i32).unwrap(), [0xFE, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(encode(&1.0f32).unwrap(), [0x00, 0x00, 0x80, 0x3F]);
+        assert_eq!(encode(&true).unwrap(), [1]);
+
+        assert_eq!(u64::decode(&encode(&u64::MAX).unwrap()).unwrap(), u64::MAX);
+        assert_eq!(i16::decode(&[0x00, 0x80]).unwrap(), i16::MIN);
+        assert_eq!(f64::decode(&encode(&-0.5f64).unwrap()).unwrap(), -0.5);
+        assert!(bool::decode(&[2]).unwrap());
+        assert!(!bool::decode(&[0]).unwrap());
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let err = 1u32.encode(&mut [0; 2]).unwrap_err();
+        assert_eq!(err.n_error, AdsErrorCode::ADSERR_DEVICE_INVALIDSIZE.into());
+
+        assert!(u16::decode(&[0; 3]).is_err());
+        assert!(<()>::decode(&[0]).is_err());
+        assert!(<[u8; 2]>::decode(&[0; 3]).is_err());
+        assert!(PlcString::<3>::decode(&[0; 3]).is_err());
+        assert!(PlcWString::<3>::from("a").encode(&mut [0; 7]).is_err());
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(<[u16; 3]>::SIZE, 6);
+        assert_eq!(<[u16; 3]>::ALIGN, 2);
+
+        let buf = encode(&[1u16, 2, 3]).unwrap();
+        assert_eq!(buf, [1, 0, 2, 0, 3, 0]);
+        assert_eq!(<[u16; 3]>::decode(&buf).unwrap(), [1, 2, 3]);
+
+        let nested = [[1i8, -1], [2, -2]];
+        assert_eq!(<[[i8; 2]; 2]>::decode(&encode(&nested).unwrap()).unwrap(), nested);
+    }
+
+    #[test]
+    fn plc_string() {
+        assert_eq!(PlcString::<80>::SIZE, 81);
+
+        let buf = encode(&PlcString::<5>::from("ab")).unwrap();
+        assert_eq!(buf, b"ab\0\0\0\0");
+        assert_eq!(PlcString::<5>::decode(&buf).unwrap().as_str(), "ab");
+
+        // Full length, the terminator is still written
+        assert_eq!(encode(&PlcString::<3>::from("abc")).unwrap(), b"abc\0");
+        assert!(encode(&PlcString::<3>::from("abcd")).is_err());
+
+        // Characters outside ISO 8859-1
+        let err = encode(&PlcString::<5>::from("€")).unwrap_err();
+        assert_eq!(err.n_error, AdsErrorCode::ADSERR_DEVICE_OUTOFRANGE.into());
+        assert_eq!(encode(&PlcString::<5>::from("ÿ")).unwrap()[0], 0xFF);
+
+        // Bytes after the terminator are ignored
+        assert_eq!(PlcString::<5>::decode(b"ab\0cd\0").unwrap().as_str(), "ab");
+    }
+
+    #[test]
+    fn plc_wstring() {
+        assert_eq!(PlcWString::<80>::SIZE, 162);
+
+        let buf = encode(&PlcWString::<3>::from("a€")).unwrap();
+        assert_eq!(buf, [0x61, 0x00, 0xAC, 0x20, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(PlcWString::<3>::decode(&buf).unwrap().as_str(), "a€");
+
+        // Surrogate pairs take two code units
+        assert_eq!(PlcWString::<2>::decode(&encode(&PlcWString::<2>::from("😀")).unwrap()).unwrap().as_str(), "😀");
+        assert!(encode(&PlcWString::<1>::from("😀")).is_err());
+        assert!(encode(&PlcWString::<2>::from("abc")).is_err());
+
+        // Unpaired surrogate
+        let err = PlcWString::<1>::decode(&[0x00, 0xD8, 0x00, 0x00]).unwrap_err();
+        assert_eq!(err.n_error, AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Duration::from(Time(1500)), Duration::from_millis(1500));
+        assert_eq!(Time::try_from(Duration::from_micros(1999)).unwrap(), Time(1));
+        assert!(Time::try_from(Duration::from_millis(u64::from(u32::MAX) + 1)).is_err());
+
+        assert_eq!(Duration::from(LTime(1)), Duration::from_nanos(1));
+        assert_eq!(LTime::try_from(Duration::from_secs(2)).unwrap(), LTime(2_000_000_000));
+        assert!(LTime::try_from(Duration::MAX).is_err());
+
+        let last = Duration::from_millis(24 * 60 * 60 * 1000 - 1);
+        assert_eq!(TimeOfDay::try_from(last).unwrap(), TimeOfDay(86_399_999));
+        assert!(TimeOfDay::try_from(Duration::from_secs(24 * 60 * 60)).is_err());
+
+        assert_eq!(encode(&Time(0x0102_0304)).unwrap(), [4, 3, 2, 1]);
+        assert_eq!(LTime::decode(&encode(&LTime(u64::MAX)).unwrap()).unwrap(), LTime(u64::MAX));
+        assert_eq!(DateAndTime::decode(&[1, 0, 0, 0]).unwrap(), DateAndTime(1));
+    }
+
+    #[test]
+    fn layout_helpers() {
+        use __private::{align_of, align_up};
+
+        assert_eq!(align_of(8, 1), 1);
+        assert_eq!(align_of(2, 8), 2);
+        assert_eq!(align_up(5, 4), 8);
+        assert_eq!(align_up(8, 4), 8);
+        assert_eq!(align_up(5, 1), 5);
+    }
+}
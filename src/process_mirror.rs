@@ -0,0 +1,162 @@
+//! Local copy of PLC variables, updated by notifications.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use tokio::sync::watch;
+use crate::{Client, Result, AdsError, AdsErrorCode, AdsDecode, AdsNotificationAttribBuilder, AdsTransMode, NotificationHandle, NotificationSample, NotCallback, NotDelivery};
+
+/// Latest value of a symbol in a [ProcessMirror].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorValue {
+    pub data        : Bytes,
+    /// Time stamp of the notification as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    /// Local time of reception
+    pub received    : Instant
+}
+
+impl MirrorValue {
+    /// Time since the reception of the value.
+    pub fn age(&self) -> Duration {
+        self.received.elapsed()
+    }
+
+    /// Decode the value as `T`.
+    pub fn decode<T: AdsDecode>(&self) -> Result<T> {
+        T::decode(&self.data)
+    }
+}
+
+/// Latest values of a set of symbols, kept up to date by `OnChange` notifications.
+///
+/// Created by [Client::process_mirror]. Reading a value does not submit a request to the target.
+/// The notifications and symbol handles are released when the mirror is dropped.
+#[derive(Debug)]
+pub struct ProcessMirror {
+    symbols : Vec<String>,
+    values  : Arc<Mutex<HashMap<String, MirrorValue>>>,
+    updates : watch::Receiver<u64>,
+    handles : Vec<NotificationHandle>
+}
+
+impl ProcessMirror {
+    /// Symbols of the mirror in the order of registration.
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Latest value of `symbol`, `None` if no notification has been received yet.
+    pub fn get(&self, symbol: &str) -> Option<MirrorValue> {
+        self.values.lock().expect("Threading Error").get(symbol).cloned()
+    }
+
+    /// Latest value of `symbol` decoded as `T`.
+    pub fn value<T: AdsDecode>(&self, symbol: &str) -> Result<T> {
+        self.get(symbol)
+            .ok_or_else(|| AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_NOTREADY.into(), s_msg : format!("No value received for symbol {}", symbol)})?
+            .decode()
+    }
+
+    /// Latest values of all symbols, taken at the same point in time.
+    ///
+    /// Symbols without a value are missing in the snapshot.
+    pub fn snapshot(&self) -> HashMap<String, MirrorValue> {
+        self.values.lock().expect("Threading Error").clone()
+    }
+
+    /// Symbols without a value or with a value older than `max_age`.
+    ///
+    /// Note that `OnChange` notifications are only sent if the value changes, hence
+    /// a constant value becomes stale as well.
+    pub fn stale(&self, max_age: Duration) -> Vec<String> {
+        let values = self.values.lock().expect("Threading Error");
+
+        self.symbols.iter()
+            .filter(| symbol | values.get(*symbol).is_none_or(| value | value.age() > max_age))
+            .cloned()
+            .collect()
+    }
+
+    /// Wait until any value of the mirror is updated.
+    pub async fn changed(&mut self) -> Result<()> {
+        self.updates.changed().await
+            .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_REMOVEHASH.into(), s_msg : String::from("Notifications of the process mirror removed")})
+    }
+
+    /// Delete all notifications and release the symbol handles.
+    pub async fn close(self) -> Result<()> {
+        let mut result = Ok(());
+
+        for handle in self.handles {
+            if let Err(err) = handle.unsubscribe().await {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+}
+
+impl Client {
+
+    /// Mirror the values of `symbols` locally (see [ProcessMirror]).
+    ///
+    /// For each symbol, a symbol handle is acquired and an `OnChange` notification is registered.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let mut mirror = ads_client.process_mirror(&["MAIN.n_cnt_a", "MAIN.n_cnt_b"]).await?;
+    ///
+    ///     loop {
+    ///         mirror.changed().await?;
+    ///
+    ///         for (symbol, value) in mirror.snapshot() {
+    ///             println!("{}: {}", symbol, value.decode::<u16>()?);
+    ///         }
+    ///
+    ///         for symbol in mirror.stale(Duration::from_secs(10)) {
+    ///             println!("{} not updated for 10 s", symbol);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn process_mirror(&self, symbols: &[&str]) -> Result<ProcessMirror> {
+        let values = Arc::new(Mutex::new(HashMap::<String, MirrorValue>::new()));
+        let (tx, updates) = watch::channel(0u64);
+        let tx = Arc::new(tx);
+
+        let mut handles = Vec::with_capacity(symbols.len());
+
+        for symbol in symbols {
+            let info = self.symbol_info(symbol).await?;
+            let attributes = AdsNotificationAttribBuilder::new(info.size, AdsTransMode::OnChange).build()?;
+
+            let (values, tx, name) = (Arc::clone(&values), Arc::clone(&tx), String::from(*symbol));
+
+            // Called by the socket reader, only updates the map
+            let callback : NotCallback = Arc::new(move | sample : NotificationSample | {
+                let value = MirrorValue { data : sample.data, timestamp : sample.timestamp, received : Instant::now() };
+
+                values.lock().expect("Threading Error").insert(name.clone(), value);
+                tx.send_modify(| version | *version = version.wrapping_add(1));
+            });
+
+            // Notifications registered so far are released by the drop of `handles` on error
+            handles.push(self.add_symbol_notification(symbol, &attributes, callback, NotDelivery::Inline).await?);
+        }
+
+        Ok(ProcessMirror {
+            symbols : symbols.iter().map(| symbol | String::from(*symbol)).collect(),
+            values,
+            updates,
+            handles
+        })
+    }
+}
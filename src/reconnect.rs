@@ -0,0 +1,200 @@
+//! Re-establishment of a lost connection.
+use std::time::Duration;
+use log::{debug, info, warn, error};
+use tokio::io::ReadHalf;
+use tokio::time::sleep;
+use crate::{Client, Result, AdsError, AdsErrorCode, ClientEvent, AdsStream, Transport, IDX_GRP_SYM_VAL_BY_HDL, IDX_GRP_SYM_RELEASE_HDL};
+
+/// Delay between attempts to reconnect if no retry delay is set
+pub(crate) const RECONNECT_DELAY : Duration = Duration::from_secs(1);
+
+/// Errors which are expected while the target is starting up
+fn is_transient(err: &AdsError) -> bool {
+    err.n_error < AdsErrorCode::ADSERR_DEVICE_ERROR.into() // AMS and router errors
+        || err.n_error == AdsErrorCode::ADSERR_DEVICE_NOTREADY.into()
+        || err.n_error == AdsErrorCode::ADSERR_DEVICE_BUSY.into()
+        || err.n_error == AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT.into()
+}
+
+impl Client {
+
+    /// Mark all notifications as unregistered, they are deleted on the target with the connection.
+    pub(crate) fn invalidate_notifications(&self) {
+        let mut not_handles = self.not_handles.lock().expect("Threading Error");
+
+        for hdl in not_handles.iter_mut() {
+            hdl.not_hdl = 0;
+        }
+    }
+
+    /// Open the connection again until it succeeds or the client is dropped.
+    pub(crate) async fn reconnect(&self, transport: &Transport, retry_delay: Duration) -> Option<ReadHalf<Box<dyn AdsStream>>> {
+        loop {
+            tokio::select! {
+                _ = sleep(retry_delay) => (),
+                _ = self.released() => return None
+            }
+
+            let mut answer : [u8; 14] = [0; 14];
+
+            match Client::open(transport, &mut answer).await {
+                Ok(stream) => {
+                    let (read, write) = tokio::io::split(stream);
+
+                    // The router might assign another port
+                    self.source.lock().expect("Threading Error").copy_from_slice(&answer[6..14]);
+                    *self.socket_wrt.lock().await = Some(write);
+
+                    info!("Connection re-established - ADS client port opened: {}", u16::from_le_bytes([answer[12], answer[13]]));
+                    return Some(read);
+                },
+                Err(e) => debug!("Failed to reconnect: {}", e)
+            }
+        }
+    }
+
+    /// Renew the symbol handles and notifications which were lost with the connection.
+    ///
+    /// Renewals which fail while the target is starting up are retried after `retry_delay`.
+    pub(crate) async fn process_reconnect(&self, retry_delay: Duration) {
+        // The target might have been restarted with another project
+        *self.symbol_table.lock().expect("Threading Error") = None;
+
+        // Lost with the connection, acquired again on the next call
+        self.take_method_handles();
+
+        let mut symbols : Vec<(String, u32)> = { // LOCK
+            let sym_handles = self.sym_handles.lock().expect("Threading Error");
+            sym_handles.iter().map(| hdl | (hdl.symbol.clone(), hdl.handle)).collect()
+        }; // UNLOCK
+
+        let mut notifications : Vec<u32> = { // LOCK
+            let not_handles = self.not_handles.lock().expect("Threading Error");
+            not_handles.iter().map(| hdl | hdl.id).collect()
+        }; // UNLOCK
+
+        let mut renewed : Vec<(u32, u32)> = Vec::new(); // (old handle, new handle)
+
+        loop {
+            let mut pending = Vec::new();
+
+            for (symbol, old_hdl) in symbols {
+                match self.request_symbol_handle(&symbol).await {
+                    Ok(new_hdl) => {
+                        let cached = { // LOCK
+                            let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+
+                            sym_handles.iter_mut()
+                                .find(| hdl | hdl.symbol == symbol)
+                                .map(| hdl | hdl.handle = new_hdl)
+                                .is_some()
+                        }; // UNLOCK
+
+                        if cached {
+                            debug!("Renewed handle of symbol {}: {} -> {}", symbol, old_hdl, new_hdl);
+                            renewed.push((old_hdl, new_hdl));
+                        } else {
+                            // Released by the user in the meantime
+                            let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &new_hdl.to_ne_bytes()).await;
+                        }
+                    },
+                    Err(e) if is_transient(&e) => pending.push((symbol, old_hdl)),
+                    Err(e) => {
+                        warn!("Failed to renew handle of symbol {} - removed from cache: {}", symbol, e);
+                        let mut sym_handles = self.sym_handles.lock().expect("Threading Error");
+                        sym_handles.retain(| hdl | hdl.symbol != symbol);
+                    }
+                }
+            }
+            symbols = pending;
+
+            let mut pending = Vec::new();
+
+            for id in notifications {
+                match self.renew_notification(id, &renewed, &symbols).await {
+                    Ok(()) => (),
+                    Err(e) if is_transient(&e) => pending.push(id),
+                    Err(e) => error!("Failed to re-register notification {}: {}", id, e)
+                }
+            }
+            notifications = pending;
+
+            if symbols.is_empty() && notifications.is_empty() {
+                break;
+            }
+
+            debug!("Target not ready - retry to renew {} symbol handles and {} notifications", symbols.len(), notifications.len());
+
+            tokio::select! {
+                _ = sleep(retry_delay) => (),
+                _ = self.released() => return
+            }
+        }
+
+        info!("Symbol handles and notifications renewed after reconnect");
+        let _ = self.events.send(ClientEvent::Reconnected);
+    }
+
+    /// Register the notification `id` again on the target.
+    async fn renew_notification(&self, id: u32, renewed: &[(u32, u32)], pending: &[(String, u32)]) -> Result<()> {
+        let registration = { // LOCK
+            let not_handles = self.not_handles.lock().expect("Threading Error");
+
+            not_handles.iter()
+                .find(| hdl | hdl.id == id)
+                .map(| hdl | (hdl.idx_grp, hdl.idx_offs, hdl.attrib, hdl.symbol.clone()))
+        }; // UNLOCK
+
+        // Deleted by the user in the meantime
+        let Some((idx_grp, idx_offs, attrib, symbol)) = registration else {
+            return Ok(());
+        };
+
+        let idx_offs = match symbol {
+            // Notifications which own their symbol handle (see Client::subscribe_symbol)
+            Some(ref symbol) => self.request_symbol_handle(symbol).await?,
+            None if idx_grp == IDX_GRP_SYM_VAL_BY_HDL => {
+                match renewed.iter().find(| (old_hdl, _) | *old_hdl == idx_offs) {
+                    Some((_, new_hdl)) => *new_hdl,
+                    None if pending.iter().any(| (_, old_hdl) | *old_hdl == idx_offs) => {
+                        return Err(AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_NOTREADY.into(), s_msg : format!("Handle {} not renewed yet", idx_offs)});
+                    },
+                    None => idx_offs // Not cached, the handle cannot be renewed
+                }
+            },
+            None => idx_offs
+        };
+
+        let not_hdl = match self.request_device_notification(idx_grp, idx_offs, &attrib).await {
+            Ok(not_hdl) => not_hdl,
+            Err(e) => {
+                if symbol.is_some() {
+                    let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &idx_offs.to_ne_bytes()).await;
+                }
+                return Err(e);
+            }
+        };
+
+        let registered = { // LOCK
+            let mut not_handles = self.not_handles.lock().expect("Threading Error");
+
+            not_handles.iter_mut()
+                .find(| hdl | hdl.id == id)
+                .map(| hdl | {
+                    hdl.not_hdl  = not_hdl;
+                    hdl.idx_offs = idx_offs;
+                })
+                .is_some()
+        }; // UNLOCK
+
+        // Deleted by the user in the meantime
+        if !registered {
+            let _ = self.request_delete_device_notification(not_hdl).await;
+
+            if symbol.is_some() {
+                let _ = self.write(IDX_GRP_SYM_RELEASE_HDL, 0, &idx_offs.to_ne_bytes()).await;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+//! Local resolution of symbol paths to index group and offset.
+use crate::{Client, Result, AdsError, AdsErrorCode, SymbolTable, ArrayInfo, DataTypeInfo};
+use crate::plc_types::range_error;
+use crate::symbols::{parse_array_type, FLAG_BITVALUES, FLAG_PROPITEM, FLAG_STATIC};
+
+/// Location of a symbol or a part of it (member, array element) in the memory of the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSymbol {
+    pub idx_grp     : u32,
+    pub idx_offs    : u32,
+    /// Size \[byte\]
+    pub size        : u32,
+    pub type_name   : String,
+    /// ADS data type identifier (ADST_*)
+    pub data_type   : u32
+}
+
+/// Accessor following the symbol name.
+#[derive(Debug, PartialEq)]
+enum PathItem<'a> {
+    Member(&'a str),
+    Index(Vec<i64>)
+}
+
+fn path_error(path: &str, s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_SYMBOLNOTFOUND.into(), s_msg : format!("Cannot resolve {}: {}", path, s_msg)}
+}
+
+/// Split `.status[1, 2][3].bError` into its accessors.
+fn parse_path<'a>(path: &str, mut rest: &'a str) -> Result<Vec<PathItem<'a>>> {
+    let mut items = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(member) = rest.strip_prefix('.') {
+            let end = member.find(['.', '[']).unwrap_or(member.len());
+            let (name, tail) = member.split_at(end);
+
+            if name.trim().is_empty() {
+                return Err(path_error(path, "empty member name"));
+            }
+            items.push(PathItem::Member(name.trim()));
+            rest = tail;
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let (indices, tail) = index.split_once(']').ok_or_else(|| path_error(path, "missing ]"))?;
+
+            let indices = indices.split(',')
+                            .map(|idx| idx.trim().parse::<i64>().map_err(|_| path_error(path, "invalid array index")))
+                            .collect::<Result<Vec<i64>>>()?;
+
+            items.push(PathItem::Index(indices));
+            rest = tail;
+        } else {
+            return Err(path_error(path, "expected . or ["));
+        }
+    }
+    Ok(items)
+}
+
+impl SymbolTable {
+
+    /// Follow aliases to the data type which defines the members or dimensions.
+    fn underlying_type(&self, type_name: &str) -> Option<&DataTypeInfo> {
+        let mut info = self.data_type(type_name)?;
+
+        // Limit the depth in case of cyclic aliases
+        for _ in 0..16 {
+            if !info.sub_items.is_empty() || !info.array_infos.is_empty() || info.type_name.is_empty() {
+                break;
+            }
+            match self.data_type(&info.type_name) {
+                Some(base) if !info.type_name.eq_ignore_ascii_case(&info.name) => info = base,
+                _ => break
+            }
+        }
+        Some(info)
+    }
+
+    /// Compute index group, index offset and size of `path` (e.g. `MAIN.axes[3].status.bError`).
+    ///
+    /// The path starts with the name of a symbol, followed by members (`.name`) and
+    /// array elements (`[idx]`, multi-dimensional `[idx_1, idx_2]`). Array indices refer to the
+    /// declared bounds of the array.
+    ///
+    /// Members of type `BIT` and dereferencing of pointers or references are not supported.
+    pub fn resolve(&self, path: &str) -> Result<ResolvedSymbol> {
+        // The symbol name contains dots (e.g. MAIN.axes), take the longest matching prefix
+        let symbol = path.char_indices()
+                        .filter(|(_, c)| *c == '.' || *c == '[')
+                        .map(|(pos, _)| pos)
+                        .chain(std::iter::once(path.len()))
+                        .rev()
+                        .find_map(|end| self.symbol(path[..end].trim()).map(|symbol| (symbol, end)));
+
+        let (symbol, end) = symbol.ok_or_else(|| path_error(path, "symbol not found"))?;
+
+        let mut resolved = ResolvedSymbol {
+            idx_grp     : symbol.idx_grp,
+            idx_offs    : symbol.idx_offs,
+            size        : symbol.size,
+            type_name   : symbol.type_name.clone(),
+            data_type   : symbol.data_type
+        };
+
+        for item in parse_path(path, &path[end..])? {
+            match item {
+                PathItem::Member(name) => {
+                    let info = self.underlying_type(&resolved.type_name)
+                                .filter(|info| !info.sub_items.is_empty())
+                                .ok_or_else(|| path_error(path, &format!("{} has no members", resolved.type_name)))?;
+
+                    let member = info.sub_items.iter()
+                                    .filter(|item| item.flags & (FLAG_PROPITEM | FLAG_STATIC) == 0)
+                                    .find(|item| item.name.eq_ignore_ascii_case(name))
+                                    .ok_or_else(|| path_error(path, &format!("{} is not a member of {}", name, info.name)))?;
+
+                    if member.flags & FLAG_BITVALUES != 0 {
+                        return Err(path_error(path, &format!("member {} is not byte aligned", member.name)));
+                    }
+
+                    if member.offs.checked_add(member.size).is_none_or(|end| end > resolved.size) {
+                        return Err(path_error(path, &format!("member {} exceeds {}", member.name, info.name)));
+                    }
+
+                    resolved.idx_offs   = resolved.idx_offs.wrapping_add(member.offs);
+                    resolved.size       = member.size;
+                    resolved.type_name  = member.type_name.clone();
+                    resolved.data_type  = member.data_type;
+                },
+                PathItem::Index(indices) => {
+                    let (dims, element, data_type) = match self.underlying_type(&resolved.type_name) {
+                        Some(info) if !info.array_infos.is_empty() => {
+                            let element = parse_array_type(&info.type_name).map(|(_, element)| element).unwrap_or(&info.type_name);
+                            (info.array_infos.clone(), element.to_owned(), info.data_type)
+                        },
+                        _ => parse_array_type(&resolved.type_name)
+                                .map(|(dims, element)| (dims, element.to_owned(), resolved.data_type))
+                                .ok_or_else(|| path_error(path, &format!("{} is not an array", resolved.type_name)))?
+                    };
+
+                    let offset = element_offset(path, &dims, &indices, resolved.size)?;
+
+                    resolved.idx_offs   = resolved.idx_offs.wrapping_add(offset.0);
+                    resolved.size       = offset.1;
+                    resolved.type_name  = element;
+                    resolved.data_type  = data_type;
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// Offset and size of an array element, the last index varies fastest.
+fn element_offset(path: &str, dims: &[ArrayInfo], indices: &[i64], size: u32) -> Result<(u32, u32)> {
+    if dims.len() != indices.len() {
+        return Err(path_error(path, &format!("expected {} array indices, got {}", dims.len(), indices.len())));
+    }
+
+    let elements = dims.iter().try_fold(1u64, |product, dim| product.checked_mul(dim.elements as u64));
+    let elements = elements.filter(|elements| *elements != 0 && (size as u64).is_multiple_of(*elements))
+                    .ok_or_else(|| path_error(path, "inconsistent array size"))?;
+    let elem_size = size as u64 / elements;
+
+    let mut linear : u64 = 0;
+    for (dim, idx) in dims.iter().zip(indices) {
+        let pos = idx.checked_sub(dim.lower_bound as i64)
+                    .and_then(|pos| u64::try_from(pos).ok())
+                    .filter(|pos| *pos < dim.elements as u64);
+
+        let Some(pos) = pos else {
+            let upper = dim.lower_bound as i64 + dim.elements as i64 - 1;
+            return Err(range_error(&format!("Array index {} out of bounds [{}..{}] in {}", idx, dim.lower_bound, upper, path)));
+        };
+        linear = linear * dim.elements as u64 + pos;
+    }
+
+    Ok(((linear * elem_size) as u32, elem_size as u32))
+}
+
+impl Client {
+
+    /// Compute index group, index offset and size of `path` locally from the [SymbolTable] of the target.
+    ///
+    /// In contrast to a symbol handle, no request is submitted to the target (except the initial upload
+    /// of the symbol table). See [SymbolTable::resolve] for the syntax of `path`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     // TIME
+    ///     let pt = ads_client.resolve("MAIN.ton_a.PT").await?;
+    ///     let mut buf = vec![0; pt.size as usize];
+    ///     ads_client.read(pt.idx_grp, pt.idx_offs, &mut buf).await?;
+    ///
+    ///     // Convert according to the data type of the symbol
+    ///     let table = ads_client.symbol_table().await?;
+    ///     println!("MAIN.ton_a.PT: {:?}", table.decode_value(&pt.type_name, &buf)?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn resolve(&self, path: &str) -> Result<ResolvedSymbol> {
+        self.symbol_table().await?.resolve(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::tests::{symbol, data_type, member, table};
+    use crate::symbols::{ADST_BIGTYPE, ADST_BIT, ADST_INT16, ADST_INT32, ADST_REAL64, ADST_UINT8};
+
+    fn st_status() -> DataTypeInfo {
+        let mut info = data_type("ST_Status", "", 4, ADST_BIGTYPE);
+        info.sub_items = vec![
+            member("bError", "BOOL", 0, 1, ADST_BIT),
+            member("nCode", "UINT", 2, 2, ADST_INT16),
+            {
+                let mut bit = member("bFlag", "BIT", 0, 1, ADST_BIT);
+                bit.flags = FLAG_BITVALUES;
+                bit
+            }
+        ];
+        info
+    }
+
+    fn st_axis() -> DataTypeInfo {
+        let mut info = data_type("ST_Axis", "", 16, ADST_BIGTYPE);
+        info.sub_items = vec![
+            member("fPos", "LREAL", 0, 8, ADST_REAL64),
+            member("status", "ST_Status", 8, 4, ADST_BIGTYPE),
+            member("nMatrix", "ARRAY [1..2, -1..0] OF BYTE", 12, 4, ADST_UINT8)
+        ];
+        info
+    }
+
+    /// `ARRAY [1..3] OF ST_Axis` declared as data type
+    fn axes_type() -> DataTypeInfo {
+        let mut info = data_type("ARRAY [1..3] OF ST_Axis", "ST_Axis", 48, ADST_BIGTYPE);
+        info.array_infos = vec![ArrayInfo { lower_bound : 1, elements : 3 }];
+        info
+    }
+
+    fn test_table() -> SymbolTable {
+        table(
+            &[
+                symbol("MAIN.axes", "ARRAY [1..3] OF ST_Axis", 0x1000, 48, ADST_BIGTYPE),
+                symbol("MAIN.grid", "ARRAY [0..2, 5..8] OF DINT", 0x2000, 48, ADST_INT32),
+                symbol("MAIN", "ST_Status", 0x3000, 4, ADST_BIGTYPE),
+                symbol("GVL.wide", "ARRAY [-2147483648..-2147483639] OF BYTE", 0, 10, ADST_UINT8)
+            ],
+            &[st_status(), st_axis(), axes_type()]
+        )
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(parse_path("", ".a[1, -2][3].b").unwrap(), [
+            PathItem::Member("a"), PathItem::Index(vec![1, -2]), PathItem::Index(vec![3]), PathItem::Member("b")
+        ]);
+        assert_eq!(parse_path("", "").unwrap(), []);
+
+        for rest in [".", "..a", "[1", "[a]", "[]", "a"] {
+            assert!(parse_path("", rest).is_err(), "{}", rest);
+        }
+    }
+
+    #[test]
+    fn members_and_elements() {
+        let table = test_table();
+
+        let resolved = table.resolve("MAIN.axes[2].status.nCode").unwrap();
+        assert_eq!(resolved, ResolvedSymbol {
+            idx_grp     : 0x4040,
+            idx_offs    : 0x1000 + 16 + 8 + 2,
+            size        : 2,
+            type_name   : String::from("UINT"),
+            data_type   : ADST_INT16
+        });
+
+        // Symbol names containing dots take precedence over members
+        assert_eq!(table.resolve("main.AXES[3]").unwrap().idx_offs, 0x1000 + 32);
+        assert_eq!(table.resolve("MAIN.bError").unwrap().idx_offs, 0x3000);
+
+        let element = table.resolve("MAIN.axes[1]").unwrap();
+        assert_eq!((element.size, element.type_name.as_str()), (16, "ST_Axis"));
+    }
+
+    #[test]
+    fn multi_dimensional_arrays() {
+        let table = test_table();
+
+        // Last index varies fastest
+        let resolved = table.resolve("MAIN.grid[1, 7]").unwrap();
+        assert_eq!((resolved.idx_offs, resolved.size, resolved.type_name.as_str()), (0x2000 + (4 + 2) * 4, 4, "DINT"));
+        assert_eq!(table.resolve("MAIN.grid[2, 8]").unwrap().idx_offs, 0x2000 + 44);
+
+        // Array type of a member, non-zero and negative lower bounds
+        assert_eq!(table.resolve("MAIN.axes[3].nMatrix[1, -1]").unwrap().idx_offs, 0x1000 + 32 + 12);
+        assert_eq!(table.resolve("MAIN.axes[3].nMatrix[2, 0]").unwrap().idx_offs, 0x1000 + 32 + 15);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let table = test_table();
+        let out_of_range : u32 = AdsErrorCode::ADSERR_DEVICE_OUTOFRANGE.into();
+
+        for path in ["MAIN.axes[0]", "MAIN.axes[4]", "MAIN.grid[0, 4]", "MAIN.grid[3, 5]", "MAIN.axes[1].nMatrix[1, 1]",
+                     "MAIN.axes[-9223372036854775808]", "MAIN.axes[9223372036854775807]", "GVL.wide[9223372036854775807]", "GVL.wide[-2147483638]"] {
+            assert_eq!(table.resolve(path).map_err(|e| e.n_error), Err(out_of_range), "{}", path);
+        }
+        assert_eq!(table.resolve("GVL.wide[-2147483639]").unwrap().idx_offs, 9);
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let table = test_table();
+
+        for path in ["MAIN.unknown", "GVL.missing", "MAIN.axes[1, 2]", "MAIN.axes[1].fPos[0]", "MAIN.axes.status",
+                     "MAIN.axes[1].status.bFlag", "MAIN.axes[1", "MAIN.grid[1]"] {
+            assert!(table.resolve(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn inconsistent_array_size() {
+        let dims = [ArrayInfo { lower_bound : 0, elements : u32::MAX }; 3];
+        assert!(element_offset("", &dims, &[0, 0, 0], 16).is_err());
+
+        let dims = [ArrayInfo { lower_bound : 0, elements : 0 }];
+        assert!(element_offset("", &dims, &[0], 0).is_err());
+
+        let dims = [ArrayInfo { lower_bound : 0, elements : 3 }];
+        assert!(element_offset("", &dims, &[0], 16).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Management of the ADS routes of a target.
+//!
+//! A route has to be added on the target before a client can communicate with it. [add_remote_route] adds the route
+//! via the AMS UDP service (port 48899) of the target, it does not require an ADS connection. Existing routes are
+//! listed and removed via the system service (ADS port 10000), the client may be connected to any port:
+//!
+//! ```rust,no_run
+//! use ads_client::{ClientBuilder, Result};
+//! use ads_client::routes;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     // Route on the target to this computer
+//!     routes::add_remote_route("192.168.1.10", "engineering-pc", "192.168.1.20.1.1", "192.168.1.20", "Administrator", "1").await?;
+//!
+//!     let ads_client = ClientBuilder::new("192.168.1.10.1.1", 851).build().await?;
+//!
+//!     for route in ads_client.list_routes().await? {
+//!         println!("{}: {} ({})", route.name, route.net_id, route.address);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::time::Duration;
+use log::info;
+use crate::{Client, Result, AdsError, AdsErrorCode, PORT_SYSTEM_SERVICE};
+use crate::udp::{self, UdpMessage, SERVICE_ADD_ROUTE, TAG_STATUS, TAG_PASSWORD, TAG_COMPUTER_NAME, TAG_NET_ID, TAG_ROUTE_NAME, TAG_USER_NAME};
+
+// Index groups of the system service
+const IDX_GRP_ROUTE_REMOVE  : u32 = 802;
+const IDX_GRP_ROUTE_LIST    : u32 = 803;
+
+const LEN_ROUTE_ENTRY_MAX   : usize = 0x800;
+const LEN_ROUTE_HEADER      : usize = 56;
+
+/// Maximum waiting time for the reply of the target
+const ROUTE_TIMEOUT         : Duration = Duration::from_secs(5);
+
+/// Route of a target (see [Client::list_routes]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub name        : String,
+    pub net_id      : String,
+    /// IP address or host name of the route's target
+    pub address     : String,
+    pub flags       : u32,
+    /// Timeout \[ms\]
+    pub timeout     : u32
+}
+
+fn route_error(s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : format!("Invalid route entry: {}", s_msg)}
+}
+
+/// NUL terminated string of `len` byte at `pos`.
+fn route_str(entry: &[u8], pos: usize, len: usize) -> Result<String> {
+    let data = pos.checked_add(len)
+                .and_then(|end| entry.get(pos..end))
+                .ok_or_else(|| route_error("string exceeds the entry"))?;
+    let data = data.split(| byte | *byte == 0).next().unwrap_or_default();
+    Ok(String::from_utf8_lossy(data).into_owned())
+}
+
+/// Route entry of the system service:
+///
+/// AmsNetId [6] | Reserved [2] | Flags [4] | Timeout [4] | Reserved [28] | Len Address [4] | Len Name [4] | Reserved [4] | Address | Name
+fn parse_route(entry: &[u8]) -> Result<Route> {
+    let header = entry.get(..LEN_ROUTE_HEADER).ok_or_else(|| route_error("missing header"))?;
+
+    let len_address = u32::from_le_bytes(header[44..48].try_into()?) as usize;
+    let len_name    = u32::from_le_bytes(header[48..52].try_into()?) as usize;
+
+    Ok(Route {
+        net_id  : udp::format_net_id(&header[0..6].try_into()?),
+        flags   : u32::from_le_bytes(header[8..12].try_into()?),
+        timeout : u32::from_le_bytes(header[12..16].try_into()?),
+        address : route_str(entry, LEN_ROUTE_HEADER, len_address)?,
+        name    : route_str(entry, LEN_ROUTE_HEADER.saturating_add(len_address), len_name)?
+    })
+}
+
+/// Add a route on the target `target_ip` to this computer.
+///
+/// - `target_ip` IP address or host name of the target, optionally with the UDP port (defaults to 48899)
+/// - `route_name` Name of the route on the target
+/// - `net_id` AmsNetId of this computer
+/// - `ip` IP address or host name of this computer as seen by the target
+/// - `user`, `password` Credentials of a user with administrative rights on the target
+///
+/// # Example
+///
+/// A stand-in for the UDP service of the target, which accepts the password `1`:
+///
+/// ```rust
+/// use tokio::net::UdpSocket;
+/// use ads_client::{routes, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let target = UdpSocket::bind("127.0.0.1:0").await?;
+///     let addr = target.local_addr()?.to_string();
+///
+///     tokio::spawn(async move {
+///         let mut buf = [0u8; 2048];
+///         loop {
+///             let (len, from) = target.recv_from(&mut buf).await.unwrap();
+///             let request = &buf[..len];
+///             let password_ok = request.windows(2).any(| w | w == [b'1', 0]);
+///
+///             // Header with the reply flag in the service ID and a status item
+///             let mut reply = request[0..24].to_vec();
+///             reply[8..12].copy_from_slice(&(6u32 | 0x8000_0000).to_le_bytes());
+///             reply[20..24].copy_from_slice(&1u32.to_le_bytes());
+///             reply.extend([1, 0, 4, 0]);
+///             reply.extend(if password_ok { 0u32 } else { 0x704 }.to_le_bytes());
+///             target.send_to(&reply, from).await.unwrap();
+///         }
+///     });
+///
+///     routes::add_remote_route(&addr, "test", "192.168.1.20.1.1", "192.168.1.20", "Administrator", "1").await?;
+///
+///     let err = routes::add_remote_route(&addr, "test", "192.168.1.20.1.1", "192.168.1.20", "Administrator", "2").await.unwrap_err();
+///     assert_eq!(err.n_error, 0x704);
+///     Ok(())
+/// }
+/// ```
+pub async fn add_remote_route(target_ip: &str, route_name: &str, net_id: &str, ip: &str, user: &str, password: &str) -> Result<()> {
+    let target = udp::resolve(target_ip).await?;
+    let net_id = udp::parse_net_id(net_id)?;
+
+    // The route refers to the system service of this computer
+    let mut request = UdpMessage::new(SERVICE_ADD_ROUTE, net_id);
+    request.port = PORT_SYSTEM_SERVICE;
+    request.add_str(TAG_ROUTE_NAME, route_name)
+           .add_bytes(TAG_NET_ID, &net_id)
+           .add_str(TAG_USER_NAME, user)
+           .add_str(TAG_PASSWORD, password)
+           .add_str(TAG_COMPUTER_NAME, ip);
+
+    info!("Add route {} on {}", route_name, target);
+    let reply = udp::request(target, &request, ROUTE_TIMEOUT).await?;
+
+    match reply.get_u32(TAG_STATUS) {
+        Some(0) => Ok(()),
+        Some(status) => Err(AdsError{n_error : status, s_msg : format!("Target {} rejected route {} (e.g. invalid credentials)", target, route_name)}),
+        None => Err(AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("Reply without status")})
+    }
+}
+
+impl Client {
+
+    /// List the routes of the target.
+    ///
+    /// The request is submitted to the system service (port 10000) of the target.
+    pub async fn list_routes(&self) -> Result<Vec<Route>> {
+        let system_service = self.with_port(PORT_SYSTEM_SERVICE);
+        let mut routes = Vec::new();
+        let mut entry = vec![0u8; LEN_ROUTE_ENTRY_MAX];
+
+        for index in 0.. {
+            match system_service.read(IDX_GRP_ROUTE_LIST, index, &mut entry).await {
+                Ok(len) => routes.push(parse_route(entry.get(..len as usize).unwrap_or(&entry))?),
+                // End of the list
+                Err(e) if e.n_error == u32::from(AdsErrorCode::ADSERR_DEVICE_NOTFOUND) => break,
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(routes)
+    }
+
+    /// Remove the route `name` from the target.
+    ///
+    /// The request is submitted to the system service (port 10000) of the target.
+    pub async fn remove_route(&self, name: &str) -> Result<()> {
+        let mut data = name.as_bytes().to_vec();
+        data.push(0);
+
+        info!("Remove route {}", name);
+        self.with_port(PORT_SYSTEM_SERVICE).write(IDX_GRP_ROUTE_REMOVE, 0, &data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UdpSocket;
+    use crate::tests::{test_client, receive, respond};
+
+    fn route_entry(net_id: [u8; 6], address: &[u8], name: &[u8]) -> Vec<u8> {
+        let mut entry = vec![0u8; LEN_ROUTE_HEADER];
+        entry[0..6].copy_from_slice(&net_id);
+        entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+        entry[12..16].copy_from_slice(&5000u32.to_le_bytes());
+        entry[44..48].copy_from_slice(&(address.len() as u32).to_le_bytes());
+        entry[48..52].copy_from_slice(&(name.len() as u32).to_le_bytes());
+        entry.extend_from_slice(address);
+        entry.extend_from_slice(name);
+        entry
+    }
+
+    #[test]
+    fn route() {
+        let entry = route_entry([192, 168, 1, 20, 1, 1], b"192.168.1.20\0", b"engineering-pc\0");
+
+        assert_eq!(parse_route(&entry).unwrap(), Route {
+            name    : String::from("engineering-pc"),
+            net_id  : String::from("192.168.1.20.1.1"),
+            address : String::from("192.168.1.20"),
+            flags   : 1,
+            timeout : 5000
+        });
+    }
+
+    #[test]
+    fn invalid_route_is_rejected() {
+        let entry = route_entry([192, 168, 1, 20, 1, 1], b"192.168.1.20\0", b"engineering-pc\0");
+
+        for len in 0..entry.len() {
+            assert!(parse_route(&entry[..len]).is_err(), "entry truncated to {} byte", len);
+        }
+
+        for (pos, len) in [(44, u32::MAX), (48, u32::MAX), (44, 0x8000_0000), (48, entry.len() as u32)] {
+            let mut entry = entry.clone();
+            entry[pos..pos + 4].copy_from_slice(&len.to_le_bytes());
+            assert!(parse_route(&entry).is_err(), "length {:#x} at {}", len, pos);
+        }
+    }
+
+    #[tokio::test]
+    async fn add_route_request() {
+        let target = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = target.local_addr().unwrap().to_string();
+
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            let (len, from) = target.recv_from(&mut buf).await.unwrap();
+            let request = UdpMessage::parse(&buf[..len]).unwrap();
+
+            let mut reply = request.clone();
+            reply.service |= 0x8000_0000;
+            reply.items = vec![(TAG_STATUS, 0u32.to_le_bytes().to_vec())];
+            target.send_to(&reply.encode().unwrap(), from).await.unwrap();
+            request
+        });
+
+        add_remote_route(&addr, "test", "192.168.1.20.1.1", "192.168.1.20", "Administrator", "1").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert_eq!((request.service, request.net_id, request.port), (SERVICE_ADD_ROUTE, [192, 168, 1, 20, 1, 1], 10000));
+        assert_eq!(request.get_str(TAG_ROUTE_NAME).as_deref(), Some("test"));
+        assert_eq!(request.get(TAG_NET_ID), Some(&[192, 168, 1, 20, 1, 1][..]));
+        assert_eq!(request.get_str(TAG_USER_NAME).as_deref(), Some("Administrator"));
+        assert_eq!(request.get(TAG_PASSWORD), Some(&b"1\0"[..]));
+        assert_eq!(request.get_str(TAG_COMPUTER_NAME).as_deref(), Some("192.168.1.20"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn list_and_remove_routes() {
+        let (client, mut remote, _) = test_client();
+        let entry = route_entry([192, 168, 1, 20, 1, 1], b"192.168.1.20\0", b"engineering-pc\0");
+
+        // Requests are submitted to the system service, although the client is connected to port 851
+        let server = async {
+            for index in 0..2u32 {
+                let (header, payload) = receive(&mut remote).await;
+                assert_eq!(&header[12..14], &PORT_SYSTEM_SERVICE.to_le_bytes());
+                assert_eq!(&payload[0..8], &[IDX_GRP_ROUTE_LIST.to_le_bytes(), index.to_le_bytes()].concat());
+
+                let response = match index {
+                    0 => [&0u32.to_le_bytes()[..], &(entry.len() as u32).to_le_bytes(), &entry].concat(),
+                    _ => [u32::from(AdsErrorCode::ADSERR_DEVICE_NOTFOUND).to_le_bytes(), 0u32.to_le_bytes()].concat()
+                };
+                respond(&mut remote, &header, &response).await;
+            }
+
+            let (header, payload) = receive(&mut remote).await;
+            assert_eq!(&header[12..14], &PORT_SYSTEM_SERVICE.to_le_bytes());
+            assert_eq!(&payload[0..4], &IDX_GRP_ROUTE_REMOVE.to_le_bytes());
+            assert_eq!(&payload[12..], b"engineering-pc\0");
+            respond(&mut remote, &header, &0u32.to_le_bytes()).await;
+        };
+
+        let client = async {
+            let routes = client.list_routes().await.unwrap();
+            assert_eq!(routes.len(), 1);
+            client.remove_route(&routes[0].name).await.unwrap();
+        };
+        tokio::join!(client, server);
+    }
+}
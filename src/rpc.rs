@@ -0,0 +1,276 @@
+//! Invocation of function block methods (`{attribute 'TcRpcEnable'}`).
+use log::info;
+use crate::{Client, Result, AdsError, AdsErrorCode, AdsEncode, AdsDecode, AdsValue, MethodInfo, IDX_GRP_SYM_VAL_BY_HDL};
+use crate::plc_types::size_error;
+
+/// Return value and output parameters of a method call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodResult {
+    /// `None` if the method has no return value
+    pub return_value    : Option<AdsValue>,
+    /// `VAR_OUTPUT` and `VAR_IN_OUT` parameters in the order of declaration
+    pub outputs         : Vec<(String, AdsValue)>
+}
+
+impl MethodResult {
+    /// Output parameter `name` (case-insensitive).
+    pub fn output(&self, name: &str) -> Option<&AdsValue> {
+        self.outputs.iter()
+            .find(|(output, _)| output.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+}
+
+fn method_error(s_msg: String) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDPARM.into(), s_msg}
+}
+
+impl Client {
+
+    /// Call the method through the handle of `path#method` (index group 0xF005).
+    async fn invoke_method(&self, path: &str, method: &str, write_data: &[u8], read_data: &mut [u8]) -> Result<u32> {
+        let hdl = self.symbol_handle(&format!("{}#{}", path, method)).await?;
+
+        info!("Call method {}#{}: {} byte parameters, {} byte result", path, method, write_data.len(), read_data.len());
+        self.read_write(IDX_GRP_SYM_VAL_BY_HDL, hdl, read_data, write_data).await
+    }
+
+    /// Call the method `method` of the function block instance `path` with the parameters `args`.
+    ///
+    /// The method must be enabled for remote calls with `{attribute 'TcRpcEnable'}`.
+    /// The layout of the parameters is taken from the method information of the [SymbolTable](crate::SymbolTable):
+    /// `args` contains one value for each `VAR_INPUT` and `VAR_IN_OUT` parameter in the order of declaration.
+    ///
+    /// The handle of the method is cached like a symbol handle. It is released after an online change
+    /// and acquired again on the next call.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, AdsValue, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     // METHOD MoveAbs : BOOL
+    ///     // VAR_INPUT
+    ///     //     fPosition : LREAL;
+    ///     //     fVelocity : LREAL;
+    ///     // END_VAR
+    ///     // VAR_OUTPUT
+    ///     //     nErrorId : UDINT;
+    ///     // END_VAR
+    ///     let result = ads_client.call_method("MAIN.fbAxis", "MoveAbs", &[AdsValue::Real(100.0), AdsValue::Real(10.0)]).await?;
+    ///
+    ///     println!("Return value: {:?}, Error ID: {:?}", result.return_value, result.output("nErrorId"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn call_method(&self, path: &str, method: &str, args: &[AdsValue]) -> Result<MethodResult> {
+        let table = self.symbol_table().await?;
+        let instance = table.resolve(path)?;
+
+        let info : &MethodInfo = table.data_type(&instance.type_name)
+                        .and_then(|fb| fb.methods.iter().find(|info| info.name.eq_ignore_ascii_case(method)))
+                        .ok_or_else(|| method_error(format!("Method {} not found for {} ({})", method, path, instance.type_name)))?;
+
+        // Parameters are marshalled without gaps in the order of declaration
+        let inputs : Vec<_> = info.params.iter().filter(|param| param.is_input()).collect();
+
+        if inputs.len() != args.len() {
+            return Err(method_error(format!("{}#{} expects {} parameters, got {}", path, method, inputs.len(), args.len())));
+        }
+
+        let mut write_data = vec![0; inputs.iter().map(|param| param.size as usize).sum()];
+        let mut offset = 0;
+
+        for (param, arg) in inputs.iter().zip(args) {
+            let end = offset + param.size as usize;
+            table.encode_type(&param.type_name, param.data_type, arg, &mut write_data[offset..end], 0)?;
+            offset = end;
+        }
+
+        let read_len = info.return_size as usize + info.params.iter()
+                                                        .filter(|param| param.is_output())
+                                                        .map(|param| param.size as usize)
+                                                        .sum::<usize>();
+        let mut read_data = vec![0; read_len];
+
+        // The declared name, the cached handle does not depend on the case of `method`
+        let rd_len = self.invoke_method(path, &info.name, &write_data, &mut read_data).await? as usize;
+        if rd_len != read_len {
+            return Err(size_error(read_len, rd_len));
+        }
+
+        let (ret, mut outputs) = read_data.split_at(info.return_size as usize);
+
+        let return_value = match info.return_size {
+            0 => None,
+            _ => Some(table.decode_type(&info.return_type, info.return_data_type, ret, 0)?)
+        };
+
+        let mut result = MethodResult { return_value, outputs : Vec::new() };
+
+        for param in info.params.iter().filter(|param| param.is_output()) {
+            let (data, rest) = outputs.split_at(param.size as usize);
+            result.outputs.push((param.name.clone(), table.decode_type(&param.type_name, param.data_type, data, 0)?));
+            outputs = rest;
+        }
+
+        Ok(result)
+    }
+
+    /// Call the method `method` of the function block instance `path` with typed parameters.
+    ///
+    /// `A` contains the `VAR_INPUT` and `VAR_IN_OUT` parameters, `R` the return value followed by the
+    /// `VAR_OUTPUT` and `VAR_IN_OUT` parameters, each in the order of declaration and without gaps.
+    /// Use `#[ads(pack_mode = 1)]` for derived structs and `()` for methods without parameters or return value.
+    ///
+    /// In contrast to [Client::call_method], the symbol table is not required.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, AdsType, Result};
+    ///
+    /// #[derive(AdsType)]
+    /// #[ads(pack_mode = 1)]
+    /// struct MoveAbsArgs {
+    ///     position : f64,
+    ///     velocity : f64,
+    /// }
+    ///
+    /// #[derive(AdsType)]
+    /// #[ads(pack_mode = 1)]
+    /// struct MoveAbsResult {
+    ///     ret         : bool,
+    ///     error_id    : u32,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let args = MoveAbsArgs { position : 100.0, velocity : 10.0 };
+    ///     let result : MoveAbsResult = ads_client.call_method_value("MAIN.fbAxis", "MoveAbs", &args).await?;
+    ///
+    ///     println!("Return value: {}, Error ID: {}", result.ret, result.error_id);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn call_method_value<A: AdsEncode, R: AdsDecode>(&self, path: &str, method: &str, args: &A) -> Result<R> {
+        let mut write_data = vec![0; A::SIZE];
+        args.encode(&mut write_data)?;
+
+        let mut read_data = vec![0; R::SIZE];
+        let rd_len = self.invoke_method(path, method, &write_data, &mut read_data).await? as usize;
+
+        if rd_len != R::SIZE {
+            return Err(size_error(R::SIZE, rd_len));
+        }
+        R::decode(&read_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{MethodParamInfo, SymbolTable, IDX_GRP_SYM_HDL_BY_NAME};
+    use crate::symbols::{ADST_BIGTYPE, ADST_BIT, ADST_REAL64, ADST_UINT32};
+    use crate::symbols::tests::{symbol, data_type, table};
+    use crate::tests::{test_client, receive, respond};
+
+    const PARA_IN   : u32 = 1;
+    const PARA_OUT  : u32 = 2;
+
+    fn param(name: &str, type_name: &str, size: u32, data_type: u32, flags: u32) -> MethodParamInfo {
+        MethodParamInfo { name : name.into(), type_name : type_name.into(), comment : String::new(), size, align_size : size, data_type, flags }
+    }
+
+    /// METHOD MoveAbs : BOOL with the inputs fPosition, fVelocity : LREAL and the output nErrorId : UDINT
+    fn fb_axis() -> SymbolTable {
+        let mut fb = data_type("FB_Axis", "", 64, ADST_BIGTYPE);
+        fb.methods = vec![MethodInfo {
+            name                : String::from("MoveAbs"),
+            return_type         : String::from("BOOL"),
+            comment             : String::new(),
+            return_size         : 1,
+            return_align_size   : 1,
+            return_data_type    : ADST_BIT,
+            flags               : 0,
+            params              : vec![
+                param("fPosition", "LREAL", 8, ADST_REAL64, PARA_IN),
+                param("nErrorId", "UDINT", 4, ADST_UINT32, PARA_OUT),
+                param("fVelocity", "LREAL", 8, ADST_REAL64, PARA_IN)
+            ]
+        }];
+        table(&[symbol("MAIN.fbAxis", "FB_Axis", 0x100, 64, ADST_BIGTYPE)], &[fb])
+    }
+
+    fn rpc_client() -> (Client, tokio::io::DuplexStream) {
+        let (client, remote, _) = test_client();
+        *client.symbol_table.lock().unwrap() = Some(Arc::new(fb_axis()));
+        (client, remote)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn call() {
+        let (client, mut remote) = rpc_client();
+
+        let server = async {
+            let (header, payload) = receive(&mut remote).await;
+            assert_eq!(&payload[0..4], &IDX_GRP_SYM_HDL_BY_NAME.to_le_bytes());
+            assert_eq!(&payload[16..], b"MAIN.fbAxis#MoveAbs");
+            respond(&mut remote, &header, &[0u32.to_le_bytes(), 4u32.to_le_bytes(), 33u32.to_le_bytes()].concat()).await;
+
+            // Inputs without gaps, return value followed by the outputs
+            let (header, payload) = receive(&mut remote).await;
+            assert_eq!(&payload[0..8], &[IDX_GRP_SYM_VAL_BY_HDL.to_le_bytes(), 33u32.to_le_bytes()].concat());
+            assert_eq!(&payload[8..12], &5u32.to_le_bytes());
+            assert_eq!(&payload[16..], &[100f64.to_le_bytes(), 10f64.to_le_bytes()].concat());
+            respond(&mut remote, &header, &[&0u32.to_le_bytes()[..], &5u32.to_le_bytes(), &[1], &7u32.to_le_bytes()].concat()).await;
+        };
+
+        let (result, _) = tokio::join!(client.call_method("MAIN.fbAxis", "moveabs", &[AdsValue::Real(100.0), AdsValue::Real(10.0)]), server);
+        let result = result.unwrap();
+
+        assert_eq!(result.return_value, Some(AdsValue::Bool(true)));
+        assert_eq!(result.output("NERRORID"), Some(&AdsValue::UInt(7)));
+        assert_eq!(client.symbol_handle("MAIN.fbAxis#MoveAbs").await.unwrap(), 33);
+    }
+
+    #[tokio::test]
+    async fn invalid_arguments_are_rejected() {
+        // No request is submitted, the connection is not served
+        let (client, _remote) = rpc_client();
+        let invalid_parm = u32::from(AdsErrorCode::ADSERR_DEVICE_INVALIDPARM);
+
+        for args in [&[][..], &[AdsValue::Real(100.0)], &[AdsValue::Real(1.0), AdsValue::Real(2.0), AdsValue::Real(3.0)]] {
+            let err = client.call_method("MAIN.fbAxis", "MoveAbs", args).await.unwrap_err();
+            assert_eq!(err.n_error, invalid_parm, "{} arguments", args.len());
+        }
+
+        for args in [[AdsValue::Real(100.0), AdsValue::String(String::from("fast"))], [AdsValue::Bool(true), AdsValue::Real(10.0)]] {
+            assert!(client.call_method("MAIN.fbAxis", "MoveAbs", &args).await.is_err(), "{:?}", args);
+        }
+
+        let err = client.call_method("MAIN.fbAxis", "MoveRel", &[]).await.unwrap_err();
+        assert_eq!(err.n_error, invalid_parm);
+        assert!(client.call_method("MAIN.fbMissing", "MoveAbs", &[]).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unexpected_response_size() {
+        let (client, mut remote) = rpc_client();
+        client.sym_handles.lock().unwrap().push(crate::SymHandle { symbol : String::from("MAIN.fbAxis#MoveAbs"), handle : 33 });
+
+        let server = async {
+            let (header, _) = receive(&mut remote).await;
+            respond(&mut remote, &header, &[&0u32.to_le_bytes()[..], &1u32.to_le_bytes(), &[1]].concat()).await;
+        };
+
+        let (result, _) = tokio::join!(client.call_method("MAIN.fbAxis", "MoveAbs", &[AdsValue::Real(1.0), AdsValue::Real(2.0)]), server);
+        assert_eq!(result.unwrap_err().n_error, u32::from(AdsErrorCode::ADSERR_DEVICE_INVALIDSIZE));
+    }
+}
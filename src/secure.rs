@@ -0,0 +1,623 @@
+//! Secure ADS: AMS/TCP over TLS, directly to the target (requires the feature *tls*).
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+#[cfg(feature = "tls-psk")]
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use ring::digest;
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, ring as provider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+#[cfg(feature = "tls-psk")]
+use openssl::error::ErrorStack;
+#[cfg(feature = "tls-psk")]
+use openssl::ssl::{SslConnector, SslMethod, SslVersion};
+#[cfg(feature = "tls-psk")]
+use tokio_openssl::SslStream;
+use crate::{Result, AdsError, AdsErrorCode, AdsStream};
+use crate::udp;
+
+/// TCP port of Secure ADS
+pub const SECURE_ADS_PORT       : u16 = 8016;
+const LOCAL_PORT_DEFAULT        : u16 = 30000;
+
+// Connect info, exchanged after the TLS handshake:
+// Size [2] | Flags [2] | Version [1] | Error [1] | AmsNetId [6] | Len User [1] | Len Password [1] | Reserved [18] | Host name [32] | User | Password
+const LEN_CONNECT_INFO          : usize = 64;
+const CONNECT_INFO_VERSION      : u8 = 1;
+const LEN_HOST_NAME             : usize = 32;
+
+// Flags of the connect info
+const FLAG_RESPONSE             : u16 = 0x0001;
+const FLAG_SELF_SIGNED          : u16 = 0x0010;
+const FLAG_IP_ADDR              : u16 = 0x0020;
+const FLAG_ADD_REMOTE           : u16 = 0x0080;
+
+fn tls_error(s_msg: String) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_ACCESSDENIED.into(), s_msg}
+}
+
+fn config_error(s_msg: String) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg}
+}
+
+/// Trust of the certificate of the target.
+#[derive(Clone)]
+enum Trust {
+    None,
+    /// PEM encoded CA certificates
+    Ca(Vec<u8>),
+    /// File of the pinned certificate fingerprints
+    FirstUse(PathBuf),
+    /// Pre-shared key and its identity
+    #[cfg(feature = "tls-psk")]
+    Psk { identity : String, key : Vec<u8> }
+}
+
+/// Configuration of a Secure ADS connection, passed to [ClientBuilder::secure](crate::ClientBuilder::secure).
+///
+/// The client connects to the target directly (TCP port 8016) instead of the local ADS router.
+/// The certificate of the target is either verified with a CA ([SecureAds::set_ca]) or pinned on the first
+/// connection ([SecureAds::set_trust_on_first_use]), e.g. for the self-signed certificates of TwinCAT.
+///
+/// Pre-shared keys (PSK) require the feature *tls-psk* (see `SecureAds::set_psk`). As rustls does not 
+/// implement external PSKs, these connections are based on OpenSSL.
+///
+/// # Example
+///
+/// A stand-in for the target which answers a Read State request:
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// use tokio::net::TcpListener;
+/// use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+/// use ads_client::{ClientBuilder, SecureAds, AdsState, Result};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let cert = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+///     let server_config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+///                             .with_safe_default_protocol_versions().unwrap()
+///                             .with_no_client_auth()
+///                             .with_single_cert(vec![CertificateDer::from(cert.cert.der().to_vec())],
+///                                               PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()))).unwrap();
+///
+///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+///     let addr = listener.local_addr()?;
+///
+///     tokio::spawn(async move {
+///         let (tcp, _) = listener.accept().await.unwrap();
+///         let mut tls = tokio_rustls::TlsAcceptor::from(Arc::new(server_config)).accept(tcp).await.unwrap();
+///
+///         // Connect info: answer with the response flag and without error
+///         let mut info = [0u8; 64];
+///         tls.read_exact(&mut info).await.unwrap();
+///         let mut rest = vec![0u8; u16::from_le_bytes([info[0], info[1]]) as usize - 64];
+///         tls.read_exact(&mut rest).await.unwrap();
+///         info[0..2].copy_from_slice(&64u16.to_le_bytes());
+///         info[2..4].copy_from_slice(&0x0001u16.to_le_bytes());
+///         tls.write_all(&info).await.unwrap();
+///         tls.flush().await.unwrap();
+///
+///         // Read State: swap source and target, set the response flag and append the state
+///         let mut request = [0u8; 38];
+///         tls.read_exact(&mut request).await.unwrap();
+///         let mut response = request.to_vec();
+///         response[6..14].copy_from_slice(&request[14..22]);
+///         response[14..22].copy_from_slice(&request[6..14]);
+///         response[2..6].copy_from_slice(&40u32.to_le_bytes());
+///         response[24..26].copy_from_slice(&5u16.to_le_bytes());
+///         response[26..30].copy_from_slice(&8u32.to_le_bytes());
+///         response.extend(0u32.to_le_bytes()); // Result
+///         response.extend(5u16.to_le_bytes()); // Run
+///         response.extend(0u16.to_le_bytes());
+///         tls.write_all(&response).await.unwrap();
+///         tls.flush().await.unwrap();
+///         let _ = tls.read(&mut request).await; // Until the client disconnects
+///     });
+///
+///     let known_hosts = std::env::temp_dir().join(format!("ads_client_known_hosts_{}", std::process::id()));
+///     let secure = SecureAds::new("127.0.0.1", "192.168.1.20.1.1")
+///                     .set_port(addr.port())
+///                     .set_trust_on_first_use(&known_hosts)
+///                     .set_credentials("Administrator", "1");
+///
+///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).secure(secure).build().await?;
+///     assert_eq!(ads_client.read_state().await?.ads_state, AdsState::Run);
+///
+///     // The fingerprint of the certificate is pinned
+///     assert!(std::fs::read_to_string(&known_hosts)?.starts_with(&format!("127.0.0.1:{} ", addr.port())));
+///     std::fs::remove_file(&known_hosts)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SecureAds {
+    host            : String,
+    port            : u16,
+    server_name     : Option<String>,
+    local_net_id    : String,
+    local_port      : u16,
+    trust           : Trust,
+    client_cert     : Option<(Vec<u8>, Vec<u8>)>,
+    credentials     : Option<(String, String)>
+}
+
+impl fmt::Debug for SecureAds {
+    // Without the key and password
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SecureAds")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("local_net_id", &self.local_net_id)
+            .field("local_port", &self.local_port)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecureAds {
+    /// - `host` IP address or host name of the target
+    /// - `local_net_id` AmsNetId of the client, the target must have a route to it (see [SecureAds::set_credentials])
+    pub fn new(host: &str, local_net_id: &str) -> Self {
+        SecureAds {
+            host            : String::from(host),
+            port            : SECURE_ADS_PORT,
+            server_name     : None,
+            local_net_id    : String::from(local_net_id),
+            local_port      : LOCAL_PORT_DEFAULT,
+            trust           : Trust::None,
+            client_cert     : None,
+            credentials     : None
+        }
+    }
+
+    /// TCP port of the target, defaults to 8016.
+    pub fn set_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// AMS port of the client, defaults to 30000.
+    pub fn set_local_port(mut self, port: u16) -> Self {
+        self.local_port = port;
+        self
+    }
+
+    /// Name checked against the certificate of the target, defaults to the host.
+    pub fn set_server_name(mut self, server_name: &str) -> Self {
+        self.server_name = Some(String::from(server_name));
+        self
+    }
+
+    /// Verify the certificate of the target with the PEM encoded CA certificate(s).
+    pub fn set_ca(mut self, ca_pem: &[u8]) -> Self {
+        self.trust = Trust::Ca(ca_pem.to_vec());
+        self
+    }
+
+    /// Authenticate the client with a PEM encoded certificate (chain) and private key.
+    pub fn set_client_cert(mut self, cert_pem: &[u8], key_pem: &[u8]) -> Self {
+        self.client_cert = Some((cert_pem.to_vec(), key_pem.to_vec()));
+        self
+    }
+
+    /// Accept the (self-signed) certificate of the target on the first connection and pin its SHA-256 fingerprint
+    /// in `known_hosts`. Later connections fail if the target presents another certificate.
+    pub fn set_trust_on_first_use(mut self, known_hosts: impl Into<PathBuf>) -> Self {
+        self.trust = Trust::FirstUse(known_hosts.into());
+        self
+    }
+
+    /// Authenticate client and target with a pre-shared key instead of certificates (requires the feature *tls-psk*).
+    ///
+    /// The TLS 1.2 PSK cipher suites are offered.
+    #[cfg(feature = "tls-psk")]
+    pub fn set_psk(mut self, identity: &str, key: &[u8]) -> Self {
+        self.trust = Trust::Psk { identity : String::from(identity), key : key.to_vec() };
+        self
+    }
+
+    /// Authenticate with a pre-shared key which is configured on the target with an identity and a password
+    /// (requires the feature *tls-psk*).
+    ///
+    /// TwinCAT derives the key as SHA-256 hash of the upper-case identity followed by the password.
+    #[cfg(feature = "tls-psk")]
+    pub fn set_psk_password(self, identity: &str, password: &str) -> Self {
+        let key = digest::digest(&digest::SHA256, format!("{}{}", identity.to_uppercase(), password).as_bytes());
+        self.set_psk(identity, key.as_ref())
+    }
+
+    /// Let the target add a route to the client with the credentials of a user of the target.
+    pub fn set_credentials(mut self, user: &str, password: &str) -> Self {
+        self.credentials = Some((String::from(user), String::from(password)));
+        self
+    }
+
+    fn client_config(&self, verifier: Option<Arc<FirstUseVerifier>>) -> Result<ClientConfig> {
+        let provider = Arc::new(provider::default_provider());
+        let builder = ClientConfig::builder_with_provider(Arc::clone(&provider))
+                        .with_safe_default_protocol_versions()
+                        .map_err(| e | config_error(e.to_string()))?;
+
+        let builder = match (&self.trust, verifier) {
+            (Trust::Ca(ca_pem), _) => {
+                let mut roots = RootCertStore::empty();
+                for cert in rustls_pemfile::certs(&mut &ca_pem[..]) {
+                    roots.add(cert?).map_err(| e | config_error(format!("Invalid CA certificate: {}", e)))?;
+                }
+                builder.with_root_certificates(roots)
+            },
+            (Trust::FirstUse(_), Some(verifier)) => builder.dangerous().with_custom_certificate_verifier(verifier),
+            _ => return Err(config_error(String::from("Secure ADS requires a CA or trust on first use")))
+        };
+
+        match &self.client_cert {
+            Some((cert_pem, key_pem)) => {
+                let certs = rustls_pemfile::certs(&mut &cert_pem[..]).collect::<std::result::Result<Vec<_>, _>>()?;
+                let key = rustls_pemfile::private_key(&mut &key_pem[..])?
+                            .ok_or_else(|| config_error(String::from("No private key found")))?;
+
+                builder.with_client_auth_cert(certs, key).map_err(| e | config_error(format!("Invalid client certificate: {}", e)))
+            },
+            None => Ok(builder.with_no_client_auth())
+        }
+    }
+
+    fn connect_info(&self, net_id: &[u8; 6]) -> Result<Vec<u8>> {
+        let mut flags = 0;
+
+        if matches!(self.trust, Trust::FirstUse(_)) {
+            flags |= FLAG_SELF_SIGNED;
+        }
+        if self.host.parse::<std::net::IpAddr>().is_ok() {
+            flags |= FLAG_IP_ADDR;
+        }
+
+        let (user, password) = match &self.credentials {
+            Some((user, password)) => {
+                flags |= FLAG_ADD_REMOTE;
+                (user.as_bytes(), password.as_bytes())
+            },
+            None => (&[][..], &[][..])
+        };
+
+        let mut info = vec![0u8; LEN_CONNECT_INFO];
+        info[0..2].copy_from_slice(&u16::try_from(LEN_CONNECT_INFO + user.len() + password.len())?.to_le_bytes());
+        info[2..4].copy_from_slice(&flags.to_le_bytes());
+        info[4] = CONNECT_INFO_VERSION;
+        info[6..12].copy_from_slice(net_id);
+        info[12] = u8::try_from(user.len())?;
+        info[13] = u8::try_from(password.len())?;
+
+        // Name of the route on the target
+        let host_name = self.local_net_id.as_bytes();
+        let len = host_name.len().min(LEN_HOST_NAME - 1);
+        info[LEN_CONNECT_INFO - LEN_HOST_NAME..LEN_CONNECT_INFO - LEN_HOST_NAME + len].copy_from_slice(&host_name[..len]);
+
+        info.extend(user);
+        info.extend(password);
+        Ok(info)
+    }
+
+    /// Open the connection and write the local AmsNetId and port into `answer` (like the router on a port request).
+    pub(crate) async fn connect(&self, answer: &mut [u8; 14]) -> Result<Box<dyn AdsStream>> {
+        let net_id = udp::parse_net_id(&self.local_net_id)?;
+        let target = format!("{}:{}", self.host, self.port);
+
+        let stream : Box<dyn AdsStream> = match &self.trust {
+            #[cfg(feature = "tls-psk")]
+            Trust::Psk { identity, key } => {
+                let mut stream = self.psk_handshake(identity, key, &target).await?;
+                self.exchange_connect_info(&mut stream, &net_id, &target).await?;
+                Box::new(stream)
+            },
+            _ => {
+                let verifier = match &self.trust {
+                    Trust::FirstUse(known_hosts) => Some(Arc::new(FirstUseVerifier::new(target.clone(), known_hosts.clone())?)),
+                    _ => None
+                };
+
+                let config = self.client_config(verifier.clone())?;
+                let server_name = ServerName::try_from(self.server_name.clone().unwrap_or_else(|| self.host.clone()))
+                                    .map_err(| e | config_error(format!("Invalid server name: {}", e)))?;
+
+                let tcp = TcpStream::connect((self.host.as_str(), self.port)).await?;
+                let mut stream = TlsConnector::from(Arc::new(config)).connect(server_name, tcp).await
+                                    .map_err(| e | tls_error(format!("TLS handshake with {} failed: {}", target, e)))?;
+
+                self.exchange_connect_info(&mut stream, &net_id, &target).await?;
+
+                // Pinned only once the target accepted the connection
+                if let Some(verifier) = verifier {
+                    verifier.persist()?;
+                }
+                Box::new(stream)
+            }
+        };
+
+        info!("Secure ADS connection to {} established", target);
+
+        answer[6..12].copy_from_slice(&net_id);
+        answer[12..14].copy_from_slice(&self.local_port.to_le_bytes());
+        Ok(stream)
+    }
+
+    /// Send the connect info and check the response of the target.
+    async fn exchange_connect_info<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: &mut S, net_id: &[u8; 6], target: &str) -> Result<()> {
+        stream.write_all(&self.connect_info(net_id)?).await?;
+        stream.flush().await?;
+
+        let mut response = [0u8; LEN_CONNECT_INFO];
+        stream.read_exact(&mut response).await?;
+
+        let len = u16::from_le_bytes([response[0], response[1]]) as usize;
+        let flags = u16::from_le_bytes([response[2], response[3]]);
+
+        if len > LEN_CONNECT_INFO {
+            stream.read_exact(&mut vec![0u8; len - LEN_CONNECT_INFO]).await?;
+        }
+
+        if flags & FLAG_RESPONSE == 0 || response[5] != 0 {
+            return Err(tls_error(format!("Target {} refused the Secure ADS connection (error {})", target, response[5])));
+        }
+        Ok(())
+    }
+
+    /// TLS handshake with a pre-shared key.
+    #[cfg(feature = "tls-psk")]
+    async fn psk_handshake(&self, identity: &str, key: &[u8], target: &str) -> Result<SslStream<TcpStream>> {
+        let ssl_error = | e : ErrorStack | config_error(format!("Invalid PSK configuration: {}", e));
+
+        let mut builder = SslConnector::builder(SslMethod::tls_client()).map_err(ssl_error)?;
+        builder.set_min_proto_version(Some(SslVersion::TLS1_2)).map_err(ssl_error)?;
+        builder.set_max_proto_version(Some(SslVersion::TLS1_2)).map_err(ssl_error)?;
+        builder.set_cipher_list("PSK").map_err(ssl_error)?;
+
+        let (identity, key) = (identity.as_bytes().to_vec(), key.to_vec());
+        builder.set_psk_client_callback(move | _, _, identity_buf, psk_buf | {
+            // The identity is NUL terminated
+            if identity.len() >= identity_buf.len() || key.len() > psk_buf.len() {
+                return Err(ErrorStack::get());
+            }
+            identity_buf[..identity.len()].copy_from_slice(&identity);
+            identity_buf[identity.len()] = 0;
+            psk_buf[..key.len()].copy_from_slice(&key);
+            Ok(key.len())
+        });
+
+        let ssl = builder.build().configure().map_err(ssl_error)?
+                    .verify_hostname(false)
+                    .into_ssl(self.server_name.as_deref().unwrap_or(&self.host)).map_err(ssl_error)?;
+
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let mut stream = SslStream::new(ssl, tcp).map_err(ssl_error)?;
+
+        Pin::new(&mut stream).connect().await
+            .map_err(| e | tls_error(format!("TLS handshake with {} failed: {}", target, e)))?;
+        Ok(stream)
+    }
+}
+
+/// Serializes the updates of the known hosts files
+static KNOWN_HOSTS_LOCK : Mutex<()> = Mutex::new(());
+
+/// Pins the certificate fingerprint of a target in a file with lines `<host:port> <SHA-256>`.
+#[derive(Debug)]
+struct FirstUseVerifier {
+    target      : String,
+    known_hosts : PathBuf,
+    pinned      : Option<String>,
+    seen        : Mutex<Option<String>>,
+    provider    : CryptoProvider
+}
+
+impl FirstUseVerifier {
+    fn new(target: String, known_hosts: PathBuf) -> Result<Self> {
+        let pinned = FirstUseVerifier::lookup(&known_hosts, &target)?;
+        Ok(FirstUseVerifier { target, known_hosts, pinned, seen : Mutex::new(None), provider : provider::default_provider() })
+    }
+
+    /// Fingerprint pinned for `target` in `known_hosts`.
+    fn lookup(known_hosts: &PathBuf, target: &str) -> Result<Option<String>> {
+        match fs::read_to_string(known_hosts) {
+            Ok(content) => Ok(content.lines()
+                            .filter_map(| line | line.split_once(' '))
+                            .find(| (host, _) | *host == target)
+                            .map(| (_, fingerprint) | String::from(fingerprint.trim()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into())
+        }
+    }
+
+    /// Store the fingerprint of the first connection.
+    fn persist(&self) -> Result<()> {
+        let seen = self.seen.lock().expect("Threading Error").clone();
+
+        let (None, Some(fingerprint)) = (&self.pinned, seen) else {
+            return Ok(());
+        };
+
+        let _lock = KNOWN_HOSTS_LOCK.lock().expect("Threading Error");
+
+        // Another connection might have pinned the target in the meantime
+        match FirstUseVerifier::lookup(&self.known_hosts, &self.target)? {
+            Some(pinned) if pinned == fingerprint => Ok(()),
+            Some(pinned) => Err(tls_error(format!("Certificate of {} changed: {} (pinned {})", self.target, fingerprint, pinned))),
+            None => {
+                warn!("Trust certificate of {} on first use: {}", self.target, fingerprint);
+
+                let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.known_hosts)?;
+                writeln!(file, "{} {}", self.target, fingerprint)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ServerCertVerifier for FirstUseVerifier {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, _intermediates: &[CertificateDer<'_>], _server_name: &ServerName<'_>,
+                            _ocsp_response: &[u8], _now: UnixTime) -> std::result::Result<ServerCertVerified, rustls::Error> {
+
+        let fingerprint : String = digest::digest(&digest::SHA256, end_entity).as_ref().iter().map(| byte | format!("{:02x}", byte)).collect();
+
+        match &self.pinned {
+            Some(pinned) if *pinned != fingerprint => {
+                Err(rustls::Error::General(format!("Certificate of {} changed: {} (pinned {})", self.target, fingerprint, pinned)))
+            },
+            _ => {
+                *self.seen.lock().expect("Threading Error") = Some(fingerprint);
+                Ok(ServerCertVerified::assertion())
+            }
+        }
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::pki_types::PrivatePkcs8KeyDer;
+    use tokio::net::TcpListener;
+
+    fn known_hosts(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ads_client_{}_{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn concurrent_first_use_pins_once() {
+        let path = known_hosts("concurrent");
+
+        let verifiers : Vec<_> = (0..8).map(| _ | {
+            let verifier = FirstUseVerifier::new(String::from("10.0.0.1:8016"), path.clone()).unwrap();
+            *verifier.seen.lock().unwrap() = Some(String::from("ab12"));
+            verifier
+        }).collect();
+
+        std::thread::scope(| scope | {
+            for verifier in &verifiers {
+                scope.spawn(|| verifier.persist().unwrap());
+            }
+        });
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "10.0.0.1:8016 ab12\n");
+
+        // A concurrent connection which saw another certificate fails
+        let verifier = FirstUseVerifier { pinned : None, ..FirstUseVerifier::new(String::from("10.0.0.1:8016"), path.clone()).unwrap() };
+        *verifier.seen.lock().unwrap() = Some(String::from("cd34"));
+        assert!(verifier.persist().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refused_connection_is_not_pinned() {
+        let cert = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+        let server_config = rustls::ServerConfig::builder_with_provider(Arc::new(provider::default_provider()))
+                                .with_safe_default_protocol_versions().unwrap()
+                                .with_no_client_auth()
+                                .with_single_cert(vec![CertificateDer::from(cert.cert.der().to_vec())],
+                                                  PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()))).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut tls = tokio_rustls::TlsAcceptor::from(Arc::new(server_config)).accept(tcp).await.unwrap();
+
+            // Answer the connect info with an error
+            let mut info = [0u8; LEN_CONNECT_INFO];
+            tls.read_exact(&mut info).await.unwrap();
+            info[0..2].copy_from_slice(&(LEN_CONNECT_INFO as u16).to_le_bytes());
+            info[2..4].copy_from_slice(&FLAG_RESPONSE.to_le_bytes());
+            info[5] = 1;
+            tls.write_all(&info).await.unwrap();
+            tls.flush().await.unwrap();
+            let _ = tls.read(&mut info).await;
+        });
+
+        let path = known_hosts("refused");
+        let secure = SecureAds::new("127.0.0.1", "192.168.1.20.1.1").set_port(port).set_trust_on_first_use(&path);
+
+        assert!(secure.connect(&mut [0; 14]).await.is_err());
+        assert!(!path.exists());
+    }
+
+    /// Secure ADS target with a pre-shared key, answers the connect info.
+    #[cfg(feature = "tls-psk")]
+    async fn psk_target(identity: &'static [u8], key: &'static [u8]) -> u16 {
+        use openssl::ssl::{Ssl, SslContext};
+
+        let mut context = SslContext::builder(SslMethod::tls_server()).unwrap();
+        context.set_cipher_list("PSK").unwrap();
+        context.set_psk_server_callback(move | _, client_identity, psk_buf | {
+            if client_identity != Some(identity) {
+                return Err(ErrorStack::get());
+            }
+            psk_buf[..key.len()].copy_from_slice(key);
+            Ok(key.len())
+        });
+        let context = context.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut tls = SslStream::new(Ssl::new(&context).unwrap(), tcp).unwrap();
+            if Pin::new(&mut tls).accept().await.is_err() {
+                return;
+            }
+
+            let mut info = [0u8; LEN_CONNECT_INFO];
+            tls.read_exact(&mut info).await.unwrap();
+            info[0..2].copy_from_slice(&(LEN_CONNECT_INFO as u16).to_le_bytes());
+            info[2..4].copy_from_slice(&FLAG_RESPONSE.to_le_bytes());
+            tls.write_all(&info).await.unwrap();
+            tls.flush().await.unwrap();
+            let _ = tls.read(&mut info).await;
+        });
+        port
+    }
+
+    #[cfg(feature = "tls-psk")]
+    #[tokio::test]
+    async fn psk_connection() {
+        let port = psk_target(b"client", b"0123456789abcdef").await;
+        let secure = SecureAds::new("127.0.0.1", "192.168.1.20.1.1").set_port(port).set_psk("client", b"0123456789abcdef");
+
+        let mut answer = [0; 14];
+        secure.connect(&mut answer).await.unwrap();
+        assert_eq!(&answer[6..14], &[192, 168, 1, 20, 1, 1, 0x30, 0x75]);
+    }
+
+    #[cfg(feature = "tls-psk")]
+    #[tokio::test]
+    async fn psk_with_wrong_key_fails() {
+        let port = psk_target(b"client", b"0123456789abcdef").await;
+        let secure = SecureAds::new("127.0.0.1", "192.168.1.20.1.1").set_port(port).set_psk_password("client", "1");
+
+        assert!(secure.connect(&mut [0; 14]).await.is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Notification of the ADS state of the target.
+use std::sync::Arc;
+use log::{debug, info};
+use tokio::runtime;
+use tokio::sync::{broadcast, watch};
+use crate::{Client, Result, StateInfo, AdsState, ClientEvent, AdsNotificationAttrib, AdsTransMode, NotificationHandle, NotificationSample, NotCallback, NotDelivery, IDX_GRP_DEVICE_DATA};
+
+impl Client {
+
+    /// Register a notification on the ADS state (index group 0xF100) and update `tx` with each change.
+    async fn register_state_notification(&self, tx: &Arc<watch::Sender<StateInfo>>) -> Result<NotificationHandle> {
+        let attrib = AdsNotificationAttrib {
+            cb_length   : 4, // ADS state + device state
+            trans_mode  : AdsTransMode::OnChange,
+            max_delay   : 0,
+            cycle_time  : 0
+        };
+
+        let tx = Arc::clone(tx);
+
+        // Called by the socket reader
+        let callback : NotCallback = Arc::new(move | sample : NotificationSample | {
+            let Some(ads_state) = sample.data.get(0..2)
+                                    .and_then(| data | AdsState::try_from(u16::from_le_bytes([data[0], data[1]])).ok()) else {
+                debug!("Invalid ADS state notification: {:?}", sample.data);
+                return;
+            };
+            let device_state = sample.data.get(2..4).map(| data | u16::from_le_bytes([data[0], data[1]]));
+
+            tx.send_if_modified(| state | {
+                let new_state = StateInfo { ads_state, device_state : device_state.unwrap_or(state.device_state) };
+                let modified = *state != new_state;
+                *state = new_state;
+                modified
+            });
+        });
+
+        let handle = self.add_notification(IDX_GRP_DEVICE_DATA, 0, &attrib, callback, NotDelivery::Inline, None).await?;
+        // The handle must not keep the connection open
+        Ok(NotificationHandle::new(self.detached(), handle))
+    }
+
+    /// Watch the ADS state of the target port instead of polling [Client::read_state].
+    ///
+    /// The receiver is initialized with the current state and updated by a device notification
+    /// on the ADS state and device state (index group 0xF100).
+    ///
+    /// The notification does not survive the connection. It is registered again when the connection 
+    /// is re-established ([ClientEvent::Reconnected]), the state is read once afterwards.
+    /// While the connection is lost, the receiver keeps the last known state.
+    /// 
+    /// The notification is deleted when all receivers are dropped. The receivers are closed 
+    /// when all clones of the client are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
+    ///
+    ///     let mut state = ads_client.watch_state().await?;
+    ///     println!("State: {:?}", *state.borrow());
+    ///
+    ///     while state.changed().await.is_ok() {
+    ///         println!("State changed: {:?}", *state.borrow_and_update());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn watch_state(&self) -> Result<watch::Receiver<StateInfo>> {
+        let (tx, rx) = watch::channel(self.read_state().await?);
+        let tx = Arc::new(tx);
+
+        let guard = self.register_state_notification(&tx).await?;
+        let client = self.detached();
+        let mut events = self.events();
+
+        runtime::Handle::current().spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    _ = client.released() => {
+                        // The connection is closed with the client
+                        guard.detach();
+                        return;
+                    },
+                    event = events.recv() => match event {
+                        Ok(ClientEvent::Reconnected) | Err(broadcast::error::RecvError::Lagged(_)) => (),
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break
+                    }
+                }
+
+                // The first sample of the renewed notification might be missed
+                match client.read_state().await {
+                    Ok(state) => {
+                        tx.send_if_modified(| current | {
+                            let modified = *current != state;
+                            *current = state;
+                            modified
+                        });
+                    },
+                    Err(e) => info!("Failed to read the ADS state after reconnect: {}", e)
+                }
+            }
+
+            let _ = guard.unsubscribe().await;
+        });
+
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::tests::{test_client, receive, respond};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dropped_client_closes_receiver() {
+        let (client, mut remote, _) = test_client();
+
+        let server = async {
+            // ReadState
+            let (header, _) = receive(&mut remote).await;
+            respond(&mut remote, &header, &[0, 0, 0, 0, 5, 0, 0, 0]).await;
+            // AddDeviceNotification
+            let (header, _) = receive(&mut remote).await;
+            respond(&mut remote, &header, &[0u32.to_le_bytes(), 7u32.to_le_bytes()].concat()).await;
+        };
+        let (state, _) = tokio::join!(client.watch_state(), server);
+        let mut state = state.unwrap();
+
+        drop(client);
+
+        let changed = tokio::time::timeout(Duration::from_secs(3), state.changed()).await.expect("Receiver not closed");
+        assert!(changed.is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::sync::mpsc;
+use crate::{Client, Result, AdsDecode, NotificationHandle, AdsNotificationAttrib, AdsNotificationAttribBuilder, AdsTransMode, NotCallback, NotDelivery, NotificationSample, CAPACITY_SAMPLES};
+use crate::plc_types::size_error;
+
+/// Value of a notification delivered by a [Subscription].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    pub data        : Bytes,
+    /// Number of samples dropped before this sample because the subscriber lagged behind
+    pub lost        : u64
+}
+
+/// Notifications of a variable as asynchronous [Stream] of [Sample]s.
+///
+/// Created by [Client::subscribe]. The samples are delivered in the order of arrival. They are buffered
+/// in a bounded queue, if the subscriber does not keep up, new samples are dropped and reported by [Sample::lost].
+///
+/// The notification is deleted when the subscription is dropped.
+#[derive(Debug)]
+pub struct Subscription {
+    guard   : NotificationHandle,
+    rx      : mpsc::Receiver<Sample>
+}
+
+impl Subscription {
+    /// Handle of the notification (see [NotificationHandle::handle]).
+    pub fn handle(&self) -> u32 {
+        self.guard.handle()
+    }
+
+    /// Wait for the next sample.
+    ///
+    /// Returns `None` if the notification was deleted.
+    pub async fn recv(&mut self) -> Option<Sample> {
+        self.rx.recv().await
+    }
+
+    /// Delete the notification and wait for the response of the target.
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.guard.unsubscribe().await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Sample;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Sample>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Decoded value of a notification delivered by a [TypedSubscription].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedSample<T> {
+    /// Time stamp as Windows FILETIME (100 ns since 1601-01-01)
+    pub timestamp   : u64,
+    pub value       : T,
+    /// Number of samples dropped before this sample because the subscriber lagged behind
+    pub lost        : u64
+}
+
+/// Notifications of a variable as asynchronous [Stream] of decoded values.
+///
+/// Created by [Client::subscribe_typed]. A sample which cannot be decoded is reported as error,
+/// the subscription continues with the next sample.
+#[derive(Debug)]
+pub struct TypedSubscription<T> {
+    inner   : Subscription,
+    _type   : PhantomData<fn() -> T>
+}
+
+impl<T: AdsDecode> TypedSubscription<T> {
+    fn decode(sample: Sample) -> Result<TypedSample<T>> {
+        T::decode(&sample.data).map(| value | TypedSample { timestamp : sample.timestamp, value, lost : sample.lost })
+    }
+
+    /// Handle of the notification (see [NotificationHandle::handle]).
+    pub fn handle(&self) -> u32 {
+        self.inner.handle()
+    }
+
+    /// Wait for the next sample.
+    ///
+    /// Returns `None` if the notification was deleted.
+    pub async fn recv(&mut self) -> Option<Result<TypedSample<T>>> {
+        self.inner.recv().await.map(Self::decode)
+    }
+
+    /// Delete the notification, release the symbol handle and wait for the response of the target.
+    pub async fn unsubscribe(self) -> Result<()> {
+        self.inner.unsubscribe().await
+    }
+}
+
+impl<T: AdsDecode> Stream for TypedSubscription<T> {
+    type Item = Result<TypedSample<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.rx.poll_recv(cx).map(| sample | sample.map(Self::decode))
+    }
+}
+
+impl Client {
+
+    /// Register a notification and receive the samples as [Stream].
+    ///
+    /// In contrast to [Client::add_device_notification], no callback is required. The notification is deleted
+    /// when the [Subscription] is dropped or with [Subscription::unsubscribe].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, AdsNotificationAttrib, AdsNotificationAttribBuilder, AdsTransMode, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let attrib = AdsNotificationAttrib {
+    ///         cb_length   : 2, // UINT
+    ///         trans_mode  : AdsTransMode::OnChange,
+    ///         max_delay   : 0,
+    ///         cycle_time  : 0
+    ///     };
+    ///
+    ///     let hdl = ads_client.symbol_handle("MAIN.n_cnt_a").await?;
+    ///     let mut subscription = ads_client.subscribe(0xF005, hdl, &attrib).await?;
+    ///
+    ///     while let Some(sample) = subscription.recv().await {
+    ///         if sample.lost > 0 {
+    ///             println!("{} samples lost", sample.lost);
+    ///         }
+    ///         println!("MAIN.n_cnt_a: {:?}", &sample.data[..]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn subscribe(&self, idx_grp: u32, idx_offs: u32, attributes: &AdsNotificationAttrib) -> Result<Subscription> {
+        self.subscribe_notification(idx_grp, idx_offs, attributes).await
+    }
+
+    /// Register a notification for `symbol` (e.g. `MAIN.n_cnt_a`) and receive the samples as [Stream].
+    ///
+    /// A symbol handle is acquired for the subscription and `cb_length` is taken from the symbol information.
+    /// Both the notification and the symbol handle are released when the
+    /// [Subscription] is dropped or with [Subscription::unsubscribe].
+    ///
+    /// If the online change detection is enabled ([ClientBuilder::set_online_change_detection](crate::ClientBuilder::set_online_change_detection)),
+    /// the handle is renewed and the notification re-registered after an online change.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, AdsTransMode, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let mut subscription = ads_client.subscribe_symbol("MAIN.n_cnt_a", AdsTransMode::OnChange, Duration::ZERO).await?;
+    ///
+    ///     while let Some(sample) = subscription.recv().await {
+    ///         println!("MAIN.n_cnt_a: {:?}", &sample.data[..]);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn subscribe_symbol(&self, symbol: &str, trans_mode: AdsTransMode, cycle_time: Duration) -> Result<Subscription> {
+        let info = self.symbol_info(symbol).await?;
+        self.subscribe_owned(symbol, info.size, trans_mode, cycle_time).await
+    }
+
+    /// Register a notification for `symbol` and receive the samples decoded as `T`.
+    ///
+    /// The size of `T` is checked against the symbol information. Like [Client::subscribe_symbol], the
+    /// notification and the symbol handle are released when the [TypedSubscription] is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, AdsTransMode, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     // UINT
+    ///     let mut subscription = ads_client.subscribe_typed::<u16>("MAIN.n_cnt_a", AdsTransMode::OnChange, Duration::ZERO).await?;
+    ///
+    ///     while let Some(sample) = subscription.recv().await {
+    ///         match sample {
+    ///             Ok(sample) => println!("MAIN.n_cnt_a: {}", sample.value),
+    ///             Err(err) => eprintln!("Invalid sample: {}", err)
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn subscribe_typed<T: AdsDecode>(&self, symbol: &str, trans_mode: AdsTransMode, cycle_time: Duration) -> Result<TypedSubscription<T>> {
+        let info = self.symbol_info(symbol).await?;
+
+        if info.size as usize != T::SIZE {
+            return Err(size_error(info.size as usize, T::SIZE));
+        }
+
+        let inner = self.subscribe_owned(symbol, info.size, trans_mode, cycle_time).await?;
+        Ok(TypedSubscription { inner, _type : PhantomData })
+    }
+
+    /// Subscribe with a symbol handle owned by the notification.
+    async fn subscribe_owned(&self, symbol: &str, size: u32, trans_mode: AdsTransMode, cycle_time: Duration) -> Result<Subscription> {
+        let attributes = AdsNotificationAttribBuilder::new(size, trans_mode)
+                            .set_cycle_time(cycle_time)
+                            .build()?;
+
+        let (callback, rx) = Client::sample_channel();
+        let guard = self.add_symbol_notification(symbol, &attributes, callback, NotDelivery::Inline).await?;
+
+        Ok(Subscription { guard, rx })
+    }
+
+    async fn subscribe_notification(&self, idx_grp: u32, idx_offs: u32, attributes: &AdsNotificationAttrib) -> Result<Subscription> {
+        let (callback, rx) = Client::sample_channel();
+        let handle = self.add_notification(idx_grp, idx_offs, attributes, callback, NotDelivery::Inline, None).await?;
+
+        Ok(Subscription { guard : NotificationHandle::new(self.clone(), handle), rx })
+    }
+
+    /// Callback which feeds the queue of a subscription.
+    fn sample_channel() -> (NotCallback, mpsc::Receiver<Sample>) {
+        let (tx, rx) = mpsc::channel::<Sample>(CAPACITY_SAMPLES);
+        let lost = Arc::new(AtomicU64::new(0));
+
+        // Called in sequence by the socket reader, must not block
+        let callback : NotCallback = Arc::new(move | sample : NotificationSample | {
+            let sample = Sample { timestamp : sample.timestamp, data : sample.data, lost : lost.swap(0, Ordering::Relaxed) };
+
+            if let Err(mpsc::error::TrySendError::Full(sample)) = tx.try_send(sample) {
+                lost.fetch_add(sample.lost + 1, Ordering::Relaxed);
+            }
+        });
+
+        (callback, rx)
+    }
+}
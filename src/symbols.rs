@@ -0,0 +1,859 @@
+//! Symbol and data type information uploaded from the target.
+use std::collections::HashMap;
+use std::sync::Arc;
+use log::info;
+use crate::{Client, Result, AdsError, AdsErrorCode, IDX_GRP_SYM_INFO_BY_NAME, IDX_GRP_SYM_UPLOAD, IDX_GRP_SYM_DT_UPLOAD, IDX_GRP_SYM_UPLOAD_INFO2};
+
+// Data type identifiers (ADST_*)
+// https://infosys.beckhoff.com/content/1033/tc3_ads.net/9407515403.html
+pub(crate) const ADST_VOID      : u32 = 0;
+pub(crate) const ADST_INT16     : u32 = 2;
+pub(crate) const ADST_INT32     : u32 = 3;
+pub(crate) const ADST_REAL32    : u32 = 4;
+pub(crate) const ADST_REAL64    : u32 = 5;
+pub(crate) const ADST_INT8      : u32 = 16;
+pub(crate) const ADST_UINT8     : u32 = 17;
+pub(crate) const ADST_UINT16    : u32 = 18;
+pub(crate) const ADST_UINT32    : u32 = 19;
+pub(crate) const ADST_INT64     : u32 = 20;
+pub(crate) const ADST_UINT64    : u32 = 21;
+pub(crate) const ADST_STRING    : u32 = 30;
+pub(crate) const ADST_WSTRING   : u32 = 31;
+pub(crate) const ADST_BIT       : u32 = 33;
+pub(crate) const ADST_BIGTYPE   : u32 = 65;
+
+// Flags of a data type entry (ADSDATATYPEFLAG_*)
+pub(crate) const FLAG_BITVALUES     : u32 = 0x0000_0020;
+pub(crate) const FLAG_PROPITEM      : u32 = 0x0000_0040;
+const FLAG_TYPEGUID                 : u32 = 0x0000_0080;
+const FLAG_COPYMASK                 : u32 = 0x0000_0200;
+const FLAG_METHODINFOS              : u32 = 0x0000_0800;
+const FLAG_ATTRIBUTES               : u32 = 0x0000_1000;
+const FLAG_ENUMINFOS                : u32 = 0x0000_2000;
+pub(crate) const FLAG_STATIC        : u32 = 0x0002_0000;
+
+const LEN_UPLOAD_INFO2      : usize = 24;
+const LEN_SYMBOL_INFO_MAX   : usize = 0xFFFF; // Entry of a single symbol incl. name, type and comment
+
+fn invalid_entry(s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : format!("Invalid upload data: {}", s_msg)}
+}
+
+/// Reads the fields of an upload entry in sequence.
+struct EntryReader<'a> {
+    buf : &'a [u8],
+    pos : usize
+}
+
+impl<'a> EntryReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        EntryReader { buf, pos : 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len)
+                    .filter(|end| *end <= self.buf.len())
+                    .ok_or_else(|| invalid_entry("entry exceeds the buffer"))?;
+
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    /// Null terminated string of `len` characters.
+    fn string(&mut self, len: usize) -> Result<String> {
+        let s = self.bytes(len)?.iter().map(|c| char::from(*c)).collect();
+        self.bytes(1)?; // Null terminator
+        Ok(s)
+    }
+
+    /// Nested entry which is prefixed by its length.
+    fn entry(&mut self) -> Result<&'a [u8]> {
+        let len = u32::from_le_bytes(self.buf.get(self.pos..self.pos + 4).ok_or_else(|| invalid_entry("missing entry length"))?.try_into()?);
+
+        if len < 4 {
+            return Err(invalid_entry("entry length too small"));
+        }
+        self.bytes(len as usize)
+    }
+}
+
+/// Dimension of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayInfo {
+    pub lower_bound : i32,
+    pub elements    : u32
+}
+
+/// Value of an enumeration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumInfo {
+    pub name    : String,
+    pub value   : i64
+}
+
+/// Symbol of the target as listed in the symbol upload (index group 0xF00B).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub name        : String,
+    pub type_name   : String,
+    pub comment     : String,
+    pub idx_grp     : u32,
+    pub idx_offs    : u32,
+    /// Size \[byte\]
+    pub size        : u32,
+    /// ADS data type identifier (ADST_*)
+    pub data_type   : u32,
+    pub flags       : u16
+}
+
+impl SymbolInfo {
+    fn parse(entry: &[u8]) -> Result<Self> {
+        let mut rd = EntryReader::new(entry);
+
+        let _entry_len  = rd.u32()?;
+        let idx_grp     = rd.u32()?;
+        let idx_offs    = rd.u32()?;
+        let size        = rd.u32()?;
+        let data_type   = rd.u32()?;
+        let flags       = rd.u16()?;
+        let _array_dim  = rd.u16()?; // Legacy
+        let name_len    = rd.u16()? as usize;
+        let type_len    = rd.u16()? as usize;
+        let comment_len = rd.u16()? as usize;
+
+        Ok(SymbolInfo {
+            name        : rd.string(name_len)?,
+            type_name   : rd.string(type_len)?,
+            comment     : rd.string(comment_len)?,
+            idx_grp,
+            idx_offs,
+            size,
+            data_type,
+            flags
+        })
+    }
+}
+
+// Flags of a method parameter (ADSMETHODPARAFLAG_*)
+const PARA_FLAG_IN      : u32 = 0x0000_0001;
+const PARA_FLAG_OUT     : u32 = 0x0000_0002;
+
+/// Parameter of a method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodParamInfo {
+    pub name        : String,
+    pub type_name   : String,
+    pub comment     : String,
+    /// Size \[byte\]
+    pub size        : u32,
+    pub align_size  : u32,
+    /// ADS data type identifier (ADST_*)
+    pub data_type   : u32,
+    pub flags       : u32
+}
+
+impl MethodParamInfo {
+    fn parse(entry: &[u8]) -> Result<Self> {
+        let mut rd = EntryReader::new(entry);
+
+        let _entry_len  = rd.u32()?;
+        let size        = rd.u32()?;
+        let align_size  = rd.u32()?;
+        let data_type   = rd.u32()?;
+        let flags       = rd.u32()?;
+        let _reserved   = rd.u32()?;
+        let _type_guid  = rd.bytes(16)?;
+        let _len_para   = rd.u16()?;
+        let name_len    = rd.u16()? as usize;
+        let type_len    = rd.u16()? as usize;
+        let comment_len = rd.u16()? as usize;
+
+        Ok(MethodParamInfo {
+            name        : rd.string(name_len)?,
+            type_name   : rd.string(type_len)?,
+            comment     : rd.string(comment_len)?,
+            size,
+            align_size,
+            data_type,
+            flags
+        })
+    }
+
+    /// `VAR_INPUT` or `VAR_IN_OUT`
+    pub fn is_input(&self) -> bool {
+        self.flags & PARA_FLAG_IN != 0
+    }
+
+    /// `VAR_OUTPUT` or `VAR_IN_OUT`
+    pub fn is_output(&self) -> bool {
+        self.flags & PARA_FLAG_OUT != 0
+    }
+}
+
+/// Method of a function block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodInfo {
+    pub name                : String,
+    /// Empty if the method has no return value
+    pub return_type         : String,
+    pub comment             : String,
+    /// Size of the return value \[byte\]
+    pub return_size         : u32,
+    pub return_align_size   : u32,
+    /// ADS data type identifier of the return value (ADST_*)
+    pub return_data_type    : u32,
+    pub flags               : u32,
+    pub params              : Vec<MethodParamInfo>
+}
+
+impl MethodInfo {
+    fn parse(entry: &[u8]) -> Result<Self> {
+        let mut rd = EntryReader::new(entry);
+
+        let _entry_len          = rd.u32()?;
+        let _version            = rd.u32()?;
+        let _vtable_idx         = rd.u32()?;
+        let return_size         = rd.u32()?;
+        let return_align_size   = rd.u32()?;
+        let _reserved           = rd.u32()?;
+        let _return_type_guid   = rd.bytes(16)?;
+        let return_data_type    = rd.u32()?;
+        let flags               = rd.u32()?;
+        let name_len            = rd.u16()? as usize;
+        let return_type_len     = rd.u16()? as usize;
+        let comment_len         = rd.u16()? as usize;
+        let params              = rd.u16()?;
+
+        let mut info = MethodInfo {
+            name                : rd.string(name_len)?,
+            return_type         : rd.string(return_type_len)?,
+            comment             : rd.string(comment_len)?,
+            return_size,
+            return_align_size,
+            return_data_type,
+            flags,
+            params              : Vec::with_capacity(params as usize)
+        };
+
+        for _ in 0..params {
+            info.params.push(MethodParamInfo::parse(rd.entry()?)?);
+        }
+        Ok(info)
+    }
+}
+
+/// Data type of the target as listed in the data type upload (index group 0xF00E).
+///
+/// Members of structs and function blocks are described by `sub_items`. For a member,
+/// `name` is the name of the member, `type_name` its data type and `offs` the offset within the parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTypeInfo {
+    pub name        : String,
+    /// Base type or, for arrays, the type of the elements
+    pub type_name   : String,
+    pub comment     : String,
+    /// Size \[byte\]
+    pub size        : u32,
+    /// Offset within the parent data type \[byte\]
+    pub offs        : u32,
+    /// ADS data type identifier (ADST_*)
+    pub data_type   : u32,
+    pub flags       : u32,
+    pub array_infos : Vec<ArrayInfo>,
+    pub sub_items   : Vec<DataTypeInfo>,
+    pub attributes  : Vec<(String, String)>,
+    pub enum_infos  : Vec<EnumInfo>,
+    /// Methods of a function block
+    pub methods     : Vec<MethodInfo>
+}
+
+impl DataTypeInfo {
+    fn parse(entry: &[u8]) -> Result<Self> {
+        let mut rd = EntryReader::new(entry);
+
+        let _entry_len  = rd.u32()?;
+        let _version    = rd.u32()?;
+        let _hash       = rd.u32()?;
+        let _type_hash  = rd.u32()?;
+        let size        = rd.u32()?;
+        let offs        = rd.u32()?;
+        let data_type   = rd.u32()?;
+        let flags       = rd.u32()?;
+        let name_len    = rd.u16()? as usize;
+        let type_len    = rd.u16()? as usize;
+        let comment_len = rd.u16()? as usize;
+        let array_dim   = rd.u16()?;
+        let sub_items   = rd.u16()?;
+
+        let mut info = DataTypeInfo {
+            name        : rd.string(name_len)?,
+            type_name   : rd.string(type_len)?,
+            comment     : rd.string(comment_len)?,
+            size,
+            offs,
+            data_type,
+            flags,
+            array_infos : Vec::with_capacity(array_dim as usize),
+            sub_items   : Vec::with_capacity(sub_items as usize),
+            attributes  : Vec::new(),
+            enum_infos  : Vec::new(),
+            methods     : Vec::new()
+        };
+
+        for _ in 0..array_dim {
+            info.array_infos.push(ArrayInfo { lower_bound : rd.u32()? as i32, elements : rd.u32()? });
+        }
+
+        for _ in 0..sub_items {
+            info.sub_items.push(DataTypeInfo::parse(rd.entry()?)?);
+        }
+
+        if flags & FLAG_TYPEGUID != 0 {
+            rd.bytes(16)?;
+        }
+
+        if flags & FLAG_COPYMASK != 0 {
+            rd.bytes(size as usize)?;
+        }
+
+        if flags & FLAG_METHODINFOS != 0 {
+            for _ in 0..rd.u16()? {
+                info.methods.push(MethodInfo::parse(rd.entry()?)?);
+            }
+        }
+
+        if flags & FLAG_ATTRIBUTES != 0 {
+            for _ in 0..rd.u16()? {
+                let name_len    = rd.u8()? as usize;
+                let value_len   = rd.u8()? as usize;
+                info.attributes.push((rd.string(name_len)?, rd.string(value_len)?));
+            }
+        }
+
+        if flags & FLAG_ENUMINFOS != 0 {
+            for _ in 0..rd.u16()? {
+                let name_len    = rd.u8()? as usize;
+                let name        = rd.string(name_len)?;
+                let value       = int_from_le(rd.bytes(size as usize)?, is_signed(data_type))?;
+                info.enum_infos.push(EnumInfo { name, value });
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Value of the attribute `name` (e.g. `{attribute 'TcRpcEnable'}`).
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub(crate) fn is_signed(data_type: u32) -> bool {
+    matches!(data_type, ADST_INT8 | ADST_INT16 | ADST_INT32 | ADST_INT64)
+}
+
+/// Little endian integer of 1, 2, 4 or 8 byte.
+pub(crate) fn int_from_le(buf: &[u8], signed: bool) -> Result<i64> {
+    Ok(match (buf.len(), signed) {
+        (1, true)   => i8::from_le_bytes(buf.try_into()?) as i64,
+        (1, false)  => u8::from_le_bytes(buf.try_into()?) as i64,
+        (2, true)   => i16::from_le_bytes(buf.try_into()?) as i64,
+        (2, false)  => u16::from_le_bytes(buf.try_into()?) as i64,
+        (4, true)   => i32::from_le_bytes(buf.try_into()?) as i64,
+        (4, false)  => u32::from_le_bytes(buf.try_into()?) as i64,
+        (8, _)      => i64::from_le_bytes(buf.try_into()?),
+        (len, _)    => return Err(invalid_entry(&format!("integer of {} byte not supported", len)))
+    })
+}
+
+/// Split `ARRAY [1..3, 0..9] OF INT` into its dimensions and the element type.
+pub(crate) fn parse_array_type(type_name: &str) -> Option<(Vec<ArrayInfo>, &str)> {
+    let rest = type_name.trim().strip_prefix("ARRAY")?.trim_start().strip_prefix('[')?;
+    let (dims, rest) = rest.split_once(']')?;
+    let element = rest.trim_start().strip_prefix("OF")?.trim();
+
+    let array_infos = dims.split(',')
+        .map(|dim| {
+            let (lower, upper) = dim.split_once("..")?;
+            let lower : i32 = lower.trim().parse().ok()?;
+            let upper : i32 = upper.trim().parse().ok()?;
+            let elements = u32::try_from(upper.checked_sub(lower)?.checked_add(1)?).ok()?;
+            Some(ArrayInfo { lower_bound : lower, elements })
+        })
+        .collect::<Option<Vec<ArrayInfo>>>()?;
+
+    Some((array_infos, element))
+}
+
+/// Data type identifier and size of the elementary IEC 61131-3 data types which are not part of the data type upload.
+pub(crate) fn elementary_type(type_name: &str) -> Option<(u32, u32)> {
+    let type_name = type_name.trim().to_ascii_uppercase();
+
+    let string_len = |prefix: &str| -> Option<u32> {
+        match type_name.strip_prefix(prefix)?.trim() {
+            ""  => Some(80),
+            len => len.strip_prefix('(')?.strip_suffix(')')?.trim().parse().ok()
+        }
+    };
+
+    if let Some(len) = string_len("STRING") {
+        return Some((ADST_STRING, len + 1));
+    }
+    if let Some(len) = string_len("WSTRING") {
+        return Some((ADST_WSTRING, 2 * (len + 1)));
+    }
+
+    Some(match type_name.as_str() {
+        "BOOL"                                  => (ADST_BIT, 1),
+        "BYTE" | "USINT"                        => (ADST_UINT8, 1),
+        "SINT"                                  => (ADST_INT8, 1),
+        "WORD" | "UINT"                         => (ADST_UINT16, 2),
+        "INT"                                   => (ADST_INT16, 2),
+        "DWORD" | "UDINT" | "TIME" | "TOD" | "TIME_OF_DAY" | "DATE" | "DT" | "DATE_AND_TIME" => (ADST_UINT32, 4),
+        "DINT"                                  => (ADST_INT32, 4),
+        "LWORD" | "ULINT" | "LTIME"             => (ADST_UINT64, 8),
+        "LINT"                                  => (ADST_INT64, 8),
+        "REAL"                                  => (ADST_REAL32, 4),
+        "LREAL"                                 => (ADST_REAL64, 8),
+        _                                       => return None
+    })
+}
+
+/// Symbols and data types of an ADS device.
+///
+/// Obtain the table of a target with [Client::symbol_table]. Names are compared case-insensitive,
+/// as in IEC 61131-3.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols     : HashMap<String, SymbolInfo>,
+    data_types  : HashMap<String, DataTypeInfo>
+}
+
+impl SymbolTable {
+    /// Parse the raw data of the symbol upload (index group 0xF00B) and the data type upload (index group 0xF00E).
+    pub fn from_upload(symbols: &[u8], data_types: &[u8]) -> Result<Self> {
+        let mut table = SymbolTable::default();
+
+        let mut rd = EntryReader::new(symbols);
+        while rd.pos < symbols.len() {
+            let symbol = SymbolInfo::parse(rd.entry()?)?;
+            table.symbols.insert(symbol.name.to_ascii_lowercase(), symbol);
+        }
+
+        let mut rd = EntryReader::new(data_types);
+        while rd.pos < data_types.len() {
+            let data_type = DataTypeInfo::parse(rd.entry()?)?;
+            table.data_types.insert(data_type.name.to_ascii_lowercase(), data_type);
+        }
+
+        Ok(table)
+    }
+
+    /// Look up a symbol by its full name (e.g. `MAIN.n_cnt_a`).
+    pub fn symbol(&self, name: &str) -> Option<&SymbolInfo> {
+        self.symbols.get(&name.to_ascii_lowercase())
+    }
+
+    /// Look up a data type by its name (e.g. `TON`).
+    pub fn data_type(&self, name: &str) -> Option<&DataTypeInfo> {
+        self.data_types.get(&name.to_ascii_lowercase())
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &SymbolInfo> {
+        self.symbols.values()
+    }
+
+    pub fn data_types(&self) -> impl Iterator<Item = &DataTypeInfo> {
+        self.data_types.values()
+    }
+}
+
+impl Client {
+
+    /// Upload the symbols and data types from the target.
+    async fn upload_symbol_table(&self) -> Result<SymbolTable> {
+        let mut upload_info : [u8; LEN_UPLOAD_INFO2] = [0; LEN_UPLOAD_INFO2];
+        self.read(IDX_GRP_SYM_UPLOAD_INFO2, 0, &mut upload_info).await?;
+
+        let n_symbols       = u32::from_le_bytes(upload_info[0..4].try_into()?);
+        let len_symbols     = u32::from_le_bytes(upload_info[4..8].try_into()?) as usize;
+        let n_data_types    = u32::from_le_bytes(upload_info[8..12].try_into()?);
+        let len_data_types  = u32::from_le_bytes(upload_info[12..16].try_into()?) as usize;
+
+        info!("Upload {} symbols ({} byte) and {} data types ({} byte)", n_symbols, len_symbols, n_data_types, len_data_types);
+
+        let mut symbols = vec![0; len_symbols];
+        let len = self.read(IDX_GRP_SYM_UPLOAD, 0, &mut symbols).await? as usize;
+        symbols.truncate(len);
+
+        let mut data_types = vec![0; len_data_types];
+        let len = self.read(IDX_GRP_SYM_DT_UPLOAD, 0, &mut data_types).await? as usize;
+        data_types.truncate(len);
+
+        SymbolTable::from_upload(&symbols, &data_types)
+    }
+
+    /// Get the information of a single symbol (index group 0xF009) without uploading the symbol table.
+    ///
+    /// In contrast to [SymbolTable::symbol], `symbol` may refer to a member or an array element (e.g. `MAIN.axes[3].status`).
+    pub async fn symbol_info(&self, symbol: &str) -> Result<SymbolInfo> {
+        let mut entry = vec![0; LEN_SYMBOL_INFO_MAX];
+        let len = self.read_write(IDX_GRP_SYM_INFO_BY_NAME, 0, &mut entry, symbol.as_bytes()).await? as usize;
+
+        SymbolInfo::parse(&entry[..len.min(LEN_SYMBOL_INFO_MAX)])
+    }
+
+    /// Get the symbols and data types of the target.
+    ///
+    /// The table is uploaded on the first call and cached afterwards. If the online change detection is
+    /// enabled ([ClientBuilder::set_online_change_detection](crate::ClientBuilder::set_online_change_detection)),
+    /// the cache is discarded after an online change.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     let table = ads_client.symbol_table().await?;
+    ///
+    ///     for symbol in table.symbols() {
+    ///         println!("{} : {} ({} byte)", symbol.name, symbol.type_name, symbol.size);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn symbol_table(&self) -> Result<Arc<SymbolTable>> {
+        let cached = self.symbol_table.lock().expect("Threading Error").clone();
+
+        if let Some(table) = cached {
+            return Ok(table);
+        }
+
+        let table = Arc::new(self.upload_symbol_table().await?);
+        *self.symbol_table.lock().expect("Threading Error") = Some(Arc::clone(&table));
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn put_string(buf: &mut Vec<u8>, s: &str) {
+        buf.extend_from_slice(s.as_bytes());
+        buf.push(0);
+    }
+
+    /// Prefix the entry with its length.
+    fn finish_entry(mut entry: Vec<u8>) -> Vec<u8> {
+        let len = entry.len() as u32;
+        entry[0..4].copy_from_slice(&len.to_le_bytes());
+        entry
+    }
+
+    /// Entry of the symbol upload.
+    pub(crate) fn encode_symbol(info: &SymbolInfo) -> Vec<u8> {
+        let mut entry = vec![0; 4];
+        entry.extend_from_slice(&info.idx_grp.to_le_bytes());
+        entry.extend_from_slice(&info.idx_offs.to_le_bytes());
+        entry.extend_from_slice(&info.size.to_le_bytes());
+        entry.extend_from_slice(&info.data_type.to_le_bytes());
+        entry.extend_from_slice(&info.flags.to_le_bytes());
+        entry.extend_from_slice(&0u16.to_le_bytes());
+        entry.extend_from_slice(&(info.name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.type_name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.comment.len() as u16).to_le_bytes());
+        put_string(&mut entry, &info.name);
+        put_string(&mut entry, &info.type_name);
+        put_string(&mut entry, &info.comment);
+        finish_entry(entry)
+    }
+
+    fn encode_param(info: &MethodParamInfo) -> Vec<u8> {
+        let mut entry = vec![0; 4];
+        entry.extend_from_slice(&info.size.to_le_bytes());
+        entry.extend_from_slice(&info.align_size.to_le_bytes());
+        entry.extend_from_slice(&info.data_type.to_le_bytes());
+        entry.extend_from_slice(&info.flags.to_le_bytes());
+        entry.extend_from_slice(&[0; 20]); // Reserved, type GUID
+        entry.extend_from_slice(&0u16.to_le_bytes());
+        entry.extend_from_slice(&(info.name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.type_name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.comment.len() as u16).to_le_bytes());
+        put_string(&mut entry, &info.name);
+        put_string(&mut entry, &info.type_name);
+        put_string(&mut entry, &info.comment);
+        finish_entry(entry)
+    }
+
+    fn encode_method(info: &MethodInfo) -> Vec<u8> {
+        let mut entry = vec![0; 4];
+        entry.extend_from_slice(&[0; 8]); // Version, vtable index
+        entry.extend_from_slice(&info.return_size.to_le_bytes());
+        entry.extend_from_slice(&info.return_align_size.to_le_bytes());
+        entry.extend_from_slice(&[0; 20]); // Reserved, type GUID
+        entry.extend_from_slice(&info.return_data_type.to_le_bytes());
+        entry.extend_from_slice(&info.flags.to_le_bytes());
+        entry.extend_from_slice(&(info.name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.return_type.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.comment.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.params.len() as u16).to_le_bytes());
+        put_string(&mut entry, &info.name);
+        put_string(&mut entry, &info.return_type);
+        put_string(&mut entry, &info.comment);
+
+        for param in &info.params {
+            entry.extend(encode_param(param));
+        }
+        finish_entry(entry)
+    }
+
+    /// Entry of the data type upload, the flags of the optional parts are set as required.
+    pub(crate) fn encode_data_type(info: &DataTypeInfo) -> Vec<u8> {
+        let mut flags = info.flags;
+        if !info.methods.is_empty()     { flags |= FLAG_METHODINFOS; }
+        if !info.attributes.is_empty()  { flags |= FLAG_ATTRIBUTES; }
+        if !info.enum_infos.is_empty()  { flags |= FLAG_ENUMINFOS; }
+
+        let mut entry = vec![0; 4];
+        entry.extend_from_slice(&[0; 12]); // Version, hash, type hash
+        entry.extend_from_slice(&info.size.to_le_bytes());
+        entry.extend_from_slice(&info.offs.to_le_bytes());
+        entry.extend_from_slice(&info.data_type.to_le_bytes());
+        entry.extend_from_slice(&flags.to_le_bytes());
+        entry.extend_from_slice(&(info.name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.type_name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.comment.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.array_infos.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&(info.sub_items.len() as u16).to_le_bytes());
+        put_string(&mut entry, &info.name);
+        put_string(&mut entry, &info.type_name);
+        put_string(&mut entry, &info.comment);
+
+        for dim in &info.array_infos {
+            entry.extend_from_slice(&dim.lower_bound.to_le_bytes());
+            entry.extend_from_slice(&dim.elements.to_le_bytes());
+        }
+
+        for item in &info.sub_items {
+            entry.extend(encode_data_type(item));
+        }
+
+        if flags & FLAG_TYPEGUID != 0 {
+            entry.extend_from_slice(&[0; 16]);
+        }
+
+        if flags & FLAG_COPYMASK != 0 {
+            entry.extend(std::iter::repeat_n(0xFF, info.size as usize));
+        }
+
+        if flags & FLAG_METHODINFOS != 0 {
+            entry.extend_from_slice(&(info.methods.len() as u16).to_le_bytes());
+            for method in &info.methods {
+                entry.extend(encode_method(method));
+            }
+        }
+
+        if flags & FLAG_ATTRIBUTES != 0 {
+            entry.extend_from_slice(&(info.attributes.len() as u16).to_le_bytes());
+            for (name, value) in &info.attributes {
+                entry.push(name.len() as u8);
+                entry.push(value.len() as u8);
+                put_string(&mut entry, name);
+                put_string(&mut entry, value);
+            }
+        }
+
+        if flags & FLAG_ENUMINFOS != 0 {
+            entry.extend_from_slice(&(info.enum_infos.len() as u16).to_le_bytes());
+            for value in &info.enum_infos {
+                entry.push(value.name.len() as u8);
+                put_string(&mut entry, &value.name);
+                entry.extend_from_slice(&value.value.to_le_bytes()[..info.size as usize]);
+            }
+        }
+        finish_entry(entry)
+    }
+
+    pub(crate) fn symbol(name: &str, type_name: &str, idx_offs: u32, size: u32, data_type: u32) -> SymbolInfo {
+        SymbolInfo {
+            name        : name.into(),
+            type_name   : type_name.into(),
+            comment     : String::new(),
+            idx_grp     : 0x4040,
+            idx_offs,
+            size,
+            data_type,
+            flags       : 0
+        }
+    }
+
+    pub(crate) fn data_type(name: &str, type_name: &str, size: u32, data_type: u32) -> DataTypeInfo {
+        DataTypeInfo {
+            name        : name.into(),
+            type_name   : type_name.into(),
+            comment     : String::new(),
+            size,
+            offs        : 0,
+            data_type,
+            flags       : 0,
+            array_infos : Vec::new(),
+            sub_items   : Vec::new(),
+            attributes  : Vec::new(),
+            enum_infos  : Vec::new(),
+            methods     : Vec::new()
+        }
+    }
+
+    pub(crate) fn member(name: &str, type_name: &str, offs: u32, size: u32, data_type: u32) -> DataTypeInfo {
+        DataTypeInfo { offs, ..self::data_type(name, type_name, size, data_type) }
+    }
+
+    /// Symbol table of the given symbols and data types.
+    pub(crate) fn table(symbols: &[SymbolInfo], data_types: &[DataTypeInfo]) -> SymbolTable {
+        let symbols : Vec<u8> = symbols.iter().flat_map(encode_symbol).collect();
+        let data_types : Vec<u8> = data_types.iter().flat_map(encode_data_type).collect();
+        SymbolTable::from_upload(&symbols, &data_types).expect("Invalid upload")
+    }
+
+    fn st_axis() -> DataTypeInfo {
+        let mut info = data_type("ST_Axis", "", 12, ADST_BIGTYPE);
+        info.comment = String::from("Axis");
+        info.sub_items = vec![
+            member("nPos", "DINT", 0, 4, ADST_INT32),
+            member("fVelo", "LREAL", 4, 8, ADST_REAL64)
+        ];
+        info.attributes = vec![(String::from("pack_mode"), String::from("1"))];
+        info
+    }
+
+    fn e_mode() -> DataTypeInfo {
+        let mut info = data_type("E_Mode", "INT", 2, ADST_INT16);
+        info.enum_infos = vec![
+            EnumInfo { name : String::from("Idle"), value : 0 },
+            EnumInfo { name : String::from("Fault"), value : -1 }
+        ];
+        info
+    }
+
+    fn fb_motor() -> DataTypeInfo {
+        let mut info = data_type("FB_Motor", "", 32, ADST_BIGTYPE);
+        info.flags = FLAG_TYPEGUID | FLAG_COPYMASK;
+        info.methods = vec![MethodInfo {
+            name                : String::from("Move"),
+            return_type         : String::from("BOOL"),
+            comment             : String::new(),
+            return_size         : 1,
+            return_align_size   : 1,
+            return_data_type    : ADST_BIT,
+            flags               : 0,
+            params              : vec![MethodParamInfo {
+                name        : String::from("fTarget"),
+                type_name   : String::from("LREAL"),
+                comment     : String::new(),
+                size        : 8,
+                align_size  : 8,
+                data_type   : ADST_REAL64,
+                flags       : PARA_FLAG_IN
+            }]
+        }];
+        info
+    }
+
+    #[test]
+    fn upload_roundtrip() {
+        let symbols = [symbol("MAIN.axis", "ST_Axis", 0x100, 12, ADST_BIGTYPE), symbol("GVL.mode", "E_Mode", 0x200, 2, ADST_INT16)];
+        let data_types = [st_axis(), e_mode(), fb_motor()];
+        let table = table(&symbols, &data_types);
+
+        assert_eq!(table.symbols().count(), 2);
+        assert_eq!(table.symbol("main.AXIS"), Some(&symbols[0]));
+        assert_eq!(table.symbol("GVL.mode"), Some(&symbols[1]));
+
+        let mut fb_motor = fb_motor();
+        fb_motor.flags |= FLAG_METHODINFOS;
+        let mut st_axis = st_axis();
+        st_axis.flags |= FLAG_ATTRIBUTES;
+        let mut e_mode = e_mode();
+        e_mode.flags |= FLAG_ENUMINFOS;
+
+        assert_eq!(table.data_type("st_axis"), Some(&st_axis));
+        assert_eq!(table.data_type("E_MODE"), Some(&e_mode));
+        assert_eq!(table.data_type("FB_Motor"), Some(&fb_motor));
+        assert_eq!(table.data_type("ST_Axis").and_then(|info| info.attribute("PACK_MODE")), Some("1"));
+
+        let params = &table.data_type("FB_Motor").unwrap().methods[0].params;
+        assert!(params[0].is_input() && !params[0].is_output());
+    }
+
+    #[test]
+    fn truncated_upload_is_rejected() {
+        let symbols = encode_symbol(&symbol("MAIN.axis", "ST_Axis", 0x100, 12, ADST_BIGTYPE));
+        let data_types = encode_data_type(&st_axis());
+
+        for len in 1..symbols.len() {
+            assert!(SymbolTable::from_upload(&symbols[..len], &[]).is_err(), "symbols truncated to {} byte", len);
+        }
+        for len in 1..data_types.len() {
+            assert!(SymbolTable::from_upload(&[], &data_types[..len]).is_err(), "data types truncated to {} byte", len);
+        }
+    }
+
+    #[test]
+    fn invalid_entry_length_is_rejected() {
+        let mut symbols = encode_symbol(&symbol("MAIN.axis", "ST_Axis", 0x100, 12, ADST_BIGTYPE));
+
+        for len in [0u32, 3, symbols.len() as u32 + 1, u32::MAX] {
+            symbols[0..4].copy_from_slice(&len.to_le_bytes());
+            assert!(SymbolTable::from_upload(&symbols, &[]).is_err(), "entry length {}", len);
+        }
+    }
+
+    #[test]
+    fn array_type() {
+        let (dims, element) = parse_array_type("ARRAY [1..3, -2..2] OF ST_Axis").unwrap();
+        assert_eq!(dims, [ArrayInfo { lower_bound : 1, elements : 3 }, ArrayInfo { lower_bound : -2, elements : 5 }]);
+        assert_eq!(element, "ST_Axis");
+
+        assert_eq!(parse_array_type("ARRAY [0..9] OF INT").map(|(_, element)| element), Some("INT"));
+        assert_eq!(parse_array_type("ARRAY [3..1] OF INT"), None);
+        assert_eq!(parse_array_type("ARRAY [-2147483648..2147483647] OF BYTE"), None);
+        assert_eq!(parse_array_type("ARRAY [0..9] INT"), None);
+        assert_eq!(parse_array_type("INT"), None);
+    }
+
+    #[test]
+    fn elementary_types() {
+        assert_eq!(elementary_type("string"), Some((ADST_STRING, 81)));
+        assert_eq!(elementary_type("STRING(20)"), Some((ADST_STRING, 21)));
+        assert_eq!(elementary_type("WSTRING(20)"), Some((ADST_WSTRING, 42)));
+        assert_eq!(elementary_type("LREAL"), Some((ADST_REAL64, 8)));
+        assert_eq!(elementary_type("ST_Axis"), None);
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(int_from_le(&[0xFF], true).unwrap(), -1);
+        assert_eq!(int_from_le(&[0xFF], false).unwrap(), 255);
+        assert_eq!(int_from_le(&[0xFF, 0xFF, 0xFF, 0xFF], false).unwrap(), 0xFFFF_FFFF);
+        assert!(int_from_le(&[0; 3], false).is_err());
+    }
+}
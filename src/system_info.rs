@@ -0,0 +1,236 @@
+//! Information about the TwinCAT system of the target.
+use std::fmt;
+use log::debug;
+use crate::{Client, Result, AdsError, AdsErrorCode, DeviceStateInfo, PORT_SYSTEM_SERVICE, IDX_GRP_TC_SYS_INFO, IDX_GRP_TC_HOSTNAME};
+
+const LEN_SYS_INFO_MAX  : usize = 0x4000;
+const LEN_HOSTNAME_MAX  : usize = 256;
+
+/// Version of TwinCAT, e.g. 3.1.4024.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TcVersion {
+    pub version     : u16,
+    pub revision    : u16,
+    pub build       : u16
+}
+
+impl fmt::Display for TcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.version, self.revision, self.build)
+    }
+}
+
+/// TwinCAT system information of a target, returned by [Client::system_info].
+///
+/// Fields which are not reported by the target are empty.
+#[derive(Debug)]
+pub struct SystemInfo {
+    pub twincat_version : TcVersion,
+    pub os_name         : String,
+    pub os_version      : String,
+    /// Target type, e.g. *CB3011-0001-M400*
+    pub platform        : String,
+    /// Hardware model, e.g. *CX9020*
+    pub hardware_model  : String,
+    pub system_id       : Option<String>,
+    pub hostname        : Option<String>,
+    pub ams_net_id      : String,
+    /// Device info of the system service
+    pub device          : DeviceStateInfo
+}
+
+/// Position following `<tag` or `</tag` (`prefix`) in `lower`, skipping elements which only start with `tag`.
+fn find_tag(lower: &str, prefix: &str) -> Option<usize> {
+    let mut from = 0;
+
+    loop {
+        let pos = lower[from..].find(prefix)? + from + prefix.len();
+
+        match lower[pos..].chars().next()? {
+            c if c == '>' || c == '/' || c.is_ascii_whitespace() => return Some(pos),
+            _ => from = pos
+        }
+    }
+}
+
+/// Text of the first element `tag` in `xml` (no CDATA).
+///
+/// The tag is compared case-insensitive, attributes are ignored.
+fn xml_text<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    // ASCII lowercase keeps the byte positions of `xml`
+    let lower = xml.to_ascii_lowercase();
+    let tag = tag.to_ascii_lowercase();
+
+    let pos = find_tag(&lower, &format!("<{}", tag))?;
+    let open_end = lower[pos..].find('>')? + pos;
+
+    if lower[..open_end].ends_with('/') {
+        return Some(""); // <tag/>
+    }
+
+    let start = open_end + 1;
+    let end = find_tag(&lower[start..], &format!("</{}", tag))? + start - tag.len() - 2;
+    Some(xml[start..end].trim())
+}
+
+fn xml_string(xml: &str, tag: &str) -> String {
+    xml_text(xml, tag).map(String::from).unwrap_or_default()
+}
+
+/// Parse the XML of the system service (`<TcSysInfo>`).
+fn parse_sys_info(xml: &str, device: DeviceStateInfo) -> SystemInfo {
+    let version = xml_text(xml, "TargetVersion").and_then(| target | Some(TcVersion {
+        version  : xml_text(target, "Version")?.parse().ok()?,
+        revision : xml_text(target, "Revision")?.parse().ok()?,
+        build    : xml_text(target, "Build")?.parse().ok()?
+    }));
+
+    let os_image = xml_text(xml, "OsImage").unwrap_or_default();
+
+    SystemInfo {
+        // The version of the system service is reported by Read Device Info as well
+        twincat_version : version.unwrap_or(TcVersion { version : device.major.into(), revision : device.minor.into(), build : device.build }),
+        os_name         : xml_string(os_image, "OsName"),
+        os_version      : xml_string(os_image, "OsVersion"),
+        platform        : xml_string(xml, "TargetType"),
+        hardware_model  : xml_text(xml, "Hardware").map(| hw | xml_string(hw, "Model")).unwrap_or_default(),
+        system_id       : xml_text(xml, "SystemId").map(String::from),
+        hostname        : None,
+        ams_net_id      : xml_text(xml, "TargetNetId").or_else(|| xml_text(xml, "NetId")).map(String::from).unwrap_or_default(),
+        device
+    }
+}
+
+impl Client {
+
+    /// Query the TwinCAT system information of the target.
+    ///
+    /// The information is read from the system service (port 10000) of the target, independent of the port of the client.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
+    ///
+    ///     let info = ads_client.system_info().await?;
+    ///     println!("{}: TwinCAT {} on {} {}", info.ams_net_id, info.twincat_version, info.os_name, info.os_version);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn system_info(&self) -> Result<SystemInfo> {
+        let system_service = self.with_port(PORT_SYSTEM_SERVICE);
+
+        let device = system_service.read_device_info().await?;
+
+        let mut buf = vec![0u8; LEN_SYS_INFO_MAX];
+        let len = system_service.read(IDX_GRP_TC_SYS_INFO, 1, &mut buf).await? as usize;
+
+        let xml = std::str::from_utf8(buf.get(..len).unwrap_or(&buf))
+                    .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : String::from("System information is not valid UTF-8")})?;
+
+        let mut info = parse_sys_info(xml.trim_end_matches('\0'), device);
+
+        // Not supported by all versions of TwinCAT
+        let mut hostname = vec![0u8; LEN_HOSTNAME_MAX];
+        match system_service.read(IDX_GRP_TC_HOSTNAME, 0, &mut hostname).await {
+            Ok(len) => {
+                let hostname = String::from_utf8_lossy(hostname.get(..len as usize).unwrap_or(&hostname));
+                info.hostname = Some(String::from(hostname.trim_end_matches('\0')));
+            },
+            Err(e) => debug!("Host name not available: {}", e)
+        }
+
+        if info.ams_net_id.is_empty() {
+            info.ams_net_id = self._dst_addr.map(| byte | byte.to_string()).join(".");
+        }
+
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reply of a CX9020 with TwinCAT 3.1.4024
+    const TC_SYS_INFO : &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TcSysInfo>
+    <TargetType>CB3011-0001-M400</TargetType>
+    <TargetVersion>
+        <Version>3</Version>
+        <Revision>1</Revision>
+        <Build>4024</Build>
+    </TargetVersion>
+    <TargetLevel>
+        <TargetLevel>PLC</TargetLevel>
+    </TargetLevel>
+    <TargetNetId>5.80.201.232.1.1</TargetNetId>
+    <Hardware>
+        <Model>CX9020</Model>
+        <SerialNo>1234567</SerialNo>
+        <CPUVersion>2.3</CPUVersion>
+        <Date>25.6.19</Date>
+        <CPUArchitecture>3</CPUArchitecture>
+    </Hardware>
+    <OsImage>
+        <ImageDevice>CX9020</ImageDevice>
+        <ImageVersion>7.0.0</ImageVersion>
+        <ImageLevel>TC3 PLC</ImageLevel>
+        <OsName>Windows CE</OsName>
+        <OsVersion>7.0</OsVersion>
+    </OsImage>
+    <SystemId>0A1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9</SystemId>
+</TcSysInfo>"#;
+
+    fn device() -> DeviceStateInfo {
+        DeviceStateInfo { major : 3, minor : 1, build : 4022, device_name : String::from("TwinCAT System") }
+    }
+
+    #[test]
+    fn sys_info() {
+        let info = parse_sys_info(TC_SYS_INFO, device());
+
+        assert_eq!(info.twincat_version, TcVersion { version : 3, revision : 1, build : 4024 });
+        assert_eq!(info.os_name, "Windows CE");
+        assert_eq!(info.os_version, "7.0");
+        assert_eq!(info.platform, "CB3011-0001-M400");
+        assert_eq!(info.hardware_model, "CX9020");
+        assert_eq!(info.system_id.as_deref(), Some("0A1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9"));
+        assert_eq!(info.ams_net_id, "5.80.201.232.1.1");
+        assert_eq!(info.hostname, None);
+    }
+
+    #[test]
+    fn missing_elements() {
+        let info = parse_sys_info("<TcSysInfo><TargetType>PC</TargetType></TcSysInfo>", device());
+
+        // Version of the system service
+        assert_eq!(info.twincat_version, TcVersion { version : 3, revision : 1, build : 4022 });
+        assert_eq!((info.os_name.as_str(), info.hardware_model.as_str(), info.ams_net_id.as_str()), ("", "", ""));
+        assert_eq!(info.system_id, None);
+    }
+
+    #[test]
+    fn attributes_and_case() {
+        let xml = r#"<TcSysInfo><OSImage type="TC/BSD"><OSName>TC/BSD</OSName><osversion >13.2</OsVersion></OSImage><SystemId/></TcSysInfo>"#;
+        let info = parse_sys_info(xml, device());
+
+        assert_eq!(info.os_name, "TC/BSD");
+        assert_eq!(info.os_version, "13.2");
+        assert_eq!(info.system_id.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn elements_with_common_prefix() {
+        let xml = "<Info><NetIdList>1.2.3.4.1.1</NetIdList><NetId>5.6.7.8.1.1</NetId><Name>a</Name ></Info>";
+
+        assert_eq!(xml_text(xml, "NetId"), Some("5.6.7.8.1.1"));
+        assert_eq!(xml_text(xml, "name"), Some("a"));
+        assert_eq!(xml_text(xml, "Net"), None);
+        assert_eq!(xml_text("<Name>a", "Name"), None);
+        assert_eq!(xml_text("<Name", "Name"), None);
+    }
+}
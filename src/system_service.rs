@@ -0,0 +1,204 @@
+//! Mode transitions of the TwinCAT system service.
+use std::time::{Duration, Instant};
+use log::{debug, info};
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout};
+use crate::{Client, Result, AdsError, AdsErrorCode, AdsState, StateInfo, ClientEvent, PORT_SYSTEM_SERVICE};
+
+/// Maximum duration of a restart of TwinCAT in Run or Config mode
+const STATE_TRANSITION_TIMEOUT  : Duration = Duration::from_secs(30);
+const STATE_POLL_INTERVAL       : Duration = Duration::from_millis(500);
+
+impl Client {
+
+    /// Wait until [Client::read_state] reports `ads_state`.
+    ///
+    /// Failed requests are ignored (e.g. while TwinCAT restarts and the connection is re-established) 
+    /// and the state is read again.
+    /// If the state is not reached within `max_wait`, an error with the last state is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use ads_client::{ClientBuilder, AdsState, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 851).build().await?;
+    ///
+    ///     ads_client.wait_for_state(AdsState::Run, Duration::from_secs(10)).await?;
+    ///     println!("PLC is running");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_for_state(&self, ads_state: AdsState, max_wait: Duration) -> Result<()> {
+        let mut last : Option<Result<StateInfo>> = None;
+
+        let wait = async {
+            loop {
+                match self.read_state().await {
+                    Ok(state) if state.ads_state == ads_state => return,
+                    Ok(state) => last = Some(Ok(state)),
+                    Err(e) => {
+                        debug!("Failed to read ADS state while waiting for {:?}: {}", ads_state, e);
+                        last = Some(Err(e));
+                    }
+                }
+                sleep(STATE_POLL_INTERVAL).await;
+            }
+        };
+
+        if timeout(max_wait, wait).await.is_ok() {
+            return Ok(());
+        }
+
+        let last = match last {
+            Some(Ok(state)) => format!("last state: {:?}", state.ads_state),
+            Some(Err(e)) => format!("last error: {}", e),
+            None => String::from("state not read")
+        };
+
+        Err(AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT.into(), s_msg : format!("ADS state {:?} not reached within {:?} - {}", ads_state, max_wait, last)})
+    }
+
+    /// Wait until the system service leaves `current`, the connection is lost or the state is not readable.
+    async fn wait_for_restart(&self, current: AdsState, events: &mut broadcast::Receiver<ClientEvent>) {
+        loop {
+            match self.read_state().await {
+                Ok(state) if state.ads_state == current => (),
+                Ok(state) => {
+                    debug!("System service left {:?}: {:?}", current, state.ads_state);
+                    return;
+                },
+                Err(e) => {
+                    debug!("ADS state not readable during restart: {}", e);
+                    return;
+                }
+            }
+
+            tokio::select! {
+                _ = sleep(STATE_POLL_INTERVAL) => (),
+                event = events.recv() => {
+                    if matches!(event, Ok(ClientEvent::Disconnected) | Err(_)) {
+                        debug!("Connection lost during restart");
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Request `request` from the system service and wait until it reports `ads_state`.
+    ///
+    /// The restart is observed first, otherwise a target which is already in `ads_state` 
+    /// would be reported before it restarted.
+    async fn switch_mode(&self, request: AdsState, ads_state: AdsState) -> Result<()> {
+        let system_service = self.with_port(PORT_SYSTEM_SERVICE);
+        let start = Instant::now();
+
+        let current = system_service.read_state().await?.ads_state;
+        let mut events = self.events();
+
+        info!("Switch TwinCAT from {:?} to {:?} mode", current, ads_state);
+        system_service.write_control(&StateInfo { ads_state : request, device_state : 0 }, None).await?;
+
+        if timeout(STATE_TRANSITION_TIMEOUT, system_service.wait_for_restart(current, &mut events)).await.is_err() {
+            return Err(AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT.into(), s_msg : format!("TwinCAT did not leave {:?} within {:?}", current, STATE_TRANSITION_TIMEOUT)});
+        }
+
+        system_service.wait_for_state(ads_state, STATE_TRANSITION_TIMEOUT.saturating_sub(start.elapsed())).await
+    }
+
+    /// Restart TwinCAT in Run mode and wait until the system service reports [AdsState::Run].
+    ///
+    /// The request is sent to the system service (port 10000) of the target, independent of the port of the client.
+    /// Returns an error if Run mode is not reached within 30 s.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use ads_client::{ClientBuilder, Result};
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let ads_client = ClientBuilder::new("5.80.201.232.1.1", 10000).build().await?;
+    ///
+    ///     ads_client.set_config_mode().await?;
+    ///     ads_client.set_run_mode().await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn set_run_mode(&self) -> Result<()> {
+        self.switch_mode(AdsState::Reset, AdsState::Run).await
+    }
+
+    /// Restart TwinCAT in Config mode and wait until the system service reports [AdsState::Config].
+    ///
+    /// The request is sent to the system service (port 10000) of the target, independent of the port of the client.
+    /// Returns an error if Config mode is not reached within 30 s.
+    pub async fn set_config_mode(&self) -> Result<()> {
+        self.switch_mode(AdsState::Reconfig, AdsState::Config).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+    use crate::{AdsCommand, AdsState, PORT_SYSTEM_SERVICE};
+    use crate::tests::{test_client, receive, respond};
+
+    /// Answer the requests to the system service with `states` until the connection is dropped
+    /// (state `None`) or all states are reported.
+    async fn system_service(remote: &mut DuplexStream, states: &mut impl Iterator<Item = Option<AdsState>>) {
+        loop {
+            let (header, _) = receive(remote).await;
+            assert_eq!(&header[12..14], &PORT_SYSTEM_SERVICE.to_le_bytes());
+
+            match u16::from_le_bytes([header[22], header[23]]) {
+                cmd if cmd == AdsCommand::WriteControl as u16 => respond(remote, &header, &[0; 4]).await,
+                cmd if cmd == AdsCommand::ReadState as u16 => match states.next() {
+                    Some(Some(state)) => respond(remote, &header, &[&[0; 4][..], &(state as u16).to_le_bytes(), &[0, 0]].concat()).await,
+                    _ => return
+                },
+                cmd => panic!("Unexpected command {}", cmd)
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_mode_waits_for_restart() {
+        let (client, mut remote, _) = test_client();
+
+        // Still in Run right after the request
+        let mut states = [AdsState::Run, AdsState::Run, AdsState::Init, AdsState::Run].map(Some).into_iter();
+
+        tokio::select! {
+            result = client.set_run_mode() => result.unwrap(),
+            _ = system_service(&mut remote, &mut states) => panic!("Run mode reported too early")
+        }
+        assert!(states.next().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn config_mode_tolerates_reconnect() {
+        let (client, mut remote, reconnect) = test_client();
+
+        // TwinCAT closes the connection while restarting
+        let mut states = [Some(AdsState::Run), Some(AdsState::Run), None].into_iter();
+        let switch = client.set_config_mode();
+        tokio::pin!(switch);
+
+        tokio::select! {
+            result = &mut switch => panic!("Config mode reported before the restart: {:?}", result),
+            _ = system_service(&mut remote, &mut states) => drop(remote)
+        }
+
+        let (local, mut remote) = tokio::io::duplex(1024);
+        reconnect.send(local).unwrap();
+
+        let mut states = [AdsState::Invalid, AdsState::Config].map(Some).into_iter();
+        tokio::select! {
+            result = switch => result.unwrap(),
+            _ = system_service(&mut remote, &mut states) => panic!("Config mode not reported")
+        }
+    }
+}
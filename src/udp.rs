@@ -0,0 +1,273 @@
+//! AMS UDP service of the TwinCAT router (port 48899), used for route management and discovery.
+//!
+//! ```text
+//! Magic [4] | Invoke ID [4] | Service [4] | AmsNetId [6] | Port [2] | Items [4] | Item ...
+//! Item:  Tag [2] | Length [2] | Data [Length]
+//! ```
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::net::{UdpSocket, lookup_host};
+use crate::{Result, AdsError, AdsErrorCode, AmsNetId};
+
+pub(crate) const UDP_PORT       : u16 = 48899;
+const UDP_MAGIC                 : u32 = 0x7114_6603;
+const UDP_REPLY                 : u32 = 0x8000_0000; // Flag of the service ID in replies
+const LEN_UDP_HEADER            : usize = 24;
+const LEN_UDP_ITEM_HEADER       : usize = 4;
+pub(crate) const LEN_UDP_MAX    : usize = 2048;
+
+// Services
+pub(crate) const SERVICE_IDENTIFY   : u32 = 1;
+pub(crate) const SERVICE_ADD_ROUTE  : u32 = 6;
+
+// Tags of the items
+pub(crate) const TAG_STATUS         : u16 = 1;
+pub(crate) const TAG_PASSWORD       : u16 = 2;
+pub(crate) const TAG_TC_VERSION     : u16 = 3;
+pub(crate) const TAG_OS_VERSION     : u16 = 4;
+pub(crate) const TAG_COMPUTER_NAME  : u16 = 5;
+pub(crate) const TAG_NET_ID         : u16 = 7;
+pub(crate) const TAG_ROUTE_NAME     : u16 = 12;
+pub(crate) const TAG_USER_NAME      : u16 = 13;
+
+static INVOKE_ID : AtomicU32 = AtomicU32::new(1);
+
+fn udp_error(s_msg: &str) -> AdsError {
+    AdsError{n_error : AdsErrorCode::ADSERR_DEVICE_INVALIDDATA.into(), s_msg : format!("Invalid UDP message: {}", s_msg)}
+}
+
+/// Parse an AmsNetId like `5.80.201.232.1.1`.
+pub(crate) fn parse_net_id(net_id: &str) -> Result<AmsNetId> {
+    let bytes = net_id.split('.').map(| byte | byte.parse::<u8>()).collect::<std::result::Result<Vec<u8>, _>>()?;
+
+    bytes.try_into()
+        .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg : format!("Invalid AmsNetId: {}", net_id)})
+}
+
+pub(crate) fn format_net_id(net_id: &AmsNetId) -> String {
+    net_id.map(| byte | byte.to_string()).join(".")
+}
+
+/// Resolve `host` or `host:port`, the port defaults to [UDP_PORT].
+pub(crate) async fn resolve(target: &str) -> Result<SocketAddr> {
+    if let Ok(addr) = target.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+
+    let addr = match target.contains(':') {
+        true  => lookup_host(target).await?.next(),
+        false => lookup_host((target, UDP_PORT)).await?.next()
+    };
+
+    addr.ok_or_else(|| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_INVALIDPARM.into(), s_msg : format!("Failed to resolve {}", target)})
+}
+
+/// Message of the AMS UDP service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UdpMessage {
+    pub service     : u32,
+    pub invoke_id   : u32,
+    pub net_id      : AmsNetId,
+    pub port        : u16,
+    pub items       : Vec<(u16, Vec<u8>)>
+}
+
+impl UdpMessage {
+    pub fn new(service: u32, net_id: AmsNetId) -> Self {
+        UdpMessage { service, invoke_id : INVOKE_ID.fetch_add(1, Ordering::Relaxed), net_id, port : 0, items : Vec::new() }
+    }
+
+    pub fn add_bytes(&mut self, tag: u16, data: &[u8]) -> &mut Self {
+        self.items.push((tag, data.to_vec()));
+        self
+    }
+
+    /// Strings are NUL terminated.
+    pub fn add_str(&mut self, tag: u16, data: &str) -> &mut Self {
+        let mut bytes = data.as_bytes().to_vec();
+        bytes.push(0);
+        self.items.push((tag, bytes));
+        self
+    }
+
+    pub fn get(&self, tag: u16) -> Option<&[u8]> {
+        self.items.iter().find(| (item_tag, _) | *item_tag == tag).map(| (_, data) | &data[..])
+    }
+
+    pub fn get_str(&self, tag: u16) -> Option<String> {
+        self.get(tag).map(| data | {
+            let data = data.split(| byte | *byte == 0).next().unwrap_or_default();
+            String::from_utf8_lossy(data).into_owned()
+        })
+    }
+
+    pub fn get_u32(&self, tag: u16) -> Option<u32> {
+        self.get(tag).and_then(| data | Some(u32::from_le_bytes(data.get(0..4)?.try_into().ok()?)))
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(LEN_UDP_HEADER + self.items.iter().map(| (_, data) | LEN_UDP_ITEM_HEADER + data.len()).sum::<usize>());
+
+        buf.extend(UDP_MAGIC.to_le_bytes());
+        buf.extend(self.invoke_id.to_le_bytes());
+        buf.extend(self.service.to_le_bytes());
+        buf.extend(self.net_id);
+        buf.extend(self.port.to_le_bytes());
+        buf.extend(u32::try_from(self.items.len())?.to_le_bytes());
+
+        for (tag, data) in &self.items {
+            buf.extend(tag.to_le_bytes());
+            buf.extend(u16::try_from(data.len())?.to_le_bytes());
+            buf.extend(data);
+        }
+        Ok(buf)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let header = data.get(..LEN_UDP_HEADER).ok_or_else(|| udp_error("missing header"))?;
+
+        if u32::from_le_bytes(header[0..4].try_into()?) != UDP_MAGIC {
+            return Err(udp_error("invalid magic number"));
+        }
+
+        let n_items = u32::from_le_bytes(header[20..24].try_into()?);
+        let mut items = Vec::new();
+        let mut pos = LEN_UDP_HEADER;
+
+        for _ in 0..n_items {
+            let item_header = data.get(pos..pos + LEN_UDP_ITEM_HEADER).ok_or_else(|| udp_error("item header exceeds the message"))?;
+            let tag = u16::from_le_bytes(item_header[0..2].try_into()?);
+            let len = u16::from_le_bytes(item_header[2..4].try_into()?) as usize;
+            pos += LEN_UDP_ITEM_HEADER;
+
+            let item = data.get(pos..pos + len).ok_or_else(|| udp_error("item exceeds the message"))?;
+            items.push((tag, item.to_vec()));
+            pos += len;
+        }
+
+        Ok(UdpMessage {
+            service     : u32::from_le_bytes(header[8..12].try_into()?),
+            invoke_id   : u32::from_le_bytes(header[4..8].try_into()?),
+            net_id      : header[12..18].try_into()?,
+            port        : u16::from_le_bytes(header[18..20].try_into()?),
+            items
+        })
+    }
+
+    /// Check whether `reply` answers this request.
+    pub fn is_reply(&self, reply: &UdpMessage) -> bool {
+        reply.service == self.service | UDP_REPLY && reply.invoke_id == self.invoke_id
+    }
+}
+
+/// Send `request` to `target` and wait for the reply.
+pub(crate) async fn request(target: SocketAddr, request: &UdpMessage, timeout: Duration) -> Result<UdpMessage> {
+    let local : SocketAddr = match target {
+        SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+        SocketAddr::V6(_) => ([0u16; 8], 0).into()
+    };
+
+    let socket = UdpSocket::bind(local).await?;
+    socket.send_to(&request.encode()?, target).await?;
+
+    let receive = async {
+        let mut buf = [0u8; LEN_UDP_MAX];
+
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await?;
+
+            // Ignore unrelated datagrams
+            match UdpMessage::parse(&buf[..len]) {
+                Ok(reply) if from == target && request.is_reply(&reply) => return Ok(reply),
+                _ => continue
+            }
+        }
+    };
+
+    tokio::time::timeout(timeout, receive).await
+        .map_err(|_| AdsError{n_error : AdsErrorCode::ADSERR_CLIENT_SYNCTIMEOUT.into(), s_msg : format!("No reply from {} within {:?}", target, timeout)})?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> UdpMessage {
+        let mut message = UdpMessage::new(SERVICE_ADD_ROUTE, [192, 168, 1, 20, 1, 1]);
+        message.port = 10000;
+        message.add_str(TAG_ROUTE_NAME, "route")
+               .add_bytes(TAG_NET_ID, &[192, 168, 1, 20, 1, 1])
+               .add_bytes(TAG_STATUS, &0x704u32.to_le_bytes())
+               .add_bytes(TAG_PASSWORD, &[]);
+        message
+    }
+
+    #[test]
+    fn roundtrip() {
+        let message = message();
+        let data = message.encode().unwrap();
+
+        assert_eq!(&data[0..4], &UDP_MAGIC.to_le_bytes());
+        assert_eq!(&data[18..20], &10000u16.to_le_bytes());
+
+        let parsed = UdpMessage::parse(&data).unwrap();
+        assert_eq!(parsed, message);
+        assert_eq!(parsed.get_str(TAG_ROUTE_NAME).as_deref(), Some("route"));
+        assert_eq!(parsed.get_u32(TAG_STATUS), Some(0x704));
+        assert_eq!(parsed.get_u32(TAG_PASSWORD), None);
+        assert_eq!(parsed.get(TAG_USER_NAME), None);
+    }
+
+    #[test]
+    fn reply() {
+        let request = message();
+        let mut reply = request.clone();
+        assert!(!request.is_reply(&reply));
+
+        reply.service |= UDP_REPLY;
+        assert!(request.is_reply(&reply));
+
+        reply.invoke_id += 1;
+        assert!(!request.is_reply(&reply));
+    }
+
+    #[test]
+    fn invalid_message_is_rejected() {
+        let data = message().encode().unwrap();
+
+        for len in 0..data.len() {
+            assert!(UdpMessage::parse(&data[..len]).is_err(), "message truncated to {} byte", len);
+        }
+
+        let mut bad_magic = data.clone();
+        bad_magic[0] ^= 0xFF;
+        assert!(UdpMessage::parse(&bad_magic).is_err());
+
+        // Item length exceeds the message
+        let mut oversized = data.clone();
+        oversized[26..28].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(UdpMessage::parse(&oversized).is_err());
+
+        let mut items = data;
+        items[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(UdpMessage::parse(&items).is_err());
+    }
+
+    #[test]
+    fn oversized_item_is_not_encoded() {
+        let mut message = message();
+        message.add_bytes(TAG_COMPUTER_NAME, &vec![0; 0x10000]);
+        assert!(message.encode().is_err());
+    }
+
+    #[test]
+    fn net_id() {
+        assert_eq!(parse_net_id("192.168.1.20.1.1").unwrap(), [192, 168, 1, 20, 1, 1]);
+        assert_eq!(format_net_id(&[192, 168, 1, 20, 1, 1]), "192.168.1.20.1.1");
+
+        for net_id in ["192.168.1.20.1", "192.168.1.20.1.1.1", "192.168.1.256.1.1", "", "a.b.c.d.e.f"] {
+            assert!(parse_net_id(net_id).is_err(), "{}", net_id);
+        }
+    }
+}